@@ -0,0 +1,133 @@
+use clap::{Parser, Subcommand};
+
+use crate::commands::*;
+use crate::error::Result;
+use crate::settings::Settings;
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  success
+  1  other errors, e.g. malformed input or a message too long for the key
+  2  invalid arguments or input, e.g. a binary message printed instead of written with -f
+  3  reading or writing a file failed
+  4  the key could not be read or is invalid
+  5  decryption failed, e.g. wrong key or modified message
+  6  verification of a signature, token or certificate failed";
+
+/// RSA with arbitrary precision: generate keys, encrypt, decrypt, sign and convert them
+#[derive(Debug, Parser)]
+#[command(name = "rsa_arbitray_precision", version, propagate_version = true, arg_required_else_help = true, after_help = EXIT_CODES)]
+pub struct Cli {
+    /// config file with the defaults of the commands, instead of $XDG_CONFIG_HOME/rust_rsa/config.toml
+    #[arg(long = "config", value_name = "FILE", global = true)]
+    pub config_file: Option<String>,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+impl Cli {
+    /// Runs the command with the settings of the config file and environment
+    pub fn run(self) -> Result<()> {
+        let settings = Settings::load(self.config_file.as_deref())?;
+        self.command.run(&settings)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Measure how long key generation takes for several key sizes and numbers of threads
+    Benchmark(benchmark::BenchmarkConfig),
+    /// Generate a key pair
+    Generate(generate::GenerateConfig),
+    /// Encrypt a message for one key, or for several recipients with -r
+    Encrypt(crypto::EncryptArgs),
+    /// Decrypt a message, containers name the key and padding they were encrypted with
+    Decrypt(crypto::DecryptArgs),
+    /// Sign a message
+    Sign(sign::SignConfig),
+    /// Verify a hex signature or a CMS SignedData
+    Verify(sign::VerifyConfig),
+    /// Check if a key is consistent and print a report of all checks
+    CheckKey(check_key::CheckKeyConfig),
+    /// Show size, exponent and fingerprints of a key
+    Info(info::InfoConfig),
+    /// Write the public key belonging to a private key
+    ExportPublic(export_public::ExportPublicConfig),
+    /// Convert a private or public key to another format
+    Convert(convert::ConvertConfig),
+    /// Build a JWK Set from the public parts of several keys
+    Jwks(jwks::JwksConfig),
+    /// Sign or verify a JSON Web Token
+    #[command(subcommand, arg_required_else_help = true)]
+    Jwt(jwt::JwtCommand),
+    /// Create a self-signed certificate or a certification request
+    Cert(cert::CertConfig),
+    /// Show a certificate and verify its signature or chain
+    VerifyCert(verify_cert::VerifyCertConfig),
+    /// Print a completion script for bash, zsh, fish, elvish or powershell
+    Completions(completions::CompletionsConfig),
+    /// Print the man page of a command, or write the pages of all commands to a directory
+    Man(man::ManConfig),
+    /// Show the settings of the config file and environment
+    #[command(subcommand, arg_required_else_help = true)]
+    Config(config::ConfigCommand),
+    /// Manage the keys of the keyring
+    #[command(subcommand, arg_required_else_help = true)]
+    Keys(keys::KeysCommand),
+}
+
+impl Command {
+    pub fn run(self, settings: &Settings) -> Result<()> {
+        match self {
+            Command::Benchmark(config) => benchmark::run(config),
+            Command::Generate(config) => generate::run(config, settings),
+            Command::Encrypt(args) => crypto::encrypt::run(crypto::CryptoConfig::encrypt(args, settings)?),
+            Command::Decrypt(args) => crypto::decrypt::run(crypto::CryptoConfig::decrypt(args, settings)?),
+            Command::Sign(config) => sign::run_sign(config, settings),
+            Command::Verify(config) => sign::run_verify(config, settings),
+            Command::CheckKey(config) => check_key::run(config),
+            Command::Info(config) => info::run(config),
+            Command::ExportPublic(config) => export_public::run(config, settings),
+            Command::Convert(config) => convert::run(config, settings),
+            Command::Jwks(config) => jwks::run(config),
+            Command::Jwt(jwt::JwtCommand::Sign(config)) => jwt::run_sign(config),
+            Command::Jwt(jwt::JwtCommand::Verify(config)) => jwt::run_verify(config),
+            Command::Cert(config) => cert::run(config, settings),
+            Command::VerifyCert(config) => verify_cert::run(config),
+            Command::Completions(config) => completions::run(config),
+            Command::Man(config) => man::run(config),
+            Command::Config(command) => config::run(command, settings),
+            Command::Keys(command) => keys::run(command, settings),
+        }
+    }
+}
+
+/// Parses the arguments after the program name, for the tests of the commands
+#[cfg(test)]
+pub(crate) fn parse(args: &[&str]) -> std::result::Result<Command, clap::Error> {
+    Cli::try_parse_from(std::iter::once("rsa").chain(args.iter().copied())).map(|cli| cli.command)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+    use clap::error::ErrorKind;
+
+    use super::{parse, Cli};
+
+    #[test]
+    fn test_definitions() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse(&["generate", "--henlo"]).unwrap_err().kind(), ErrorKind::UnknownArgument);
+        assert_eq!(parse(&["check-key"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        assert_eq!(parse(&["generate", "-s"]).unwrap_err().kind(), ErrorKind::InvalidValue);
+        assert_eq!(parse(&["generate", "-h"]).unwrap_err().kind(), ErrorKind::DisplayHelp);
+        assert_eq!(parse(&["jwt"]).unwrap_err().kind(), ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand);
+        assert_eq!(parse(&["--version"]).unwrap_err().kind(), ErrorKind::DisplayVersion);
+        assert_eq!(parse(&["sign", "--help"]).unwrap_err().kind(), ErrorKind::DisplayHelp);
+    }
+}
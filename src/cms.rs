@@ -0,0 +1,584 @@
+//! Cryptographic Message Syntax (RFC 5652) EnvelopedData and SignedData with RSA keys.
+//! Content is encrypted with AES-256-CBC, the content key is transported with RSAES-OAEP (RFC 4055)
+//! or RSAES-PKCS1-v1_5. Signatures use signed attributes, so the signature covers the content type,
+//! the signing time and the digest of the content.
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use chrono::Utc;
+use rand::RngCore;
+use rug::Integer;
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use crate::formats::der::{self, DerReader};
+use crate::formats::{pem, pkcs1, spki};
+use crate::hash::HashAlgorithm;
+use crate::key_gen::{RsaKey, RsaPrivateKey, RsaPublicKey};
+use crate::padding::Padding;
+use crate::signature::SignatureScheme;
+use crate::x509::Certificate;
+
+pub const OID_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 1];
+pub const OID_SIGNED_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 2];
+pub const OID_ENVELOPED_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 3];
+pub const OID_RSAES_OAEP: &[u64] = &[1, 2, 840, 113549, 1, 1, 7];
+pub const OID_AES128_CBC: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 1, 2];
+pub const OID_AES192_CBC: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 1, 22];
+pub const OID_AES256_CBC: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 1, 42];
+pub const OID_CONTENT_TYPE: &[u64] = &[1, 2, 840, 113549, 1, 9, 3];
+pub const OID_MESSAGE_DIGEST: &[u64] = &[1, 2, 840, 113549, 1, 9, 4];
+pub const OID_SIGNING_TIME: &[u64] = &[1, 2, 840, 113549, 1, 9, 5];
+
+pub const PEM_LABEL: &str = "CMS";
+
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+
+/// Decrypts AES-CBC content, the key length selects AES-128, AES-192 or AES-256
+fn aes_cbc_decrypt(key: &[u8], iv: &[u8; 16], encrypted: &[u8]) -> Result<Vec<u8>> {
+    let decrypted = match key.len() {
+        16 => cbc::Decryptor::<aes::Aes128>::new(key.into(), iv.into()).decrypt_padded_vec_mut::<Pkcs7>(encrypted),
+        24 => cbc::Decryptor::<aes::Aes192>::new(key.into(), iv.into()).decrypt_padded_vec_mut::<Pkcs7>(encrypted),
+        32 => cbc::Decryptor::<aes::Aes256>::new(key.into(), iv.into()).decrypt_padded_vec_mut::<Pkcs7>(encrypted),
+        _ => return Err(Error::Padding("decryption error".to_string())),
+    };
+    decrypted.map_err(|_| Error::Padding("decryption error".to_string()))
+}
+
+/// Identifies the key of a recipient or signer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identifier {
+    /// Issuer name (DER) and serial number of the certificate of the key
+    IssuerAndSerial { issuer: Vec<u8>, serial: Integer },
+    /// Key identifier, for keys without a certificate
+    SubjectKeyId(Vec<u8>),
+}
+
+impl Identifier {
+    pub fn from_certificate(certificate: &Certificate) -> Self {
+        Identifier::IssuerAndSerial { issuer: certificate.issuer_der().to_vec(), serial: certificate.serial().clone() }
+    }
+
+    /// The first 160 bits of the SHA-256 hash of the public key, method 1 of RFC 7093
+    pub fn from_key<K: RsaKey>(key: &K) -> Self {
+        let mut id = HashAlgorithm::Sha256.digest(&pkcs1::public_key_to_der(key));
+        id.truncate(20);
+        Identifier::SubjectKeyId(id)
+    }
+
+    fn to_der(&self) -> Vec<u8> {
+        match self {
+            Identifier::IssuerAndSerial { issuer, serial } => der::sequence(&[issuer.clone(), der::integer(serial)]),
+            // [0] IMPLICIT SubjectKeyIdentifier
+            Identifier::SubjectKeyId(id) => der::tlv(0x80, id),
+        }
+    }
+
+    fn read(reader: &mut DerReader) -> Result<Self> {
+        match reader.peek_tag() {
+            Some(0x80) => Ok(Identifier::SubjectKeyId(reader.read(0x80)?.to_vec())),
+            _ => {
+                let mut id = reader.read_sequence()?;
+                let issuer = id.read_raw()?.to_vec();
+                let serial = id.read_integer()?;
+                id.finish()?;
+                Ok(Identifier::IssuerAndSerial { issuer, serial })
+            },
+        }
+    }
+
+    fn is_key_id(&self) -> bool {
+        matches!(self, Identifier::SubjectKeyId(_))
+    }
+}
+
+/// Public key of a recipient of an EnvelopedData
+#[derive(Debug, Clone)]
+pub struct Recipient {
+    pub id: Identifier,
+    pub key: RsaPublicKey,
+}
+
+impl Recipient {
+    pub fn from_certificate(certificate: &Certificate) -> Self {
+        Recipient { id: Identifier::from_certificate(certificate), key: certificate.public_key().clone() }
+    }
+
+    pub fn from_key(key: RsaPublicKey) -> Self {
+        Recipient { id: Identifier::from_key(&key), key }
+    }
+}
+
+/// ContentInfo wrapping `content` of type `content_type`
+fn content_info(content_type: &[u64], content: &[u8]) -> Vec<u8> {
+    der::sequence(&[der::oid(content_type), der::context(0, content)])
+}
+
+/// Parses DER or a PEM block labelled CMS or PKCS7 and returns the content of the ContentInfo of `content_type`
+fn read_content_info<'a>(data: &'a [u8], pem_buffer: &'a mut Vec<u8>, content_type: &[u64]) -> Result<DerReader<'a>> {
+    let data = match pem::is_pem(data) {
+        true => {
+            let (label, der) = pem::decode(&String::from_utf8_lossy(data))?;
+            if label != PEM_LABEL && label != "PKCS7" {
+                return Err(Error::Encoding(format!("expected a CMS PEM block, found '{}'", label)));
+            }
+            *pem_buffer = der;
+            &pem_buffer[..]
+        },
+        false => data,
+    };
+    let mut outer = DerReader::new(data);
+    let mut info = outer.read_sequence()?;
+    outer.finish()?;
+    if info.read_oid()? != content_type {
+        return Err(Error::Encoding("unexpected CMS content type".to_string()));
+    }
+    let mut content = DerReader::new(info.read(0xa0)?);
+    info.finish()?;
+    let inner = content.read_sequence()?;
+    content.finish()?;
+    Ok(inner)
+}
+
+/// AlgorithmIdentifier of the key transport, RFC 4055 section 4.1 for the OAEP parameters
+fn key_transport_algorithm(padding: Padding) -> Result<Vec<u8>> {
+    match padding {
+        Padding::Pkcs1 => Ok(der::sequence(&[der::oid(der::OID_RSA_ENCRYPTION), der::null()])),
+        Padding::Oaep(hash) => {
+            let mgf = der::sequence(&[der::oid(der::OID_MGF1), hash.algorithm_identifier()]);
+            let params = der::sequence(&[der::context(0, &hash.algorithm_identifier()), der::context(1, &mgf)]);
+            Ok(der::sequence(&[der::oid(OID_RSAES_OAEP), params]))
+        },
+        Padding::None => Err(Error::InvalidArgs("Error, CMS needs pkcs1 or oaep padding".to_string())),
+    }
+}
+
+fn read_hash_algorithm(data: &[u8]) -> Result<HashAlgorithm> {
+    let mut outer = DerReader::new(data);
+    let mut algorithm = outer.read_sequence()?;
+    outer.finish()?;
+    let oid = algorithm.read_oid()?;
+    if !algorithm.is_empty() {
+        algorithm.read_null()?;
+    }
+    algorithm.finish()?;
+    [HashAlgorithm::Sha256, HashAlgorithm::Sha384, HashAlgorithm::Sha512]
+        .into_iter()
+        .find(|hash| hash.oid() == oid.as_slice())
+        .ok_or_else(|| Error::Encoding("unsupported hash algorithm, only SHA-2 is supported".to_string()))
+}
+
+fn read_key_transport_algorithm(data: &[u8]) -> Result<Padding> {
+    let mut outer = DerReader::new(data);
+    let mut algorithm = outer.read_sequence()?;
+    outer.finish()?;
+    let oid = algorithm.read_oid()?;
+    if oid == der::OID_RSA_ENCRYPTION {
+        return Ok(Padding::Pkcs1);
+    }
+    if oid != OID_RSAES_OAEP {
+        return Err(Error::Encoding("unsupported key transport algorithm".to_string()));
+    }
+    let mut params = algorithm.read_sequence()?;
+    algorithm.finish()?;
+    // the default is SHA-1, which is not supported
+    let hash = read_hash_algorithm(params.read_optional(0xa0)?.ok_or_else(|| Error::Encoding("OAEP with SHA-1 is not supported".to_string()))?)?;
+    if let Some(mgf) = params.read_optional(0xa1)? {
+        let mut outer = DerReader::new(mgf);
+        let mut mgf = outer.read_sequence()?;
+        if mgf.read_oid()? != der::OID_MGF1 || read_hash_algorithm(mgf.read_raw()?)? != hash {
+            return Err(Error::Encoding("OAEP with a different mask generation function is not supported".to_string()));
+        }
+    }
+    if params.read_optional(0xa2)?.is_some() {
+        return Err(Error::Encoding("OAEP labels are not supported".to_string()));
+    }
+    params.finish()?;
+    Ok(Padding::Oaep(hash))
+}
+
+/// EnvelopedData with `content` encrypted for every recipient, wrapped in a ContentInfo
+pub fn encrypt(content: &[u8], recipients: &[Recipient], padding: Padding) -> Result<Vec<u8>> {
+    if recipients.is_empty() {
+        return Err(Error::InvalidArgs("Error, at least one recipient is needed".to_string()));
+    }
+    let mut content_key = Zeroizing::new([0u8; 32]);
+    let mut iv = [0u8; 16];
+    rand::thread_rng().fill_bytes(content_key.as_mut_slice());
+    rand::thread_rng().fill_bytes(&mut iv);
+    let encrypted = Aes256CbcEnc::new(content_key.as_slice().into(), &iv.into()).encrypt_padded_vec_mut::<Pkcs7>(content);
+
+    let algorithm = key_transport_algorithm(padding)?;
+    let recipient_infos = recipients.iter()
+        .map(|recipient| {
+            let version = if recipient.id.is_key_id() { 2 } else { 0 };
+            Ok(der::sequence(&[
+                der::integer(&Integer::from(version)),
+                recipient.id.to_der(),
+                algorithm.clone(),
+                der::octet_string(&padding.encrypt(&recipient.key, content_key.as_slice())?),
+            ]))
+        })
+        .collect::<Result<Vec<Vec<u8>>>>()?;
+    // version 2 as soon as one recipient is identified by a key identifier
+    let version = if recipients.iter().any(|r| r.id.is_key_id()) { 2 } else { 0 };
+
+    let encrypted_content_info = der::sequence(&[
+        der::oid(OID_DATA),
+        der::sequence(&[der::oid(OID_AES256_CBC), der::octet_string(&iv)]),
+        // [0] IMPLICIT OCTET STRING
+        der::tlv(0x80, &encrypted),
+    ]);
+    let enveloped = der::sequence(&[der::integer(&Integer::from(version)), der::set(&recipient_infos), encrypted_content_info]);
+    Ok(content_info(OID_ENVELOPED_DATA, &enveloped))
+}
+
+/// Decrypts an EnvelopedData, trying every key transport recipient with `key`
+pub fn decrypt(data: &[u8], key: &RsaPrivateKey) -> Result<Vec<u8>> {
+    let mut buffer = vec![];
+    let mut enveloped = read_content_info(data, &mut buffer, OID_ENVELOPED_DATA)?;
+    enveloped.read_integer()?;
+    // originatorInfo is not used by key transport
+    enveloped.read_optional(0xa0)?;
+
+    let own_id = Identifier::from_key(key);
+    let mut recipient_infos = DerReader::new(enveloped.read(der::TAG_SET)?);
+    let mut candidates = vec![];
+    while !recipient_infos.is_empty() {
+        // other recipient types like key agreement are tagged and skipped
+        if recipient_infos.peek_tag() != Some(der::TAG_SEQUENCE) {
+            recipient_infos.read_tlv()?;
+            continue;
+        }
+        let mut info = recipient_infos.read_sequence()?;
+        info.read_integer()?;
+        let id = Identifier::read(&mut info)?;
+        let padding = read_key_transport_algorithm(info.read_raw()?);
+        let encrypted_key = info.read_octet_string()?;
+        info.finish()?;
+        // recipients with our key identifier first, certificates can not be matched without the certificate
+        match id == own_id {
+            true => candidates.insert(0, (padding, encrypted_key)),
+            false => candidates.push((padding, encrypted_key)),
+        }
+    }
+
+    let mut encrypted_content_info = enveloped.read_sequence()?;
+    if encrypted_content_info.read_oid()? != OID_DATA {
+        return Err(Error::Encoding("unsupported content type, only data is supported".to_string()));
+    }
+    let mut algorithm = encrypted_content_info.read_sequence()?;
+    let key_len = match algorithm.read_oid()? {
+        oid if oid == OID_AES128_CBC => 16,
+        oid if oid == OID_AES192_CBC => 24,
+        oid if oid == OID_AES256_CBC => 32,
+        _ => return Err(Error::Encoding("unsupported content encryption algorithm, only AES-CBC is supported".to_string())),
+    };
+    let iv: [u8; 16] = algorithm.read_octet_string()?.try_into().map_err(|_| Error::Encoding("invalid IV".to_string()))?;
+    algorithm.finish()?;
+    let encrypted = encrypted_content_info.read(0x80)?;
+    encrypted_content_info.finish()?;
+
+    // the encrypted key of another recipient can pass the PKCS#1 v1.5 check by chance, then the content
+    // doesn't decrypt and the remaining candidates are tried
+    let mut error = Error::Padding("the key is not a recipient of the message".to_string());
+    for (padding, encrypted_key) in candidates {
+        let content_key = match padding.and_then(|padding| padding.decrypt(key, encrypted_key)) {
+            Ok(content_key) => Zeroizing::new(content_key),
+            Err(_) => continue,
+        };
+        if content_key.len() != key_len {
+            continue;
+        }
+        match aes_cbc_decrypt(&content_key, &iv, encrypted) {
+            Ok(content) => return Ok(content),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// Key and identity of the signer of a SignedData
+pub struct Signer<'a> {
+    pub key: &'a RsaPrivateKey,
+    pub scheme: SignatureScheme,
+    /// Included in the SignedData and used to identify the signer, otherwise a key identifier is used
+    pub certificate: Option<&'a Certificate>,
+}
+
+/// The DER SET OF signed attributes, which is what the signature is calculated over
+fn signed_attributes(digest: &[u8]) -> Vec<u8> {
+    let attribute = |oid: &[u64], value: Vec<u8>| der::sequence(&[der::oid(oid), der::set(&[value])]);
+    der::set(&[
+        attribute(OID_CONTENT_TYPE, der::oid(OID_DATA)),
+        attribute(OID_SIGNING_TIME, der::time(&Utc::now())),
+        attribute(OID_MESSAGE_DIGEST, der::octet_string(digest)),
+    ])
+}
+
+/// SignedData over `content` wrapped in a ContentInfo, without the content if `detached` is set
+pub fn sign(content: &[u8], signer: &Signer, detached: bool) -> Result<Vec<u8>> {
+    let hash = signer.scheme.hash();
+    let attributes = signed_attributes(&hash.digest(content));
+    let signature = signer.scheme.sign(signer.key, &attributes)?;
+    let id = match signer.certificate {
+        Some(certificate) => {
+            if certificate.public_key().modulus() != signer.key.modulus() {
+                return Err(Error::InvalidArgs("Error, the certificate does not belong to the key".to_string()));
+            }
+            Identifier::from_certificate(certificate)
+        },
+        None => Identifier::from_key(signer.key),
+    };
+    let signature_algorithm = match signer.scheme {
+        // rsaEncryption is what most implementations write for PKCS#1 v1.5, RFC 3370 section 3.2
+        SignatureScheme::Pkcs1(_) => spki::rsa_algorithm_identifier(),
+        SignatureScheme::Pss(_) => signer.scheme.algorithm_identifier(),
+    };
+
+    // the signed attributes are [0] IMPLICIT, but signed with the SET OF tag
+    let mut implicit_attributes = attributes;
+    implicit_attributes[0] = 0xa0;
+    let version = if id.is_key_id() { 3 } else { 1 };
+    let signer_info = der::sequence(&[
+        der::integer(&Integer::from(version)),
+        id.to_der(),
+        hash.algorithm_identifier(),
+        implicit_attributes,
+        signature_algorithm,
+        der::octet_string(&signature),
+    ]);
+
+    let mut encapsulated = vec![der::oid(OID_DATA)];
+    if !detached {
+        encapsulated.push(der::context(0, &der::octet_string(content)));
+    }
+    let mut signed = vec![
+        der::integer(&Integer::from(version)),
+        der::set(&[hash.algorithm_identifier()]),
+        der::sequence(&encapsulated),
+    ];
+    if let Some(certificate) = signer.certificate {
+        // [0] IMPLICIT CertificateSet
+        signed.push(der::context(0, certificate.to_der()));
+    }
+    signed.push(der::set(&[signer_info]));
+    Ok(content_info(OID_SIGNED_DATA, &der::sequence(&signed)))
+}
+
+/// Content and certificates of a verified SignedData
+#[derive(Debug)]
+pub struct Verified {
+    pub content: Vec<u8>,
+    /// included certificate whose key verified the signature, only set if no key was given
+    pub signer: Option<Certificate>,
+    /// all included certificates, possible intermediates for the signer
+    pub certificates: Vec<Certificate>,
+}
+
+/// Verifies the first signer of a SignedData. `detached_content` is needed if the content is not
+/// included, otherwise it has to match the included content. Without `key`, the included
+/// certificate of the signer is used, which says nothing about who the signer is until the
+/// certificate is verified, too.
+pub fn verify(data: &[u8], detached_content: Option<&[u8]>, key: Option<&RsaPublicKey>) -> Result<Verified> {
+    let mut buffer = vec![];
+    let mut signed = read_content_info(data, &mut buffer, OID_SIGNED_DATA)?;
+    signed.read_integer()?;
+    signed.read(der::TAG_SET)?;
+
+    let mut encapsulated = signed.read_sequence()?;
+    if encapsulated.read_oid()? != OID_DATA {
+        return Err(Error::Encoding("unsupported content type, only data is supported".to_string()));
+    }
+    let included = match encapsulated.read_optional(0xa0)? {
+        Some(content) => {
+            let mut content = DerReader::new(content);
+            let octets = content.read_octet_string()?;
+            content.finish()?;
+            Some(octets)
+        },
+        None => None,
+    };
+    encapsulated.finish()?;
+    let content = match (included, detached_content) {
+        (Some(included), Some(detached)) if included != detached => {
+            return Err(Error::Verification("the given content does not match the included content".to_string()));
+        },
+        (Some(content), _) | (None, Some(content)) => content,
+        (None, None) => return Err(Error::InvalidArgs("Error, the signature is detached, the signed content is needed".to_string())),
+    };
+
+    let mut certificates = vec![];
+    if let Some(set) = signed.read_optional(0xa0)? {
+        let mut set = DerReader::new(set);
+        while !set.is_empty() {
+            // other certificate formats are tagged and skipped
+            match set.peek_tag() {
+                Some(der::TAG_SEQUENCE) => certificates.push(Certificate::from_der(set.read_raw()?)?),
+                _ => { set.read_tlv()?; },
+            }
+        }
+    }
+    // revocation information is not checked
+    signed.read_optional(0xa1)?;
+    let mut signer_infos = DerReader::new(signed.read(der::TAG_SET)?);
+    signed.finish()?;
+
+    let mut signer_info = signer_infos.read_sequence()?;
+    signer_info.read_integer()?;
+    let id = Identifier::read(&mut signer_info)?;
+    let hash = read_hash_algorithm(signer_info.read_raw()?)?;
+    let attributes = signer_info.read_optional(0xa0)?;
+    let signature_algorithm = signer_info.read_raw()?;
+    let signature = signer_info.read_octet_string()?;
+
+    let (key, signer) = match key {
+        Some(key) => (key.clone(), None),
+        None => {
+            let certificate = certificates.iter()
+                .find(|certificate| match &id {
+                    Identifier::IssuerAndSerial { issuer, serial } => certificate.issuer_der() == issuer.as_slice() && certificate.serial() == serial,
+                    Identifier::SubjectKeyId(_) => Identifier::from_key(certificate.public_key()) == id,
+                })
+                .ok_or_else(|| Error::Verification("the certificate of the signer is not included".to_string()))?;
+            (certificate.public_key().clone(), Some(certificate.clone()))
+        },
+    };
+    let scheme = match spki::read_rsa_algorithm_identifier(&mut DerReader::new(signature_algorithm)) {
+        Ok(()) => SignatureScheme::Pkcs1(hash),
+        Err(_) => SignatureScheme::from_algorithm_identifier(signature_algorithm)?,
+    };
+    if scheme.hash() != hash {
+        return Err(Error::Encoding("digest and signature algorithm use different hashes".to_string()));
+    }
+
+    let signed_bytes = match attributes {
+        Some(attributes) => {
+            check_signed_attributes(attributes, &hash.digest(content))?;
+            // the signature is calculated over the SET OF encoding
+            der::tlv(der::TAG_SET, attributes)
+        },
+        None => content.to_vec(),
+    };
+    scheme.verify(&key, &signed_bytes, signature)?;
+    Ok(Verified { content: content.to_vec(), signer, certificates })
+}
+
+/// Checks that the content type is data and the message digest matches `digest`
+fn check_signed_attributes(attributes: &[u8], digest: &[u8]) -> Result<()> {
+    let mut attributes = DerReader::new(attributes);
+    let (mut content_type, mut message_digest) = (false, false);
+    while !attributes.is_empty() {
+        let mut attribute = attributes.read_sequence()?;
+        let oid = attribute.read_oid()?;
+        let mut values = DerReader::new(attribute.read(der::TAG_SET)?);
+        if oid == OID_CONTENT_TYPE {
+            content_type = values.read_oid()? == OID_DATA;
+        } else if oid == OID_MESSAGE_DIGEST {
+            message_digest = values.read_octet_string()? == digest;
+        }
+    }
+    match (content_type, message_digest) {
+        (true, true) => Ok(()),
+        (_, false) => Err(Error::Verification("message digest does not match the content".to_string())),
+        (false, _) => Err(Error::Verification("content type attribute is missing".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+
+    use super::{Identifier, Recipient, Signer};
+    use crate::error::Error;
+    use crate::formats::der::{self, DerReader};
+    use crate::hash::HashAlgorithm;
+    use crate::key_gen::generate_key_pair;
+    use crate::padding::Padding;
+    use crate::signature::SignatureScheme;
+    use crate::x509::{Certificate, CertificateBuilder};
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        let (other_sk, other_pk) = generate_key_pair(1024, 2);
+        let message = b"Sed ut perspiciatis unde omnis iste natus error sit voluptatem";
+        for padding in [Padding::Pkcs1, Padding::Oaep(HashAlgorithm::Sha256)] {
+            let recipients = [Recipient::from_key(other_pk.clone()), Recipient::from_key(pk.clone())];
+            let encrypted = super::encrypt(message, &recipients, padding).unwrap();
+            assert_eq!(super::decrypt(&encrypted, &sk).unwrap(), message);
+            assert_eq!(super::decrypt(&encrypted, &other_sk).unwrap(), message);
+            let only_other = super::encrypt(message, &recipients[..1], padding).unwrap();
+            assert!(matches!(super::decrypt(&only_other, &sk), Err(Error::Padding(_))));
+        }
+        assert!(super::encrypt(message, &[Recipient::from_key(pk)], Padding::None).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_tries_every_candidate() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        let message = b"Sed ut perspiciatis unde omnis iste natus error sit voluptatem";
+        let encrypted = super::encrypt(message, &[Recipient::from_key(pk.clone())], Padding::Pkcs1).unwrap();
+        let mut info = DerReader::new(&encrypted).read_sequence().unwrap();
+        info.read_oid().unwrap();
+        let mut enveloped = DerReader::new(info.read(0xa0).unwrap()).read_sequence().unwrap();
+        let version = enveloped.read_raw().unwrap();
+        let recipient_info = DerReader::new(enveloped.read(der::TAG_SET).unwrap()).read_raw().unwrap();
+        let content = enveloped.read_raw().unwrap();
+        let mut content_info = DerReader::new(content).read_sequence().unwrap();
+        content_info.read_oid().unwrap();
+        let mut algorithm = content_info.read_sequence().unwrap();
+        algorithm.read_oid().unwrap();
+        let iv: [u8; 16] = algorithm.read_octet_string().unwrap().try_into().unwrap();
+        let ciphertext = content_info.read(0x80).unwrap();
+
+        // a key that passes the RSA padding check but doesn't decrypt the content, read last so it is tried first
+        let wrong_key = (0..=u8::MAX).map(|i| [i; 32]).find(|key| super::aes_cbc_decrypt(key, &iv, ciphertext).is_err()).unwrap();
+        let wrong_info = der::sequence(&[
+            der::integer(&Integer::from(2)),
+            Identifier::from_key(&pk).to_der(),
+            super::key_transport_algorithm(Padding::Pkcs1).unwrap(),
+            der::octet_string(&Padding::Pkcs1.encrypt(&pk, &wrong_key).unwrap()),
+        ]);
+        let recipient_infos = der::tlv(der::TAG_SET, &[recipient_info, &wrong_info].concat());
+        let enveloped = der::sequence(&[version.to_vec(), recipient_infos, content.to_vec()]);
+        let spliced = super::content_info(super::OID_ENVELOPED_DATA, &enveloped);
+        assert_eq!(super::decrypt(&spliced, &sk).unwrap(), message);
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        let certificate = Certificate::from_der(&CertificateBuilder::new("CN=signer".parse().unwrap()).self_signed(&sk).unwrap()).unwrap();
+        let content = b"signed content";
+        for scheme in [SignatureScheme::Pkcs1(HashAlgorithm::Sha256), SignatureScheme::Pss(HashAlgorithm::Sha384)] {
+            let signer = Signer { key: &sk, scheme, certificate: None };
+            let attached = super::sign(content, &signer, false).unwrap();
+            let verified = super::verify(&attached, None, Some(&pk)).unwrap();
+            assert_eq!(verified.content, content);
+            assert!(verified.signer.is_none());
+            assert_eq!(super::verify(&attached, Some(content), Some(&pk)).unwrap().content, content);
+            // a given content has to match the included one
+            assert!(matches!(super::verify(&attached, Some(b"other content"), Some(&pk)), Err(Error::Verification(_))));
+            // without certificate, the key is needed
+            assert!(super::verify(&attached, None, None).is_err());
+
+            let signer = Signer { key: &sk, scheme, certificate: Some(&certificate) };
+            let detached = super::sign(content, &signer, true).unwrap();
+            let verified = super::verify(&detached, Some(content), None).unwrap();
+            assert_eq!(verified.content, content);
+            assert_eq!(verified.signer.unwrap().to_der(), certificate.to_der());
+            assert_eq!(verified.certificates.len(), 1);
+            assert!(super::verify(&detached, None, None).is_err());
+            assert!(matches!(super::verify(&detached, Some(b"other content"), None), Err(Error::Verification(_))));
+            assert!(super::verify(&detached, Some(content), Some(&generate_key_pair(1024, 2).1)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_identifier() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        assert_eq!(Identifier::from_key(&sk), Identifier::from_key(&pk));
+        let certificate = Certificate::from_der(&CertificateBuilder::new("CN=a".parse().unwrap()).self_signed(&sk).unwrap()).unwrap();
+        assert!(matches!(Identifier::from_certificate(&certificate), Identifier::IssuerAndSerial { .. }));
+    }
+}
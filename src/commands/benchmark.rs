@@ -1,274 +1,566 @@
-use crate::parser::parser::OptParser;
-use crate::{opt, parser::opt::FlagType};
-use crate::parser::opt::OptDescriptor;
-use std::io::Write;
-use std::{collections::HashMap, time};
-use crate::key_gen;
-
-use super::util::{InitConfigError, self};
-
-type Result<T> = std::result::Result<T, InitConfigError>;
-
-const L_SIZE: &str = "size";
-const L_THREADS: &str = "threads";
-const L_FILE: &str = "file";
-const L_HELP: &str = "help";
-const L_REPEATS: &str = "repeats";
+use std::time;
 
-const S_SIZE: &str = "s";
-const S_THREADS: &str = "t";
-const S_FILE: &str = "f";
-const S_HELP: &str = "h";
-const S_REPEATS: &str = "r";
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::{Args, ValueEnum};
+use serde_json::{json, Value};
 
-#[derive(Debug)]
-pub struct InitBenchmarkError {
-    msg: String,
-}
+use crate::key_gen;
+use crate::output::{self, FileMode};
+use crate::error::{Error, Result};
 
-impl InitBenchmarkError {
-    pub fn get_msg(&self) -> &str {
-        &self.msg
-    }
-}
+use super::util;
 
-// benchmark commands:
-// benchmark [OPTIONS]
-// OPTIONS:
-// -s, --size <1024 2048 ...> size of keys to be used, from 128 to 8192, 1204 and 2048 if empty
-// -t, --threads <1 2 3 ...> number of threads to be used, num cpus if empty
-// -f, --file <file_name> save results to a file, bm.txt if empty
-// -h, --help print help for this command
-// -r, --repeats number of repeats, defaults to 5
-#[derive(Debug)]
+/// Benchmark key generation, if no options are given, 2048 bit keys with as many threads as physical cores
+#[derive(Debug, Args)]
 pub struct BenchmarkConfig {
+    /// sizes of the keys in bits, from 128 to 8192
+    #[arg(short = 's', long = "size", num_args = 1.., default_value = "2048", value_parser = util::parse_bit_size)]
     pub bit_sizes: Vec<u32>,
+    /// numbers of threads, at least 2, defaults to the number of physical cores
+    #[arg(short = 't', long = "threads", num_args = 1.., value_parser = parse_n_threads)]
     pub n_threads: Vec<usize>,
-    pub file: Option<String>,
+    /// how to write the results: table, json or csv
+    #[arg(short = 'F', long, value_enum, default_value_t = ReportFormat::Table)]
+    pub format: ReportFormat,
+    /// save results to a file instead of printing them, bm.txt, bm.json or bm.csv if FILE_NAME is empty
+    #[arg(short, long, value_name = "FILE_NAME")]
+    pub file: Option<Option<String>>,
+    /// overwrite the results file if it exists
+    #[arg(long, requires = "file")]
+    pub force: bool,
+    /// number of measured runs per key size and number of threads
+    #[arg(short, long, default_value_t = 5, value_parser = clap::value_parser!(u16).range(1..))]
     pub repeats: u16,
-    pub print_help: bool,
+    /// number of runs before measuring, which are not recorded
+    #[arg(short, long, default_value_t = 1)]
+    pub warmup: u16,
 }
 
 impl BenchmarkConfig {
-    pub fn init(args: &[String]) -> Result<Self> {
-        let expected = vec![
-            opt!(S_SIZE, L_SIZE, FlagType::MultiArg(false)),
-            opt!(S_THREADS, L_THREADS, FlagType::MultiArg(false)),
-            opt!(S_FILE, L_FILE, FlagType::SingleArg(true)),
-            opt!(S_HELP, L_HELP, FlagType::NoArg),
-            opt!(S_REPEATS, L_REPEATS, FlagType::SingleArg(false)),
-        ];
-
-        let parser = OptParser::new(args, expected);
-        let found_opts = parser.consume()?;
-
-        // Go through found options and create config accordingly
-        let mut bit_sizes = vec![2048];
-        let mut n_threads = vec![num_cpus::get_physical()];
-        let mut file = None;
-        let mut print_help = false;
-        let mut repeats = 5;
-        for opt in found_opts {
-            match opt.get_name() {
-                L_SIZE => if let Some(size_strings) = opt.consume() {
-                    bit_sizes = Self::parse_bit_sizes(size_strings)?;
-                }
-                L_THREADS => if let Some(thread_strings) = opt.consume() {
-                    n_threads = Self::parse_n_threads(thread_strings)?;
-                },
-                L_FILE => match opt.consume() {
-                    // indexing directly is safe, since file refers to SingleArg and parser returns None
-                    // if no arg was provided, otherwise file_name will hold exactly one value
-                    Some(file_name) => {
-                        // if file already exists, return error                      
-                        file = Some(util::verify_file_name(&file_name[0])?)
-                    },
-                    //bm.txt gets overwritten
-                    None => file = Some("bm.txt".to_string()),
-                    
-                },
-                L_HELP => print_help = true,
-                L_REPEATS => {
-                    if let Some(repeats_strings) = opt.consume() {
-                        if let Ok(n) = repeats_strings[0].parse::<u16>() {
-                            repeats = n;
-                        } else {
-                            return Err(InitConfigError { msg: format!("Unable to parse number of repeats: {}", repeats_strings[0])});
-                        }
-                    }
-                }
-                invalid => return Err(InitConfigError { msg: format!("Parser returned invalid opt: {}", invalid) }),
-            }
+    /// The given numbers of threads, or the number of physical cores
+    pub fn n_threads(&self) -> Vec<usize> {
+        match self.n_threads.is_empty() {
+            true => vec![num_cpus::get_physical()],
+            false => self.n_threads.clone(),
         }
+    }
 
-        Ok(BenchmarkConfig { bit_sizes, n_threads, file, repeats, print_help })
+    /// File to store the results in
+    pub fn file(&self) -> Option<&str> {
+        let default = match self.format {
+            ReportFormat::Table => "bm.txt",
+            ReportFormat::Json => "bm.json",
+            ReportFormat::Csv => "bm.csv",
+        };
+        self.file.as_ref().map(|name| name.as_deref().unwrap_or(default))
     }
+}
 
-    #[inline(always)]
-    fn parse_bit_sizes(size_strings: Vec<String>) -> Result<Vec<u32>> {
-        let mut parsed_sizes = vec![];
+fn parse_n_threads(n_threads: &str) -> Result<usize> {
+    match n_threads.parse::<usize>() {
+        Ok(n) if n >= 2 => Ok(n),
+        Ok(n) => Err(Error::InvalidArgs(format!("Invald thread size: {}, needs to be at least 2", n))),
+        Err(_) => Err(Error::InvalidArgs(format!("Unable to parse number of threads: {}", n_threads))),
+    }
+}
 
-        for size in size_strings {
-            if let Err(_) = size.parse::<u32>() {
-                return Err(InitConfigError { msg: format!("Unable to parse bit size: {}", size )});
-            }
-            let n = size.parse::<u32>().unwrap();
-            if !util::is_valid_bit_size(n) {
-                return Err(InitConfigError { msg: format!("Invalid bit size: {}, needs to be in range of 128 to 8192 and power of 2.", n)});
-            }
-            parsed_sizes.push(n);
+/// Spread above which the results of a cell get a note, as standard deviation relative to the mean
+const HIGH_SPREAD: f64 = 0.25;
+
+/// Durations of the runs of one benchmark cell in microseconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    // sorted ascending, never empty
+    samples: Vec<u64>,
+}
+
+impl Stats {
+    pub fn new(mut samples: Vec<u64>) -> Self {
+        assert!(!samples.is_empty(), "a benchmark needs at least one sample");
+        samples.sort_unstable();
+        Stats { samples }
+    }
+
+    pub fn samples(&self) -> &[u64] {
+        &self.samples
+    }
+
+    pub fn min(&self) -> u64 {
+        self.samples[0]
+    }
+
+    pub fn max(&self) -> u64 {
+        self.samples[self.samples.len() - 1]
+    }
+
+    pub fn median(&self) -> f64 {
+        let n = self.samples.len();
+        match n % 2 {
+            0 => (self.samples[n / 2 - 1] + self.samples[n / 2]) as f64 / 2.0,
+            _ => self.samples[n / 2] as f64,
         }
+    }
 
-        Ok(parsed_sizes)
+    pub fn mean(&self) -> f64 {
+        self.samples.iter().sum::<u64>() as f64 / self.samples.len() as f64
     }
 
-    #[inline(always)]
-    fn parse_n_threads(thread_strings: Vec<String>) -> Result<Vec<usize>> {
-        let mut parsed_threads = vec![];
-        for n_threads in thread_strings {
-            if let Err(_) = n_threads.parse::<usize>() {
-                return Err(InitConfigError { msg: format!("Unable to parse number of threads: {}", n_threads) });
-            }
-            let n = n_threads.parse::<usize>().unwrap();
-            if n < 2 {
-                return Err(InitConfigError { msg: format!("Invald thread size: {}, needs to be at least 2", n) })
-            }
-            parsed_threads.push(n);
+    /// Sample standard deviation, 0 for a single sample
+    pub fn stddev(&self) -> f64 {
+        if self.samples.len() < 2 {
+            return 0.0;
         }
-        Ok(parsed_threads)
+        let mean = self.mean();
+        let squares: f64 = self.samples.iter().map(|&sample| (sample as f64 - mean).powi(2)).sum();
+        (squares / (self.samples.len() - 1) as f64).sqrt()
+    }
+
+    /// Nearest-rank percentile, the smallest sample that is at least as large as `p` percent of the samples
+    pub fn percentile(&self, p: u32) -> u64 {
+        let rank = (p as usize * self.samples.len()).div_ceil(100).max(1);
+        self.samples[rank - 1]
+    }
+
+    /// Samples above the upper Tukey fence, the third quartile plus 1.5 times the interquartile range
+    pub fn outliers(&self) -> usize {
+        let (q1, q3) = (self.percentile(25) as f64, self.percentile(75) as f64);
+        let fence = q3 + 1.5 * (q3 - q1);
+        self.samples.iter().filter(|&&sample| sample as f64 > fence).count()
+    }
+
+    /// A warning if the standard deviation is large compared to the mean, the median is more meaningful then
+    pub fn outlier_note(&self) -> Option<String> {
+        let spread = self.stddev() / self.mean();
+        if spread <= HIGH_SPREAD {
+            return None;
+        }
+        let outliers = match self.outliers() {
+            0 => String::new(),
+            n => format!(", {} of {} runs are outliers", n, self.samples.len()),
+        };
+        Some(format!("high spread: the standard deviation is {:.0}% of the mean{}, compare the median or use more repeats", spread * 100.0, outliers))
     }
 }
 
-// impl Configuration for BenchmarkConfig {
-fn get_help_message() -> String {
-    "Usage:\n\n\
-    benchmark [OPTIONS]\n\n\
-    OPTIONS:\n\
-    -s, --size <1024 2048 ...> size of keys to be used, from 128 to 8192, 1204 and 2048 if empty\n\
-    -t, --threads <1 2 3 ...> number of threads to be used, num cpus if empty\n\
-    -f, --file <file_name> save results to a file, bm.txt if empty\n\
-    -h, --help print help for this command\n\
-    -r, --repeats number of repeats, defaults to 5".to_string()
+/// Output format of the results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Table,
+    Json,
+    Csv,
 }
-// }
-
-// runs the benchmark
-// results are stored in a hash map of the form <bitsize, vec(n_threads, time)>
-fn benchmark_threads(repeats: u16, n_threads: &Vec<usize>, bit_sizes: &Vec<u32>)  -> HashMap<u32, Vec<(usize, u128)>>{
-    let mut benchmark_results = HashMap::new();
-
-    for bit_size in bit_sizes {
-        let mut entry = vec![];
-        for t in n_threads {
-            let mut single_result = (*t, 0);
-            for _ in 0..repeats {
-                single_result.1 += benchmark_generate_key_pair(*bit_size, *t)
-            }
-            single_result.1 /= repeats as u128;
-            entry.push(single_result);
+
+/// The machine and build the benchmark ran on, to compare results over time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Environment {
+    pub cpu_model: String,
+    pub physical_cores: usize,
+    pub logical_cores: usize,
+    pub crate_version: String,
+    /// the arbitrary precision arithmetic used for key generation
+    pub backend: String,
+    pub os: String,
+    pub arch: String,
+    /// when the benchmark started
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Environment {
+    pub fn detect() -> Self {
+        Environment {
+            cpu_model: cpu_model().unwrap_or_else(|| "unknown".to_string()),
+            physical_cores: num_cpus::get_physical(),
+            logical_cores: num_cpus::get(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            backend: "GMP (rug)".to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            timestamp: Utc::now(),
         }
-        benchmark_results.insert(*bit_size, entry);
     }
-    benchmark_results
+
+    fn timestamp(&self) -> String {
+        self.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
 }
 
-fn results_to_string(benchmark_results: HashMap<u32, Vec<(usize, u128)>>) -> String {
-    let mut results_string = String::new();
-    for (bit_size, results_per_thread) in benchmark_results {
-        results_string += &format!("Bitsize: {}\n", bit_size); 
-        for (n_threads, time) in results_per_thread {
-            results_string += &format!("\t{} threads: {}ms\n", n_threads, time);
-        }
+/// Model name of the CPU from /proc/cpuinfo, or from sysctl on macOS
+fn cpu_model() -> Option<String> {
+    if let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo") {
+        return cpuinfo
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| matches!(key.trim(), "model name" | "Model" | "Hardware" | "cpu model"))
+            .map(|(_, model)| model.trim().to_string());
     }
+    let output = std::process::Command::new("sysctl").args(["-n", "machdep.cpu.brand_string"]).output().ok()?;
+    let model = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !model.is_empty()).then_some(model)
+}
 
-    results_string
+/// Statistics of one key size and number of threads
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    pub bits: u32,
+    pub threads: usize,
+    pub stats: Stats,
 }
 
-fn write_results_to_file(results: &str, file_name: &str) -> std::io::Result<()> {
-    let mut file = std::fs::File::create(file_name)?;
+/// The results of a benchmark, sorted by key size and number of threads
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub environment: Environment,
+    pub repeats: u16,
+    pub warmup: u16,
+    pub results: Vec<BenchmarkResult>,
+}
 
-    Ok(file.write_all(results.as_bytes())?)
+/// Microseconds as milliseconds with three decimals
+fn ms(micros: f64) -> String {
+    format!("{:.3}", micros / 1000.0)
 }
 
-pub fn run(config: BenchmarkConfig) {
-    // print help always stops program
-    if config.print_help {
-        println!("{}", get_help_message());
-        return;
-    }
-    let benchmark_results = benchmark_threads(config.repeats, &config.n_threads, &config.bit_sizes);
-    let results_string = results_to_string(benchmark_results);
-    println!("{}", results_string);
-    
-    if let Some(file_name) = config.file {
-        // store results
-        if let Err(e) = write_results_to_file(&results_string, &file_name) {
-            eprintln!("Error writing to file {}", e);
-        } else {
-            println!("Wrote results to file: {}", file_name);
+/// Microseconds rounded to a thousandth, so float noise doesn't end up in the output
+fn round_us(micros: f64) -> f64 {
+    (micros * 1000.0).round() / 1000.0
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+const CSV_HEADER: &str = "timestamp,crate_version,backend,cpu_model,physical_cores,logical_cores,os,arch,\
+    bits,threads,runs,warmup,min_us,median_us,mean_us,stddev_us,p90_us,p99_us,max_us";
+
+impl Report {
+    pub fn format(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Table => self.to_table(),
+            ReportFormat::Json => serde_json::to_string_pretty(&self.to_json()).unwrap_or_default() + "\n",
+            ReportFormat::Csv => self.to_csv(),
         }
     }
+
+    pub fn to_table(&self) -> String {
+        let env = &self.environment;
+        let mut table = format!(
+            "rsa_arbitray_precision {}, {}, {}/{}\nCPU: {}, {} physical / {} logical cores\n{}, {} runs after {} warmup runs\n\n",
+            env.crate_version, env.backend, env.os, env.arch, env.cpu_model, env.physical_cores, env.logical_cores,
+            env.timestamp(), self.repeats, self.warmup
+        );
+        table += &format!(
+            "{:>5}  {:>7}  {:>4}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}\n",
+            "bits", "threads", "runs", "min ms", "median ms", "mean ms", "stddev ms", "p90 ms", "p99 ms", "max ms"
+        );
+        let mut notes = vec![];
+        for result in &self.results {
+            let stats = &result.stats;
+            table += &format!(
+                "{:>5}  {:>7}  {:>4}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}\n",
+                result.bits,
+                result.threads,
+                stats.samples().len(),
+                ms(stats.min() as f64),
+                ms(stats.median()),
+                ms(stats.mean()),
+                ms(stats.stddev()),
+                ms(stats.percentile(90) as f64),
+                ms(stats.percentile(99) as f64),
+                ms(stats.max() as f64),
+            );
+            if let Some(note) = stats.outlier_note() {
+                notes.push(format!("note: {} bits, {} threads: {}\n", result.bits, result.threads, note));
+            }
+        }
+        table + &notes.concat()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let env = &self.environment;
+        json!({
+            "timestamp": env.timestamp(),
+            "crate_version": env.crate_version,
+            "backend": env.backend,
+            "cpu_model": env.cpu_model,
+            "physical_cores": env.physical_cores,
+            "logical_cores": env.logical_cores,
+            "os": env.os,
+            "arch": env.arch,
+            "repeats": self.repeats,
+            "warmup": self.warmup,
+            "results": self.results.iter().map(|result| {
+                let stats = &result.stats;
+                json!({
+                    "bits": result.bits,
+                    "threads": result.threads,
+                    "runs": stats.samples().len(),
+                    "min_us": stats.min(),
+                    "median_us": round_us(stats.median()),
+                    "mean_us": round_us(stats.mean()),
+                    "stddev_us": round_us(stats.stddev()),
+                    "p90_us": stats.percentile(90),
+                    "p99_us": stats.percentile(99),
+                    "max_us": stats.max(),
+                    "samples_us": stats.samples(),
+                    "note": stats.outlier_note(),
+                })
+            }).collect::<Vec<Value>>(),
+        })
+    }
+
+    /// One row per result, each with the environment, so rows of several runs can be appended to one file
+    pub fn to_csv(&self) -> String {
+        let env = &self.environment;
+        let mut csv = CSV_HEADER.to_string() + "\n";
+        for result in &self.results {
+            let stats = &result.stats;
+            let fields = [
+                env.timestamp(),
+                csv_field(&env.crate_version),
+                csv_field(&env.backend),
+                csv_field(&env.cpu_model),
+                env.physical_cores.to_string(),
+                env.logical_cores.to_string(),
+                csv_field(&env.os),
+                csv_field(&env.arch),
+                result.bits.to_string(),
+                result.threads.to_string(),
+                stats.samples().len().to_string(),
+                self.warmup.to_string(),
+                stats.min().to_string(),
+                round_us(stats.median()).to_string(),
+                round_us(stats.mean()).to_string(),
+                round_us(stats.stddev()).to_string(),
+                stats.percentile(90).to_string(),
+                stats.percentile(99).to_string(),
+                stats.max().to_string(),
+            ];
+            csv += &(fields.join(",") + "\n");
+        }
+        csv
+    }
+}
+
+// runs the benchmark, the warmup runs of every cell are not recorded.
+// Key sizes and numbers of threads are run in the given order, the results are sorted.
+fn benchmark_threads(repeats: u16, warmup: u16, n_threads: &[usize], bit_sizes: &[u32]) -> Vec<BenchmarkResult> {
+    let mut results = vec![];
+    for &bits in bit_sizes {
+        for &threads in n_threads {
+            for _ in 0..warmup {
+                benchmark_generate_key_pair(bits, threads);
+            }
+            let samples = (0..repeats).map(|_| benchmark_generate_key_pair(bits, threads)).collect();
+            results.push(BenchmarkResult { bits, threads, stats: Stats::new(samples) });
+        }
+    }
+    results.sort_by_key(|result| (result.bits, result.threads));
+    results
+}
+
+pub fn run(config: BenchmarkConfig) -> Result<()> {
+    // fail before the benchmark, which can take a while
+    if let Some(file_name) = config.file() {
+        output::check_overwrite(file_name, config.force)?;
+    }
+    // every cell once, the results are sorted anyway
+    let mut n_threads = config.n_threads();
+    let mut bit_sizes = config.bit_sizes.clone();
+    n_threads.sort_unstable();
+    bit_sizes.sort_unstable();
+    n_threads.dedup();
+    bit_sizes.dedup();
+    let environment = Environment::detect();
+    let results = benchmark_threads(config.repeats, config.warmup, &n_threads, &bit_sizes);
+    let report = Report { environment, repeats: config.repeats, warmup: config.warmup, results };
+    let formatted = report.format(config.format);
+
+    match config.file() {
+        Some(file_name) => {
+            output::write_file(file_name, formatted.as_bytes(), FileMode::Default, config.force)?;
+            println!("Wrote results to file: {}", file_name);
+        },
+        None => print!("{}", formatted),
+    }
+    Ok(())
 }
 
-fn benchmark_generate_key_pair(bits: u32, n_threads: usize) -> u128 {
+/// Duration of generating a key pair in microseconds
+fn benchmark_generate_key_pair(bits: u32, n_threads: usize) -> u64 {
     let start = time::Instant::now();
     let (_, _) = key_gen::generate_key_pair(bits, n_threads);
-    // println!("Created {} bit key pair in {}, with {} threads", bits, start.elapsed().as_millis(), n_threads);
-    start.elapsed().as_millis()
+    start.elapsed().as_micros() as u64
 }
 
 #[cfg(test)]
 mod tests {
-    use super::BenchmarkConfig;
+    use clap::error::ErrorKind;
+
+    use crate::cli::{parse, Command};
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{benchmark_threads, BenchmarkConfig, BenchmarkResult, Environment, Report, ReportFormat, Stats};
+
+    fn parse_benchmark(args: &[&str]) -> Result<BenchmarkConfig, clap::Error> {
+        match parse(&[&["benchmark"], args].concat())? {
+            Command::Benchmark(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
 
     #[test]
     fn test_init_valid() {
-        let args = (vec!["benchmark", "-f", "blub", "--threads", "2", "4", "6", "--size", "512", "1024", "2048", "-h"])
-            .into_iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
-        let config = BenchmarkConfig::init(&args[1..]);
+        let config = parse_benchmark(&["-f", "blub", "--threads", "2", "4", "6", "--size", "512", "1024", "2048", "-r", "3"]);
         assert!(config.is_ok());
         let config = config.unwrap();
         assert_eq!(config.bit_sizes, vec![512, 1024, 2048]);
-        assert!(config.print_help);
-        assert_eq!(config.n_threads, vec![2, 4, 6]);
-        assert_eq!(config.file.unwrap(), "blub");
+        assert_eq!(config.repeats, 3);
+        assert_eq!(config.warmup, 1);
+        assert_eq!(config.n_threads(), vec![2, 4, 6]);
+        assert_eq!(config.file().unwrap(), "blub");
+        assert_eq!(parse_benchmark(&["-h"]).unwrap_err().kind(), ErrorKind::DisplayHelp);
     }
 
     #[test]
     fn test_init_defaults() {
-        let args = vec!["-f".to_string()];
-
-        let config = BenchmarkConfig::init(&args);
+        let config = parse_benchmark(&["-f"]);
         assert!(config.is_ok());
         let config = config.unwrap();
         assert_eq!(config.bit_sizes, vec![2048]);
-        assert_eq!(config.n_threads, vec![num_cpus::get_physical()]);
-        assert_eq!(config.file.unwrap(), "bm.txt");
+        assert_eq!(config.n_threads(), vec![num_cpus::get_physical()]);
+        assert_eq!(config.file().unwrap(), "bm.txt");
+        assert_eq!(config.format, ReportFormat::Table);
+        assert_eq!(parse_benchmark(&["-F", "json", "-f"]).unwrap().file().unwrap(), "bm.json");
+        assert_eq!(parse_benchmark(&["--format", "csv", "-f"]).unwrap().file().unwrap(), "bm.csv");
+        assert_eq!(parse_benchmark(&["-F", "xml"]).unwrap_err().kind(), ErrorKind::InvalidValue);
     }
 
     #[test]
     fn test_init_invalid_flag() {
-        let args = vec!["-f".to_string(), "file.txt".to_string(), "--henlo".to_string()];
-        let config = BenchmarkConfig::init(&args);
+        let config = parse_benchmark(&["-f", "file.txt", "--henlo"]);
         assert!(config.is_err());
-        assert_eq!(config.unwrap_err().msg, "Invalid option: --henlo");
+        let error = config.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnknownArgument);
+        assert!(error.to_string().contains("unexpected argument '--henlo'"));
     }
 
     #[test]
     fn test_init_invalid_bit_size() {
-        let args = vec!["-s".to_string(), "512".to_string(), "1023".to_string()];
-        let config = BenchmarkConfig::init(&args);
+        let config = parse_benchmark(&["-s", "512", "1023"]);
         assert!(config.is_err());
-        assert_eq!(config.unwrap_err().msg, "Invalid bit size: 1023, needs to be in range of 128 to 8192 and power of 2.");
+        let error = config.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ValueValidation);
+        assert!(error.to_string().contains("Invalid bit size: 1023, needs to be in range of 128 to 8192 and power of 2."));
     }
 
     #[test]
     fn test_init_invalid_n_threads() {
-        let args = vec!["-t".to_string(), "5".to_string(), "bla".to_string()];
-        let config = BenchmarkConfig::init(&args);
+        let config = parse_benchmark(&["-t", "5", "bla"]);
         assert!(config.is_err());
-        assert_eq!(config.unwrap_err().msg, "Unable to parse number of threads: bla" )
+        assert!(config.unwrap_err().to_string().contains("Unable to parse number of threads: bla"));
+    }
+
+    #[test]
+    fn test_init_warmup() {
+        assert_eq!(parse_benchmark(&["-w", "0"]).unwrap().warmup, 0);
+        assert_eq!(parse_benchmark(&["-r", "0"]).unwrap_err().kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::new(vec![400, 100, 300, 200]);
+        assert_eq!(stats.samples(), &[100, 200, 300, 400]);
+        assert_eq!((stats.min(), stats.max()), (100, 400));
+        assert_eq!(stats.median(), 250.0);
+        assert_eq!(stats.mean(), 250.0);
+        assert!((stats.stddev() - 129.0994).abs() < 1e-3);
+        assert_eq!((stats.percentile(50), stats.percentile(90), stats.percentile(99)), (200, 400, 400));
+        assert_eq!(stats.outliers(), 0);
+
+        let single = Stats::new(vec![42]);
+        assert_eq!((single.median(), single.stddev(), single.percentile(1), single.percentile(99)), (42.0, 0.0, 42, 42));
+        assert_eq!(single.outlier_note(), None);
+    }
+
+    #[test]
+    fn test_outliers() {
+        let mut samples: Vec<u64> = (1000..1019).collect();
+        samples.push(9000);
+        let stats = Stats::new(samples);
+        assert_eq!(stats.percentile(90), 1017);
+        assert_eq!(stats.percentile(99), 9000);
+        assert_eq!(stats.outliers(), 1);
+        assert!(stats.outlier_note().unwrap().contains("1 of 20 runs are outliers"));
+        assert_eq!(Stats::new(vec![1000, 1100, 1050]).outlier_note(), None);
+    }
+
+    fn report() -> Report {
+        Report {
+            environment: Environment {
+                cpu_model: "Test CPU, \"fast\"".to_string(),
+                physical_cores: 4,
+                logical_cores: 8,
+                crate_version: "1.2.3".to_string(),
+                backend: "GMP (rug)".to_string(),
+                os: "linux".to_string(),
+                arch: "x86_64".to_string(),
+                timestamp: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            },
+            repeats: 2,
+            warmup: 1,
+            results: vec![
+                BenchmarkResult { bits: 1024, threads: 2, stats: Stats::new(vec![1500, 2500]) },
+                BenchmarkResult { bits: 2048, threads: 4, stats: Stats::new(vec![10000, 12000]) },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_sorted_results() {
+        let results = benchmark_threads(1, 0, &[4, 2], &[256, 128]);
+        let cells: Vec<_> = results.iter().map(|result| (result.bits, result.threads, result.stats.samples().len())).collect();
+        assert_eq!(cells, vec![(128, 2, 1), (128, 4, 1), (256, 2, 1), (256, 4, 1)]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_formats() {
+        let report = report();
+        let table = report.format(ReportFormat::Table);
+        assert!(table.starts_with("rsa_arbitray_precision 1.2.3, GMP (rug), linux/x86_64\nCPU: Test CPU, \"fast\", 4 physical / 8 logical cores\n2024-05-01T12:00:00Z, 2 runs"));
+        let rows: Vec<_> = table.lines().skip_while(|line| !line.trim_start().starts_with("bits")).skip(1).filter(|line| !line.starts_with("note")).collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].trim_start().starts_with("1024") && rows[0].contains("2.000"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.format(ReportFormat::Json)).unwrap();
+        assert_eq!(json["timestamp"], "2024-05-01T12:00:00Z");
+        assert_eq!(json["physical_cores"], 4);
+        assert_eq!(json["logical_cores"], 8);
+        assert_eq!(json["crate_version"], "1.2.3");
+        assert_eq!(json["results"][1]["bits"], 2048);
+        assert_eq!(json["results"][1]["median_us"], 11000.0);
+        assert_eq!(json["results"][1]["samples_us"], serde_json::json!([10000, 12000]));
+        assert!(json["results"][0]["note"].is_string());
+        assert!(json["results"][1]["note"].is_null());
+
+        let csv = report.format(ReportFormat::Csv);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("timestamp,crate_version,backend,cpu_model,physical_cores,logical_cores,os,arch,bits,threads,"));
+        assert_eq!(lines[1], "2024-05-01T12:00:00Z,1.2.3,GMP (rug),\"Test CPU, \"\"fast\"\"\",4,8,linux,x86_64,1024,2,2,1,1500,2000,2000,707.107,2500,2500,2500");
+        // the header and the rows have the same number of columns, apart from the quoted comma
+        assert_eq!(lines[0].split(',').count() + 1, lines[1].split(',').count());
+    }
+
+    #[test]
+    fn test_detect_environment() {
+        let environment = Environment::detect();
+        assert!(!environment.cpu_model.is_empty());
+        assert!(environment.logical_cores >= environment.physical_cores && environment.physical_cores >= 1);
+        assert_eq!(environment.crate_version, env!("CARGO_PKG_VERSION"));
+    }
+}
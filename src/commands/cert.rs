@@ -0,0 +1,147 @@
+use std::io::Write;
+
+use chrono::{Duration, Utc};
+use clap::Args;
+use rug::Integer;
+
+use crate::error::{Error, Result};
+use crate::formats::{pem, Encoding};
+use crate::output::{self, FileMode};
+use crate::settings::Settings;
+use crate::signature::SignatureScheme;
+use crate::x509::{self, CertificateBuilder, Name, SubjectAltName};
+
+use super::util;
+
+/// Create a self-signed certificate or a certification request for a private key
+#[derive(Debug, Args)]
+#[command(allow_negative_numbers = true)]
+pub struct CertConfig {
+    /// subject, e.g. CN=example.com,O=Example or /CN=example.com/O=Example
+    #[arg(short, long, value_name = "NAME")]
+    subject: Option<Name>,
+    /// subject alternative names, separated by commas, e.g. DNS:example.com,IP:127.0.0.1
+    #[arg(short = 'a', long = "san", value_name = "NAME", value_delimiter = ',')]
+    subject_alt_names: Vec<SubjectAltName>,
+    /// number of days the certificate is valid, at most 36500
+    #[arg(short, long, default_value_t = 365, value_parser = clap::value_parser!(i64).range(1..=36500))]
+    days: i64,
+    /// serial number, decimal or hex with 0x prefix, random if not given
+    #[arg(short = 'n', long, value_parser = parse_serial)]
+    serial: Option<Integer>,
+    /// certificate is a CA
+    #[arg(short, long)]
+    ca: bool,
+    /// maximum number of intermediate CAs below a CA
+    #[arg(short, long, value_name = "N", requires = "ca")]
+    path_len: Option<u32>,
+    /// signature scheme: pkcs1 or pss, optionally followed by the hash, e.g. pss-sha384
+    #[arg(short = 'S', long, default_value = "pkcs1-sha256")]
+    scheme: SignatureScheme,
+    /// create a PKCS#10 certification request instead of a certificate
+    #[arg(short, long)]
+    request: bool,
+    /// encoding of the output, defaults to output_format of the config
+    #[arg(short, long)]
+    encoding: Option<Encoding>,
+    /// save output to file, otherwise it is printed
+    #[arg(short, long, value_name = "FILE_NAME")]
+    file: Option<String>,
+    /// overwrite FILE_NAME if it exists
+    #[arg(long, requires = "file")]
+    force: bool,
+    /// file containing the private key
+    key_file: String,
+}
+
+/// Decimal or hex with 0x prefix, like OpenSSL's -set_serial
+fn parse_serial(serial: &str) -> Result<Integer> {
+    let parsed = match serial.strip_prefix("0x").or_else(|| serial.strip_prefix("0X")) {
+        Some(hex) => Integer::from_str_radix(hex, 16),
+        None => Integer::from_str_radix(serial, 10),
+    };
+    match parsed {
+        Ok(serial) if serial > 0 => Ok(serial),
+        _ => Err(Error::InvalidArgs(format!("Invalid parameter for -n/--serial: {}, has to be a positive number.", serial))),
+    }
+}
+
+pub fn run(config: CertConfig, settings: &Settings) -> Result<()> {
+    let encoding = config.encoding.unwrap_or(settings.output_format);
+    let key = util::read_private_key(&config.key_file)?;
+    let now = Utc::now();
+    let mut builder = CertificateBuilder::new(config.subject.unwrap_or_default())
+        .subject_alt_names(config.subject_alt_names)
+        .validity(now, now + Duration::days(config.days))
+        .signature_scheme(config.scheme);
+    if let Some(serial) = config.serial {
+        builder = builder.serial(serial);
+    }
+    if config.ca {
+        builder = builder.ca(config.path_len);
+    }
+
+    let (der, label) = match config.request {
+        true => (builder.request(&key)?, x509::PEM_LABEL_REQUEST),
+        false => (builder.self_signed(&key)?, x509::PEM_LABEL_CERTIFICATE),
+    };
+    let encoded = match encoding {
+        Encoding::Pem => pem::encode(label, &der).into_bytes(),
+        Encoding::Der => der,
+    };
+
+    match config.file {
+        Some(file_name) => {
+            output::write_file(&file_name, &encoded, FileMode::Default, config.force)?;
+            println!("Wrote {} to file: {}", label.to_lowercase(), file_name);
+        },
+        None => std::io::stdout().write_all(&encoded)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CertConfig;
+    use crate::cli::{parse, Command};
+    use crate::formats::Encoding;
+    use crate::hash::HashAlgorithm;
+    use crate::signature::SignatureScheme;
+
+    fn parse_cert(args: &[&str]) -> Result<CertConfig, clap::Error> {
+        match parse(&[&["cert"], args].concat())? {
+            Command::Cert(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_init() {
+        let args = ["-s", "CN=example.com", "-a", "DNS:example.com,IP:127.0.0.1", "-d", "30", "-n", "0x1f", "-c", "-p", "0", "-S", "pss", "-e", "der", "sk_file"];
+        let config = parse_cert(&args).unwrap();
+        assert_eq!(config.subject.unwrap().common_name(), Some("example.com"));
+        assert_eq!(config.subject_alt_names.len(), 2);
+        assert_eq!(config.days, 30);
+        assert_eq!(config.serial.unwrap(), 31);
+        assert!(config.ca);
+        assert_eq!(config.path_len, Some(0));
+        assert_eq!(config.scheme, SignatureScheme::Pss(HashAlgorithm::Sha256));
+        assert_eq!(config.encoding, Some(Encoding::Der));
+        assert_eq!(config.key_file, "sk_file");
+
+        let config = parse_cert(&["sk_file", "--days=7"]).unwrap();
+        assert_eq!(config.days, 7);
+        assert!(config.subject.is_none() && config.serial.is_none());
+    }
+
+    #[test]
+    fn test_init_invalid() {
+        assert!(parse_cert(&["-s", "CN=a"]).is_err());
+        assert!(parse_cert(&["-p", "1", "sk_file"]).is_err());
+        assert!(parse_cert(&["-d", "0", "sk_file"]).is_err());
+        assert!(parse_cert(&["-d", "-3", "sk_file"]).is_err());
+        assert!(parse_cert(&["-d", "36501", "sk_file"]).is_err());
+        assert!(parse_cert(&["-d", "9223372036854775807", "sk_file"]).is_err());
+        assert!(parse_cert(&["-n", "-5", "sk_file"]).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use clap::Args;
+
+use crate::error::Result;
+
+use super::util::{self, KeyType, LoadedKey};
+
+/// Check if a key is consistent
+#[derive(Debug, Args)]
+pub struct CheckKeyConfig {
+    /// type of the key, detected from the file if not given
+    #[arg(short, long)]
+    key_type: Option<KeyType>,
+    /// file containing rsa key
+    key_file: String,
+}
+
+pub fn run(config: CheckKeyConfig) -> Result<()> {
+    let report = match util::load_key(&config.key_file, config.key_type.map(KeyType::is_private))? {
+        LoadedKey::Private(key) => {
+            println!("Checking private key {}", config.key_file);
+            key.validation_report()
+        },
+        LoadedKey::Public(key) => {
+            println!("Checking public key {}", config.key_file);
+            key.validation_report()
+        },
+    };
+
+    println!("{}", report);
+    report.into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+
+    use super::CheckKeyConfig;
+    use crate::cli::{parse, Command};
+    use crate::commands::util::KeyType;
+
+    fn parse_check_key(args: &[&str]) -> Result<CheckKeyConfig, clap::Error> {
+        match parse(&[&["check-key"], args].concat())? {
+            Command::CheckKey(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_init() {
+        let config = parse_check_key(&["-k", "public", "pk_file"]).unwrap();
+        assert_eq!(config.key_file, "pk_file");
+        assert_eq!(config.key_type, Some(KeyType::Public));
+
+        let config = parse_check_key(&["sk_file", "--key-type=private"]).unwrap();
+        assert_eq!(config.key_type, Some(KeyType::Private));
+        assert_eq!(parse_check_key(&["sk_file"]).unwrap().key_type, None);
+        assert!(parse_check_key(&["sk_file", "--key=private"]).is_err());
+    }
+
+    #[test]
+    fn test_init_missing_key_file() {
+        assert_eq!(parse_check_key(&[]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        assert_eq!(parse_check_key(&["-h"]).unwrap_err().kind(), ErrorKind::DisplayHelp);
+        assert_eq!(parse_check_key(&["-k", "secret", "sk_file"]).unwrap_err().kind(), ErrorKind::InvalidValue);
+    }
+}
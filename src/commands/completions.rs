@@ -0,0 +1,51 @@
+use std::io::Write;
+
+use clap::{Args, CommandFactory};
+use clap_complete::Shell;
+
+use crate::cli::Cli;
+use crate::error::Result;
+
+/// Print a completion script for a shell, generated from the options of all commands
+#[derive(Debug, Args)]
+pub struct CompletionsConfig {
+    /// shell to complete for
+    shell: Shell,
+}
+
+/// Completion script for `shell`, e.g. to save to /etc/bash_completion.d
+pub fn completion_script(shell: Shell) -> Vec<u8> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    let mut script = vec![];
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    script
+}
+
+pub fn run(config: CompletionsConfig) -> Result<()> {
+    std::io::stdout().write_all(&completion_script(config.shell))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap_complete::Shell;
+
+    use super::completion_script;
+    use crate::cli::{parse, Command};
+
+    #[test]
+    fn test_init() {
+        assert!(matches!(parse(&["completions", "zsh"]), Ok(Command::Completions(_))));
+        assert!(parse(&["completions", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn test_completion_script() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = String::from_utf8(completion_script(shell)).unwrap();
+            assert!(script.contains("verify-cert"));
+            assert!(script.contains("recipient"));
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use clap::Subcommand;
+
+use crate::error::Result;
+use crate::settings::Settings;
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective settings as config file, with the source of each value
+    Show,
+}
+
+pub fn run(command: ConfigCommand, settings: &Settings) -> Result<()> {
+    match command {
+        ConfigCommand::Show => print!("{}", settings.show()),
+    }
+    Ok(())
+}
@@ -1,182 +1,316 @@
-use std::io::Read;
-
-use crate::{opt, parser::{opt::FlagType, parser::OptParser}};
-
-use super::util::InitConfigError;
-use crate::parser::opt::OptDescriptor;
-
-type Result<T> = std::result::Result<T, InitConfigError>;
-
-const L_FILE: &str = "file";
-const L_KEY: &str = "key";
-const L_FROM: &str = "from";
-const L_HELP: &str = "help";
-
-const S_FILE: &str = "f";
-const S_KEY: &str = "k";
-const S_FROM: &str = "F";
-const S_HELP: &str = "h";
-
-// Encrypt a message 
-// encrypt [OPTIONS] key_file message
-// key_file: file containing rsa key
-// message: message to be encrypted (can be from file, too)
-// 
-// OPTIONS:
-// -f, --file [file_name] specify if message should be saved to file, will be 'out' if 'file_name' is empty
-// -k, --key [private | public] if key for decryption is private or public (default is private) // NOT IMPLEMENTED
-// -F, --from message comes from file, otherwise will be string
-// -h, --help display help message for this command
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::error::Result;
+use crate::formats::Encoding;
+use crate::output::{FileMode, Output};
+use crate::padding::Padding;
+use crate::settings::Settings;
+
+use super::keys;
+use super::util::{self, KeyType};
+
+/// Options of encrypt and decrypt
+#[derive(Debug, Args)]
+struct CryptoOptions {
+    /// save the output to a file, 'out' if FILE_NAME is empty, '-' is stdout
+    #[arg(short, long, value_name = "FILE_NAME", num_args = 0..=1, default_missing_value = "out")]
+    file: Option<String>,
+    /// type of the key, public for encryption and private for decryption, the other way round is not implemented
+    #[arg(short, long, value_enum)]
+    key_type: Option<KeyType>,
+    /// name or fingerprint of a key in the keyring, used instead of KEY_FILE
+    #[arg(short = 'K', long = "key", value_name = "NAME")]
+    key_name: Option<String>,
+    /// the message is a file, otherwise it is the argument itself
+    #[arg(short = 'F', long = "from")]
+    from_file: bool,
+    /// padding scheme: none, pkcs1, oaep or oaep-sha384/-sha512, defaults to padding of the config,
+    /// or none, or oaep for CMS and recipients. Containers name their padding, it only has to be given for bare hex ciphertexts
+    #[arg(short, long)]
+    padding: Option<Padding>,
+    /// use a CMS EnvelopedData, the message is encrypted with AES and the key with RSA.
+    /// For encryption, KEY_FILE can be a certificate, for decryption MESSAGE is the file containing the EnvelopedData
+    #[arg(short, long)]
+    cms: bool,
+}
+
+/// Encrypt a message, the ciphertext is written as container with the padding and the fingerprint of the key,
+/// so decrypt can find the key and padding
+#[derive(Debug, Args)]
+#[command(allow_negative_numbers = true, allow_missing_positional = true)]
+pub struct EncryptArgs {
+    #[command(flatten)]
+    options: CryptoOptions,
+    /// armored (pem) or binary (der) container or EnvelopedData, defaults to output_format of the config
+    #[arg(short, long)]
+    encoding: Option<Encoding>,
+    /// write the bare ciphertext as hex instead of a container
+    #[arg(short = 'R', long, conflicts_with_all = ["cms", "recipients"])]
+    raw: bool,
+    /// encrypt for a recipient, can be given several times. The message is encrypted with AES-256-GCM
+    /// and its key with RSA for every recipient, so the message can be of any length.
+    /// It is read and written in chunks, so large messages can be piped through
+    #[arg(short, long = "recipient", value_name = "KEY_FILE", conflicts_with = "cms")]
+    recipients: Vec<String>,
+    /// overwrite the output file if it exists
+    #[arg(long, requires = "file")]
+    force: bool,
+    /// encrypt even for keys that are expired or revoked in the keyring
+    #[arg(long)]
+    allow_invalid_key: bool,
+    /// file containing the rsa key, not given with -r or --key
+    #[arg(required_unless_present_any = ["recipients", "key_name"], conflicts_with_all = ["recipients", "key_name"])]
+    key_file: Option<String>,
+    /// message to be encrypted, '-' reads it from stdin
+    message: String,
+}
+
+/// Decrypt a message
+#[derive(Debug, Args)]
+#[command(allow_negative_numbers = true, allow_missing_positional = true)]
+pub struct DecryptArgs {
+    #[command(flatten)]
+    options: CryptoOptions,
+    /// file containing the rsa key, or a directory containing the key the message was encrypted for, not given with --key
+    #[arg(required_unless_present = "key_name", conflicts_with = "key_name")]
+    key_file: Option<String>,
+    /// message to be decrypted, '-' reads it from stdin
+    message: String,
+    /// overwrite the output file if it exists
+    #[arg(long, requires = "file")]
+    force: bool,
+}
+
+/// Arguments of encrypt and decrypt
+#[derive(Debug)]
 pub struct CryptoConfig
 {
     key_file: String,
     message: String,
     from_file: bool,
     use_private: bool,
+    padding: Option<Padding>,
+    // padding of the config, used if none is given
+    default_padding: Option<Padding>,
+    cms: bool,
+    encoding: Encoding,
+    raw: bool,
+    recipients: Vec<String>,
     file: Option<String>,
-    print_help: bool,
+    // keys that are expired or revoked in the keyring are refused unless allowed
+    keyring_dir: PathBuf,
+    allow_invalid_key: bool,
+    // overwrite the output file
+    force: bool,
 }
 
-// message needs to be smaller than key size
 impl CryptoConfig {
-    pub fn init(args: &[String], do_encrypt: bool) -> Result<Self> {
-
-        // manually check if only help flag was given (not optimal, but watcha gonna do)
-        if args.len() < 3 {
-            for arg in args {
-                if arg == "-h" || arg == "--help" {
-                    return Err(InitConfigError { msg: if do_encrypt { encrypt::get_help_message()} else { decrypt::get_help_message() }});
-                }
-            }
-        }
-
-        let expected = vec![
-            opt!(S_FILE, L_FILE, FlagType::SingleArg(true)),
-            opt!(S_KEY, L_KEY, FlagType::SingleArg(false)),
-            opt!(S_FROM, L_FROM, FlagType::NoArg),
-            opt!(S_HELP, L_HELP, FlagType::NoArg),
-        ];
-
-
-
-        let message = match args.get(args.len().wrapping_sub(1)) {
-            Some(s) => s.trim().to_string(),
-            None => return Err(InitConfigError { msg: "Error, no argument for message provided".to_string()}),
+    pub fn encrypt(args: EncryptArgs, settings: &Settings) -> Result<Self> {
+        // with recipients or --key, the message is the only positional argument
+        let options = args.options;
+        let key_file = match (args.key_file, &options.key_name) {
+            (Some(key_file), _) => key_file,
+            (None, Some(name)) => {
+                let (keyring, metadata) = keys::find_encryption_key(settings, name)?;
+                keyring.public_key_path(&metadata).to_string_lossy().to_string()
+            },
+            (None, None) => String::new(),
         };
+        Ok(CryptoConfig {
+            key_file,
+            message: args.message,
+            from_file: options.from_file,
+            use_private: options.key_type.is_some_and(KeyType::is_private),
+            padding: options.padding,
+            default_padding: settings.padding,
+            cms: options.cms,
+            encoding: args.encoding.unwrap_or(settings.output_format),
+            raw: args.raw,
+            recipients: args.recipients,
+            file: options.file,
+            keyring_dir: settings.keyring_dir.clone(),
+            allow_invalid_key: args.allow_invalid_key,
+            force: args.force,
+        })
+    }
 
-        let key_file = match args.get(args.len().wrapping_sub(2)) {
-            Some(s) => s.clone(),
-            None => return Err(InitConfigError { msg: "Error, no argument for key file provided".to_string()}),
+    pub fn decrypt(args: DecryptArgs, settings: &Settings) -> Result<Self> {
+        let options = args.options;
+        let key_file = match (args.key_file, &options.key_name) {
+            (Some(key_file), _) => key_file,
+            (None, Some(name)) => {
+                let (keyring, metadata) = keys::find_encryption_key(settings, name)?;
+                keyring.private_key_path(&metadata)?.to_string_lossy().to_string()
+            },
+            (None, None) => unreachable!("the key file is required without --key"),
         };
+        Ok(CryptoConfig {
+            key_file,
+            message: args.message,
+            from_file: options.from_file,
+            use_private: options.key_type.is_none_or(KeyType::is_private),
+            padding: options.padding,
+            default_padding: settings.padding,
+            cms: options.cms,
+            encoding: Encoding::Pem,
+            raw: false,
+            recipients: vec![],
+            file: options.file,
+            keyring_dir: settings.keyring_dir.clone(),
+            allow_invalid_key: false,
+            force: args.force,
+        })
+    }
 
-        let parser = OptParser::new(&args[..args.len() - 2], expected);
-        let found_opts = parser.consume()?;
-        let mut file = None;
-        let mut use_private = if do_encrypt { false } else { true };
-        let mut from_file = false;
-        let mut print_help = false;
-
-        for opt in found_opts {
-            match opt.get_name() {
-                L_FILE => match opt.consume() {
-                    Some(file_name) => file = Some(file_name[0].clone()),
-                    None => file = Some("out".to_string()),
-                },
-                L_KEY => match opt.consume().unwrap()[0].as_str() {
-                    "public" => use_private = false,
-                    "private" => use_private = true,
-                    invalid => return Err(InitConfigError { msg: format!("Invalid parameter for -k/--key: {}, has to be 'public' or 'private'.", invalid)}),
-                },
-                L_FROM => from_file = true,
-                L_HELP => print_help = true,
-                invalid => return Err(InitConfigError { msg: format!("Parser returned invalid opt: {}", invalid) }),
-            }
+    /// Padding to use, CMS and recipients default to OAEP since textbook RSA can not transport keys.
+    /// The padding of the config is not checked against containers, they name their own padding.
+    fn padding(&self) -> Padding {
+        match (self.padding.or(self.default_padding), self.cms || !self.recipients.is_empty()) {
+            (Some(padding), _) => padding,
+            (None, true) => Padding::Oaep(crate::hash::HashAlgorithm::Sha256),
+            (None, false) => Padding::None,
         }
+    }
 
-        return Ok(CryptoConfig { key_file, message, from_file, use_private, file, print_help });
+    /// Reads the whole message, it can contain arbitrary bytes
+    fn get_message_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.open_message(self.from_file)?.read_to_end(&mut buf)?;
+        Ok(buf)
     }
 
-    fn get_message(&self) -> std::io::Result<String> {
-        if self.from_file {
-            let mut f = std::fs::File::open(&self.message)?;
-            let mut buf = String::new();
-            let _ = f.read_to_string(&mut buf)?;
-            Ok(buf)
-        } else {
-            Ok(self.message.clone())
+    /// Reader for the message: stdin for '-', the file if `from_file` is set, otherwise the message itself
+    fn open_message(&self, from_file: bool) -> Result<Box<dyn Read>> {
+        match (self.message.as_str(), from_file) {
+            ("-", _) => Ok(Box::new(std::io::stdin().lock())),
+            (file_name, true) => Ok(Box::new(File::open(file_name)?)),
+            (message, false) => Ok(Box::new(Cursor::new(message.as_bytes().to_vec()))),
         }
     }
-}
 
-pub mod encrypt {
-    use std::{io::Write, fs::File};
+    /// The file given with -f, None if the output goes to stdout
+    fn output_file(&self) -> Option<&str> {
+        self.file.as_deref().filter(|file_name| *file_name != "-")
+    }
 
-    use crate::{key_gen::{RsaPublicKey, RsaKey, self}, input_module};
+    /// Writer for the output, the file given with -f or stdout. A file is only replaced when the output is finished
+    fn open_output(&self) -> Result<Output> {
+        Output::open(self.output_file(), FileMode::Default, self.force)
+    }
 
-    use super::CryptoConfig;
+    /// Writes `data` to the file given with -f or to stdout
+    fn write_output(&self, data: &[u8]) -> Result<()> {
+        let mut output = self.open_output()?;
+        output.write_all(data)?;
+        output.finish()
+    }
+}
+
+pub mod encrypt {
+    use crate::{key_gen::{RsaKey, self}, input_module};
+    use crate::cms::{self, Recipient};
+    use crate::container::{self, Container, Envelope};
+    use crate::error::{Error, Result};
+    use crate::formats::{pem, Encoding};
+    use crate::formats::pem::PemWriter;
+
+    use super::{keys, util, CryptoConfig};
+
+    /// Encrypts the message once and the content key for every recipient, streaming the message
+    fn run_recipients(config: CryptoConfig) -> Result<()> {
+        let keys = config.recipients.iter().map(|file| util::read_public_key(file)).collect::<Result<Vec<_>>>()?;
+        for key in &keys {
+            keys::check_valid(&config.keyring_dir, key, config.allow_invalid_key)?;
+        }
+        let mut message = config.open_message(config.from_file)?;
+        let mut output = config.open_output()?;
+        match config.encoding {
+            Encoding::Pem => {
+                let mut armor = PemWriter::new(output, container::PEM_LABEL)?;
+                Envelope::seal_stream(&keys, config.padding(), &mut message, &mut armor)?;
+                armor.finish()?.finish()?;
+            },
+            Encoding::Der => {
+                Envelope::seal_stream(&keys, config.padding(), &mut message, &mut output)?;
+                output.finish()?;
+            },
+        }
+        if let Some(file_name) = config.output_file() {
+            println!("Stored cipher for {} recipients to {}", keys.len(), file_name);
+        }
+        Ok(())
+    }
 
-    #[inline(always)]
-    pub fn get_help_message() -> String {
-        "Usage:\n\n\
-        encrypt [OPTIONS] key_file message \n\
-        key_file: file containing rsa key\n\
-        message: message to be encrypted (can be from file, too)\n\n\
-        OPTIONS:\n\
-        -f, --file [file_name] specify if message should be saved to file, will be 'out' if 'file_name' is empty\n\
-        -k, --key [private | public] if key for encryption is private or public (default is private) // NOT IMPLEMENTED\n\
-        -F, --from message comes from file, otherwise will be string\n\
-        -h, --help display help message for this command\n".to_string()
+    /// Encrypts the message for the key or certificate in the key file
+    fn run_cms(config: CryptoConfig) -> Result<()> {
+        let recipient = match util::read_public_key_or_certificate(&config.key_file)? {
+            (_, Some(certificate)) => Recipient::from_certificate(&certificate),
+            (key, None) => Recipient::from_key(key),
+        };
+        keys::check_valid(&config.keyring_dir, &recipient.key, config.allow_invalid_key)?;
+        let der = cms::encrypt(&config.get_message_bytes()?, &[recipient], config.padding())?;
+        let encoded = match config.encoding {
+            Encoding::Pem => pem::encode(cms::PEM_LABEL, &der).into_bytes(),
+            Encoding::Der => der,
+        };
+        config.write_output(&encoded)?;
+        if let Some(file_name) = config.output_file() {
+            println!("Stored enveloped data to {}", file_name);
+        }
+        Ok(())
     }
     
-    pub fn run(config: CryptoConfig) {
-        if config.print_help {
-            println!("{}", get_help_message());
-            return;
-        }
+    pub fn run(config: CryptoConfig) -> Result<()> {
         let key = if config.use_private {
-            eprintln!("Error, encryption via private key is not yet implemented!");
-            return;
+            return Err(Error::InvalidArgs("Error, encryption via private key is not yet implemented!".to_string()));
+        } else if config.cms {
+            return run_cms(config);
+        } else if !config.recipients.is_empty() {
+            return run_recipients(config);
         } else {
-            match RsaPublicKey::from_file(&config.key_file) {
-                Ok(key) => key,
-                Err(_) => { 
-                    eprintln!("Unable to open key file {}, or file contains invalid public key", config.key_file);
-                    return;
-                }
-            }
+            util::read_public_key(&config.key_file)?
         };
+        keys::check_valid(&config.keyring_dir, &key, config.allow_invalid_key)?;
         
         // get message either from file or from config
-        let message = match config.get_message() {
-            Ok(m) => m,
-            Err(e) => {
-                eprintln!("Error while getting message from file: {}", e); 
-                return;
+        let message = config.get_message_bytes()?;
+
+        if !config.raw {
+            let container = Container::seal(&key, config.padding(), &message)?;
+            let encoded = match config.encoding {
+                Encoding::Pem => container.to_armor().into_bytes(),
+                Encoding::Der => container.to_bytes(),
+            };
+            config.write_output(&encoded)?;
+            if let Some(file_name) = config.output_file() {
+                println!("Stored cipher to {}", file_name);
             }
-        };
+            return Ok(());
+        }
         
-        // parse message to Integer, so it can be encrypted
-        let integer_message = input_module::string_to_number(message);
-        println!("{}", integer_message);
-        let cipher = key_gen::encrypt_msg(&integer_message, key);
-        let string_cipher = RsaPublicKey::into_hex(&cipher);
+        // pad message and parse it to Integer, so it can be encrypted
+        let k = key.size();
+        let encoded_message = config.padding().encode(&message, k)?;
+        let integer_message = input_module::os2ip(&encoded_message);
+        let cipher = key_gen::encrypt_msg(&integer_message, &key)?;
+        let string_cipher = input_module::to_hex(&input_module::i2osp(&cipher, k)?);
     
-        match config.file {
-            Some(file_name) => {
-                match File::create(&file_name) {
-                    Ok(mut f) => { 
-                        match f.write_all(string_cipher.as_bytes()) {
-                            Ok(_) => println!("Stored cipher to {}", file_name),
-                            Err(e) => eprintln!("Couldn't write cipher to file {}: {}", file_name, e)
-                        }
-                    },
-                    Err(_) => eprintln!("Unable to create file: {}", file_name),
-                }
+        match (config.output_file(), &config.file) {
+            (Some(file_name), _) => {
+                config.write_output(string_cipher.as_bytes())?;
+                println!("Stored cipher to {}", file_name);
             },
-            None => {
+            // '-' writes only the cipher, for pipes
+            (None, Some(_)) => config.write_output(string_cipher.as_bytes())?,
+            (None, None) => {
                 println!("Cipher is:\n{}", string_cipher)
             }
         }
+        Ok(())
     }
 }
 
@@ -185,89 +319,222 @@ pub mod encrypt {
 // then in run, branch according to decrypt/ encrypt. For help messages, consider adding boolean to switch words decrypt/encrypt
 // also implement both decryption/encryption with both keys. can be done by just calling different functions
 pub mod decrypt {
-    use std::{fs::File, io::Write};
+    use std::io::{BufReader, Cursor, Read};
 
     use rug::{Integer, Complete};
 
-    use crate::{key_gen::{RsaKey, self, RsaPrivateKey}, input_module};
+    use crate::{key_gen::{RsaKey, RsaPrivateKey, self}, input_module};
+    use crate::cms;
+    use crate::container::{self, Envelope, Message};
+    use crate::error::{Error, Result};
+    use crate::formats::pem::PemReader;
+
+    use super::{util, CryptoConfig, Padding};
+
+    /// Decrypts the EnvelopedData in the message file, the content can be binary
+    fn run_cms(config: CryptoConfig, key: &RsaPrivateKey) -> Result<()> {
+        let mut data = vec![];
+        config.open_message(true)?.read_to_end(&mut data)?;
+        config.write_output(&cms::decrypt(&data, key)?)?;
+        if let Some(file_name) = config.output_file() {
+            println!("Stored message to {}", file_name);
+        }
+        Ok(())
+    }
 
-    use super::CryptoConfig;
+    fn check_padding(config: &CryptoConfig, used: Padding) -> Result<()> {
+        match config.padding.filter(|padding| *padding != used) {
+            Some(padding) => Err(Error::KeyMismatch(format!("message was encrypted with {} padding, not with {}", used, padding))),
+            None => Ok(()),
+        }
+    }
 
-    #[inline(always)]
-    pub fn get_help_message() -> String {
-        "Usage:\n\n\
-        decrypt [OPTIONS] key_file message \n\
-        key_file: file containing rsa key\n\
-        message: message to be decrypt (can be from file, too)\n\n\
-        OPTIONS:\n\
-        -f, --file [file_name] specify if message should be saved to file, will be 'out' if 'file_name' is empty\n\
-        -k, --key [private | public] if key for decryption is private or public (default is private) // NOT IMPLEMENTED\n\
-        -F, --from message comes from file, otherwise will be string\n\
-        -h, --help display help message for this command\n".to_string()
+    /// Reads the first bytes of `input` and returns them together with a reader that still starts with them
+    fn peek<R: Read>(mut input: R, len: u64) -> Result<(Vec<u8>, impl Read)> {
+        let mut start = vec![];
+        (&mut input).take(len).read_to_end(&mut start)?;
+        Ok((start.clone(), Cursor::new(start).chain(input)))
     }
 
-    pub fn run(config: CryptoConfig) {
-        if config.print_help {
-            println!("{}", get_help_message());
-            return;
+    /// Writes the decrypted bytes to the output, only printing them on the terminal needs them to be utf8
+    fn write_message(config: &CryptoConfig, message: Vec<u8>) -> Result<()> {
+        match (config.output_file(), &config.file) {
+            (Some(file_name), _) => {
+                config.write_output(&message)?;
+                println!("Stored message to {}", file_name);
+            },
+            (None, Some(_)) => config.write_output(&message)?,
+            (None, None) => println!("Message is:\n{}", String::from_utf8(message)?),
+        }
+        Ok(())
+    }
+
+    /// Decrypts a container with the key and padding named in its header.
+    /// Version 2 messages are decrypted chunk by chunk, version 1 messages are short and read at once.
+    fn run_container<R: Read>(config: CryptoConfig, input: R) -> Result<()> {
+        let (start, mut input) = peek(input, container::MAGIC.len() as u64 + 1)?;
+        if start.last() != Some(&container::VERSION_HYBRID) {
+            let mut bytes = vec![];
+            input.read_to_end(&mut bytes)?;
+            let container = Message::decode(&bytes)?;
+            check_padding(&config, container.padding())?;
+            let key = util::read_private_key_matching(&config.key_file, &container.fingerprints())?;
+            return write_message(&config, container.open(&key)?);
         }
-        
-        let key = if config.use_private {
-            match RsaPrivateKey::from_file(&config.key_file) {
-                Ok(key) => key,
-                Err(_) => { 
-                    eprintln!("Unable to open key file {}, or file contains invalid public key", config.key_file);
-                    return;
-                }
-            }
-        } else {
-            eprintln!("Error, decryption via public key is not yet implemented!");
-            return;
-        };
 
-        let cipher = match config.get_message() {
-            Ok(m) => m,
-            Err(e) => {
-                eprintln!("Error while getting cipher from file: {}", e); 
-                return;
+        let envelope = Envelope::read_header(&mut input)?;
+        check_padding(&config, envelope.padding)?;
+        let key = util::read_private_key_matching(&config.key_file, &envelope.fingerprints())?;
+        let mut output = config.open_output()?;
+        envelope.open_stream(&key, &mut input, &mut output)?;
+        output.finish()?;
+        if let Some(file_name) = config.output_file() {
+            println!("Stored message to {}", file_name);
+        }
+        Ok(())
+    }
+
+    pub fn run(config: CryptoConfig) -> Result<()> {
+        if !config.use_private {
+            return Err(Error::InvalidArgs("Error, decryption via public key is not yet implemented!".to_string()));
+        }
+        if config.cms {
+            let key = util::read_private_key(&config.key_file)?;
+            return run_cms(config, &key);
+        }
+
+        // containers start with the magic or the armor, everything else is a bare hex ciphertext
+        let (start, mut input) = peek(BufReader::new(config.open_message(config.from_file)?), container::MAGIC.len() as u64)?;
+        if start == container::MAGIC {
+            return run_container(config, input);
+        }
+        if start.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'-') {
+            let armor = PemReader::new(BufReader::new(input))?;
+            if armor.label() != container::PEM_LABEL {
+                return Err(Error::Encoding(format!("expected a PEM block labelled {}, found '{}'", container::PEM_LABEL, armor.label())));
             }
-        };
+            return run_container(config, armor);
+        }
+        let key = util::read_private_key(&config.key_file)?;
+        let mut cipher = String::new();
+        input.read_to_string(&mut cipher)?;
 
-        let integer_cipher = match Integer::parse_radix(cipher, 16) {
+        let integer_cipher = match Integer::parse_radix(cipher.trim(), 16) {
             Ok(incomplete) => incomplete.complete(),
-            Err(_) => {
-                eprintln!("Unable to parse cipher to integer.");
-                return;
-            }
+            Err(_) => return Err(Error::InvalidArgs("Unable to parse cipher to integer.".to_string())),
         };
 
-        let integer_message = key_gen::decrypt_cypher(&integer_cipher, key);
-        println!("{}", integer_message);
-        let message = match input_module::number_to_string(integer_message) {
-            Ok(m) => m,
-            Err(_) => {
-                eprintln!("Couldn't convert message to string. Original message may have contained invalid utf8");
-                return;
-            }
-        };
+        let k = key.size();
+        let integer_message = key_gen::decrypt_cypher(&integer_cipher, &key)?;
+        let encoded_message = input_module::i2osp(&integer_message, k)?;
+        write_message(&config, config.padding().decode(&encoded_message, k)?)
+    }
+}
 
-        match config.file {
-            Some(file_name) => {
-                match File::create(&file_name) {
-                    Ok(mut f) => { 
-                        match f.write_all(message.as_bytes()) {
-                            Ok(_) => println!("Stored message to {}", file_name),
-                            Err(e) => eprintln!("Couldn't write message to file {}: {}", file_name, e)
-                        }
-                    },
-                    Err(_) => eprintln!("Unable to create file: {}", file_name),
-                }
-            },
-            None => {
-                println!("Message is:\n{}", message)
-            }
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+    use clap::CommandFactory;
+
+    use super::CryptoConfig;
+    use crate::cli::{parse, Cli, Command};
+    use crate::error::Error;
+    use crate::formats::LoadedKey;
+    use crate::key_gen::generate_key_pair;
+    use crate::keyring::{KeyMetadata, Keyring, Usage};
+    use crate::padding::Padding;
+    use crate::settings::Settings;
+
+    /// Parses the arguments of encrypt or decrypt
+    fn parse_crypto(args: &[&str]) -> Result<CryptoConfig, clap::Error> {
+        match parse(args)? {
+            Command::Encrypt(args) => Ok(CryptoConfig::encrypt(args, &Settings::default()).unwrap()),
+            Command::Decrypt(args) => Ok(CryptoConfig::decrypt(args, &Settings::default()).unwrap()),
+            command => panic!("parsed {:?}", command),
         }
     }
-}
 
-// TODO testitests
\ No newline at end of file
+    #[test]
+    fn test_init_recipients() {
+        let config = parse_crypto(&["encrypt", "-r", "pk1", "--recipient", "pk2", "-F", "message_file"]).unwrap();
+        assert_eq!(config.recipients, vec!["pk1", "pk2"]);
+        assert_eq!(config.message, "message_file");
+        assert!(matches!(config.padding(), Padding::Oaep(_)));
+        assert!(parse_crypto(&["encrypt", "-R", "-r", "pk1", "message"]).is_err());
+        assert_eq!(parse_crypto(&["encrypt", "-r", "pk1", "pk2", "message"]).unwrap_err().kind(), ErrorKind::ArgumentConflict);
+
+        let config = parse_crypto(&["encrypt", "-p", "pkcs1", "pk1", "message"]).unwrap();
+        assert_eq!(config.key_file, "pk1");
+        assert!(config.recipients.is_empty());
+        assert_eq!(config.padding(), Padding::Pkcs1);
+    }
+
+    #[test]
+    fn test_init_positionals() {
+        // options can follow the positional arguments
+        let config = parse_crypto(&["decrypt", "sk1", "cipher", "-F", "--padding=oaep"]).unwrap();
+        assert_eq!((config.key_file.as_str(), config.message.as_str()), ("sk1", "cipher"));
+        assert!(config.from_file && config.use_private);
+        assert_eq!(config.padding, Some(Padding::Oaep(crate::hash::HashAlgorithm::Sha256)));
+        // the positional arguments used to be taken from the end, even if they were flags
+        assert_eq!(parse_crypto(&["encrypt", "-F", "-c"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        assert_eq!(parse_crypto(&["encrypt", "pk1"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        // inline messages are used as they are
+        assert_eq!(parse_crypto(&["encrypt", "pk1", " message\n"]).unwrap().message, " message\n");
+
+        let usage = Cli::command().find_subcommand_mut("encrypt").unwrap().render_usage().to_string();
+        assert!(usage.ends_with("[KEY_FILE] <MESSAGE>"), "{}", usage);
+    }
+
+    #[test]
+    fn test_init_stdio() {
+        let config = parse_crypto(&["encrypt", "-f", "-", "-r", "pk1", "-"]).unwrap();
+        assert_eq!(config.file.as_deref(), Some("-"));
+        assert_eq!(config.output_file(), None);
+        assert_eq!(config.message, "-");
+
+        let config = parse_crypto(&["encrypt", "-f", "cipher", "pk1", "-"]).unwrap();
+        assert_eq!(config.output_file(), Some("cipher"));
+        let config = parse_crypto(&["encrypt", "pk1", "message", "-f"]).unwrap();
+        assert_eq!(config.output_file(), Some("out"));
+    }
+
+    #[test]
+    fn test_init_keyring() {
+        let mut settings = Settings::default();
+        settings.keyring_dir = std::env::temp_dir().join(format!("rust_rsa_keyring_crypto_{}", std::process::id()));
+        let keyring = Keyring::open(&settings.keyring_dir);
+        let (sk, _) = generate_key_pair(256, 2);
+        let metadata = KeyMetadata::new("alice", &sk, vec![Usage::Encrypt], "", None);
+        keyring.add(&LoadedKey::Private(sk), &metadata).unwrap();
+        let (signing, _) = generate_key_pair(256, 2);
+        let metadata = KeyMetadata::new("carol", &signing, vec![Usage::Sign], "", None);
+        keyring.add(&LoadedKey::Private(signing), &metadata).unwrap();
+
+        let encrypt = |args: &[&str]| match parse(args).unwrap() {
+            Command::Encrypt(args) => CryptoConfig::encrypt(args, &settings),
+            command => panic!("parsed {:?}", command),
+        };
+        let config = encrypt(&["encrypt", "--key", "alice", "message"]).unwrap();
+        assert!(config.key_file.ends_with("public.pem"));
+        assert_eq!(config.message, "message");
+        // the usage of the key has to allow encryption
+        assert!(matches!(encrypt(&["encrypt", "-K", "carol", "message"]), Err(Error::InvalidKey(_))));
+        assert!(encrypt(&["encrypt", "-K", "dave", "message"]).is_err());
+        assert_eq!(parse(&["encrypt", "-K", "alice", "pk1", "message"]).unwrap_err().kind(), ErrorKind::ArgumentConflict);
+        // overwriting the output doesn't allow invalid keys
+        let config = encrypt(&["encrypt", "-K", "alice", "-f", "out", "--force", "message"]).unwrap();
+        assert!(config.force && !config.allow_invalid_key);
+        assert!(encrypt(&["encrypt", "-K", "alice", "--allow-invalid-key", "message"]).unwrap().allow_invalid_key);
+        assert_eq!(parse(&["encrypt", "--force", "pk1", "message"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+
+        let config = match parse(&["decrypt", "-K", "alice", "cipher"]).unwrap() {
+            Command::Decrypt(args) => CryptoConfig::decrypt(args, &settings).unwrap(),
+            command => panic!("parsed {:?}", command),
+        };
+        assert!(config.key_file.ends_with("private.pem"));
+        assert_eq!(config.message, "cipher");
+        assert_eq!(parse(&["decrypt", "cipher"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        std::fs::remove_dir_all(keyring.dir()).unwrap();
+    }
+}
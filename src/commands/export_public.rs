@@ -0,0 +1,77 @@
+use std::io::Write;
+
+use clap::Args;
+
+use crate::error::Result;
+use crate::formats::{self, Encoding, KeyFormat};
+use crate::output::{self, FileMode};
+use crate::settings::Settings;
+
+use super::util;
+
+/// Derive the public key from a private key
+#[derive(Debug, Args)]
+pub struct ExportPublicConfig {
+    /// format of the public key: legacy, pkcs1, spki, openssh or jwk
+    #[arg(short = 'o', long = "out-format", default_value = "legacy")]
+    format: KeyFormat,
+    /// encoding for pkcs1 and spki, defaults to output_format of the config
+    #[arg(short, long)]
+    encoding: Option<Encoding>,
+    /// save public key to file, otherwise it is printed
+    #[arg(short, long, value_name = "FILE_NAME")]
+    file: Option<String>,
+    /// overwrite FILE_NAME if it exists
+    #[arg(long, requires = "file")]
+    force: bool,
+    /// file containing the private key
+    key_file: String,
+}
+
+pub fn run(config: ExportPublicConfig, settings: &Settings) -> Result<()> {
+    let encoding = config.encoding.unwrap_or(settings.output_format);
+    let private_key = util::read_private_key(&config.key_file)?;
+    let encoded = formats::encode_public_key(&private_key.to_public_key(), config.format, encoding)?;
+
+    match config.file {
+        Some(file_name) => {
+            output::write_file(&file_name, &encoded, FileMode::Default, config.force)?;
+            println!("Wrote public key to file: {}", file_name);
+        },
+        None => std::io::stdout().write_all(&encoded)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExportPublicConfig;
+    use crate::cli::{parse, Command};
+    use crate::formats::{Encoding, KeyFormat};
+
+    fn parse_export_public(args: &[&str]) -> Result<ExportPublicConfig, clap::Error> {
+        match parse(&[&["export-public"], args].concat())? {
+            Command::ExportPublic(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_init() {
+        let config = parse_export_public(&["-o", "spki", "-e", "der", "-f", "pk.der", "sk_file"]).unwrap();
+        assert_eq!(config.format, KeyFormat::Spki);
+        assert_eq!(config.encoding, Some(Encoding::Der));
+        assert_eq!(config.file.unwrap(), "pk.der");
+        assert_eq!(config.key_file, "sk_file");
+
+        let config = parse_export_public(&["sk_file"]).unwrap();
+        assert_eq!(config.format, KeyFormat::Legacy);
+        assert_eq!(config.encoding, None);
+    }
+
+    #[test]
+    fn test_init_invalid_format() {
+        let error = parse_export_public(&["-o", "xml", "sk_file"]).unwrap_err();
+        assert!(error.to_string().contains("Invalid key format: xml, has to be 'legacy', 'pkcs1', 'pkcs8', 'spki', 'openssh' or 'jwk'."));
+    }
+}
@@ -1,139 +1,132 @@
-use std::io::Write;
-
-use crate::{commands::util::*, opt, parser::{opt::FlagType, parser::{OptParser}}, key_gen::{self, RsaKey}};
-use crate::parser::opt::OptDescriptor;
-use chrono::Local;
+use clap::{ArgGroup, Args};
+use chrono::{Duration, Local, Utc};
+use zeroize::Zeroizing;
+
+use crate::key_gen::{self, PublicExponent, RsaKey};
+use crate::error::Result;
+use crate::formats::LoadedKey;
+use crate::input_module::to_hex;
+use crate::keyring::{KeyMetadata, Keyring, Usage};
+use crate::output;
+use crate::settings::Settings;
 
 use super::util;
 
-type Result<T> = std::result::Result<T, InitConfigError>;
-
-const L_SIZE: &str = "size";
-const L_FILE: &str = "file";
-const L_HELP: &str = "help";
-
-const S_SIZE: &str = "s";
-const S_FILE: &str = "f";
-const S_HELP: &str = "h";
-
-#[derive(Debug)]
-pub struct InitGenerateError {
-    msg: String,
+/// Generate a key pair
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("output").required(true).args(["file", "name", "print_private"])))]
+pub struct GenerateConfig {
+    /// length of the key in bits, only powers of 2 permitted, defaults to key_size of the config
+    #[arg(short, long, value_parser = util::parse_bit_size)]
+    size: Option<u32>,
+    /// public exponent: random or an odd number like 65537, defaults to exponent of the config
+    #[arg(short = 'E', long)]
+    exponent: Option<PublicExponent>,
+    /// save keypair to files sk_<FILE_NAME> and pk_<FILE_NAME>, if FILE_NAME is empty, it is the creation date
+    #[arg(short, long, value_name = "FILE_NAME")]
+    file: Option<Option<String>>,
+    /// overwrite the key files if they exist
+    #[arg(long, requires = "file")]
+    force: bool,
+    /// add the key pair to the keyring under NAME instead of printing it
+    #[arg(short, long, conflicts_with = "file")]
+    name: Option<String>,
+    /// comment of the key in the keyring
+    #[arg(short, long, requires = "name", default_value = "")]
+    comment: String,
+    /// number of days until the key in the keyring expires, at most 36500
+    #[arg(short = 'x', long, value_name = "DAYS", requires = "name", value_parser = clap::value_parser!(i64).range(1..=36500))]
+    expires: Option<i64>,
+    /// print the private key to stdout
+    #[arg(long)]
+    print_private: bool,
 }
 
-impl InitGenerateError {
-    pub fn get_msg(&self) -> &str {
-        &self.msg
+pub fn run(config: GenerateConfig, settings: &Settings) -> Result<()> {
+    let size = config.size.unwrap_or(settings.key_size);
+    let exponent = config.exponent.unwrap_or(settings.exponent);
+    let file = config.file.map(|name| name.unwrap_or_else(|| Local::now().format("%y-%m-%dT%H:%M").to_string()));
+    // fail before the key is generated, which can take a while
+    if let Some(file_name) = &file {
+        output::check_overwrite(format!("sk_{}", file_name), config.force)?;
+        output::check_overwrite(format!("pk_{}", file_name), config.force)?;
     }
-}
-// Generate a key pair, default size is set to 2048 bit
-// generate [OPTIONS]
-// OPTIONS:
-// -s, --size n specify length n of key in bits, only powers of 2 permitted
-// -f, --file file_name save keypair to file, if file_name is empty, create file with date and size.
-// -h, --help display help message for this command
-// show help for this command
-pub struct GenerateConfig {
-    size: u32,
-    file: Option<String>,
-    print_help: bool,
+    println!("Generating {} bit key pair...", size);
+    let (sk, pk) = key_gen::generate_key_pair_with_exponent(size, num_cpus::get_physical(), exponent);
+
+    if let Some(name) = config.name {
+        let expires = config.expires.map(|days| Utc::now() + Duration::days(days));
+        let metadata = KeyMetadata::new(&name, &sk, vec![Usage::Encrypt, Usage::Sign], &config.comment, expires);
+        Keyring::open(&settings.keyring_dir).add(&LoadedKey::Private(sk), &metadata)?;
+        println!("Added key {} with fingerprint {} to the keyring", name, to_hex(&metadata.fingerprint));
+        return Ok(());
+    }
+
+    match file {
+        Some(file_name) => {
+            // the private key is only readable by the owner
+            sk.write_to_file(&format!("sk_{}", file_name), config.force)?;
+            println!("Wrote private key to file: sk_{}", file_name);
+            pk.write_to_file(&format!("pk_{}", file_name), config.force)?;
+            println!("Wrote public key to file: pk_{}", file_name);
+        },
+        None => {
+            println!("RSA Keys:");
+            println!("{}", *Zeroizing::new(sk.serialize()))
+        },
+    }
+    Ok(())
 }
 
-impl GenerateConfig {
-    pub fn init(args: &[String]) -> Result<Self> {
-        let expected = vec![
-            opt!(S_SIZE, L_SIZE, FlagType::MultiArg(false)),
-            opt!(S_FILE, L_FILE, FlagType::SingleArg(true)),
-            opt!(S_HELP, L_HELP, FlagType::NoArg),
-        ];
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
 
-        // get parser and get all opts
-        let parser = OptParser::new(args, expected);
-        let found_opts = parser.consume()?;
+    use crate::cli::{parse, Command};
 
-        // set default values
-        let mut size = 2048;
-        let mut file = None;
-        let mut print_help = false;
-        // there is a bug, where if file is parsed before size, that size will be invalid 
-        for opt in found_opts {
-            match opt.get_name() {
-                L_SIZE => size = Self::parse_bit_size(opt.consume().unwrap()[0].clone())?,
-                L_FILE =>  match opt.consume() {
-                    Some(name) => file = Some(util::verify_file_name(&name[0])?),
-                    None => {
-                        let s = Local::now().format("%y-%m-%dT%H:%M").to_string();
-                        file = Some(format!("{}", s)); 
-                    }
-                },
-                L_HELP => print_help = true,
-                invalid => {
-                    return Err(InitConfigError{ msg: format!("Parser returned invalid argument: {}", invalid) })
-                }
-            }
-        }
+    use super::GenerateConfig;
+    use crate::key_gen::PublicExponent;
 
-        Ok(GenerateConfig { size, file, print_help })
+    fn parse_generate(args: &[&str]) -> Result<GenerateConfig, clap::Error> {
+        match parse(&[&["generate"], args].concat())? {
+            Command::Generate(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
     }
 
-    fn parse_bit_size(size: String) -> Result<u32> {
-        match size.parse::<u32>() {
-            Ok(n) => {
-                if util::is_valid_bit_size(n) {
-                    Ok(n)
-                } else {
-                    Err(InitConfigError{ msg: format!("Invalid key size: {}", n)})
-                }
-            }
-            Err(_) => Err(InitConfigError{ msg: format!("Unable to parse input to number: {}", size)}),
+    #[test]
+    fn test_init_order() {
+        // the size used to be ignored if it came after the file
+        for args in [["-f", "name", "-s", "1024"], ["-s", "1024", "-f", "name"]] {
+            let config = parse_generate(&args).unwrap();
+            assert_eq!(config.size, Some(1024));
+            assert_eq!(config.file, Some(Some("name".to_string())));
         }
+        let config = parse_generate(&["--size=512", "-f"]).unwrap();
+        assert_eq!(config.size, Some(512));
+        assert_eq!(config.file, Some(None));
     }
 
-}
-
-fn get_help_message() -> String {
-    "Usage:\n\n\
-    generate [OPTIONS]\n\n\
-    OPTIONS:\n\
-    -s, --size n specify length n of key in bits, only powers of 2 permitted\n\
-    -f, --file file_name save keypair to file, if file_name is empty, create file with name = creation date\n\
-    -h, --help display help message for this command".to_string()
-}
-
-
-pub fn run(config: GenerateConfig) {
-    if config.print_help {
-        return println!("{}", get_help_message());
-    }
-    println!("Generating {} bit key pair...", config.size);
-    let (sk, pk) = key_gen::generate_key_pair(config.size, num_cpus::get_physical());    
-    
-    match config.file {
-        Some(file_name) => {
-            // write secret key
-            write_to_file(sk, true, &file_name);
-            write_to_file(pk, false, &file_name);
-        }, 
-        None => {
-            println!("RSA Keys:");
-            println!("{}", sk.serialize())
-        },
+    #[test]
+    fn test_init_defaults() {
+        let config = parse_generate(&["--print-private"]).unwrap();
+        assert_eq!(config.size, None);
+        assert_eq!(config.exponent, None);
+        assert_eq!(config.file, None);
+        assert_eq!(config.name, None);
+        assert!(parse_generate(&["-s", "1000", "--print-private"]).is_err());
+        assert_eq!(parse_generate(&["-E", "65537", "-f"]).unwrap().exponent, Some(PublicExponent::Fixed(65537)));
+        assert!(parse_generate(&["-E", "4", "-f"]).is_err());
+        // the private key is only printed when asked for
+        assert_eq!(parse_generate(&[]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        assert_eq!(parse_generate(&["-f", "--print-private"]).unwrap_err().kind(), ErrorKind::ArgumentConflict);
+        assert_eq!(parse_generate(&["-n", "alice", "-c", "work"]).unwrap().name.as_deref(), Some("alice"));
+        assert!(parse_generate(&["-n", "alice", "-f", "name"]).is_err());
+        assert!(parse_generate(&["-c", "work"]).is_err());
+        assert_eq!(parse_generate(&["-n", "alice", "-x", "365"]).unwrap().expires, Some(365));
+        assert!(parse_generate(&["-x", "365"]).is_err());
+        assert!(parse_generate(&["-n", "alice", "-x", "36501"]).is_err());
+        assert!(parse_generate(&["-f", "name", "--force"]).unwrap().force);
+        assert!(parse_generate(&["--force"]).is_err());
     }
 }
-
-fn write_to_file<K: RsaKey>(key: K, is_private: bool, file_name: &str) {
-    let (prefix, key_type) = if is_private { ("sk_".to_string(), "private") } else { ("pk_".to_string(), "public key") }; 
-    let file_name = prefix + &file_name;
-    let f_pk = std::fs::File::create(&file_name);
-            match f_pk {
-                Ok(mut f) => {
-                    let key_string = key.serialize();
-                    let _ = f.write_all(key_string.as_bytes());
-                    println!("Wrote {key_type} to file: {file_name}");
-                },
-                Err(e) => {
-                    eprintln!("Error creating {key_type} file: {e}.\nKey has not been saved")
-                }
-            } 
-}
-
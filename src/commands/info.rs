@@ -0,0 +1,109 @@
+use clap::Args;
+use serde_json::json;
+
+use crate::error::Result;
+use crate::input_module::to_hex;
+use crate::key_gen::RsaKey;
+
+use super::util::{self, KeyType, LoadedKey};
+
+/// Show information about a key
+#[derive(Debug, Args)]
+pub struct InfoConfig {
+    /// type of the key, detected from the file if not given
+    #[arg(short, long)]
+    key_type: Option<KeyType>,
+    /// print information as json
+    #[arg(short, long)]
+    json: bool,
+    /// file containing rsa key
+    key_file: String,
+}
+
+/// Formats a fingerprint as colon separated hex bytes
+fn colon_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<String>>().join(":")
+}
+
+pub fn key_info<K: RsaKey>(key_file: &str, key: &K, as_json: bool) -> String {
+    let key_type = if key.is_private() { "private" } else { "public" };
+    let bits = key.modulus().significant_bits();
+    // the public exponent is chosen randomly when generating keys, so it can be as large as the modulus
+    let exponent = if key.public_exponent().significant_bits() <= 64 {
+        key.public_exponent().to_string()
+    } else {
+        format!("0x{}", key.public_exponent().to_string_radix(16))
+    };
+
+    if as_json {
+        json!({
+            "file": key_file,
+            "type": key_type,
+            "modulus_bits": bits,
+            "public_exponent": key.public_exponent().to_string(),
+            "spki_sha256": to_hex(&key.fingerprint()),
+            "ssh_fingerprint": key.ssh_fingerprint(),
+            "crt_params": key.has_crt_params(),
+        }).to_string()
+    } else {
+        format!(
+            "Key file:        {}\n\
+            Type:            {}\n\
+            Modulus size:    {} bit\n\
+            Public exponent: {}\n\
+            SPKI SHA-256:    {}\n\
+            SSH fingerprint: {}\n\
+            CRT parameters:  {}",
+            key_file, key_type, bits, exponent, colon_hex(&key.fingerprint()), key.ssh_fingerprint(),
+            if key.has_crt_params() { "present" } else { "not present" }
+        )
+    }
+}
+
+pub fn run(config: InfoConfig) -> Result<()> {
+    let info = match util::load_key(&config.key_file, config.key_type.map(KeyType::is_private))? {
+        LoadedKey::Private(key) => key_info(&config.key_file, &key, config.json),
+        LoadedKey::Public(key) => key_info(&config.key_file, &key, config.json),
+    };
+    println!("{}", info);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+
+    use super::key_info;
+    use crate::cli::{parse, Command};
+    use crate::commands::util::KeyType;
+    use crate::key_gen::RsaPublicKey;
+
+    #[test]
+    fn test_init() {
+        let config = match parse(&["info", "--json", "pk_file"]).unwrap() {
+            Command::Info(config) => config,
+            command => panic!("parsed {:?}", command),
+        };
+        assert!(config.json);
+        assert_eq!(config.key_file, "pk_file");
+        assert_eq!(config.key_type, None);
+
+        match parse(&["info", "--key-type", "private", "sk_file"]).unwrap() {
+            Command::Info(config) => assert_eq!(config.key_type, Some(KeyType::Private)),
+            command => panic!("parsed {:?}", command),
+        }
+        // --key names a key in the keyring elsewhere
+        assert!(parse(&["info", "--key", "private", "sk_file"]).is_err());
+    }
+
+    #[test]
+    fn test_key_info_json() {
+        let key = RsaPublicKey::new(Integer::from(3233), Integer::from(17));
+        let info: serde_json::Value = serde_json::from_str(&key_info("pk", &key, true)).unwrap();
+        assert_eq!(info["type"], "public");
+        assert_eq!(info["modulus_bits"], 12);
+        assert_eq!(info["public_exponent"], "17");
+        assert_eq!(info["crt_params"], false);
+        assert!(info["ssh_fingerprint"].as_str().unwrap().starts_with("SHA256:"));
+    }
+}
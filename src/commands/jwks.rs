@@ -0,0 +1,80 @@
+use std::io::Write;
+
+use clap::Args;
+
+use crate::error::Result;
+use crate::formats::jwk;
+use crate::output::{self, FileMode};
+
+use super::util;
+
+/// Build a JWK Set (RFC 7517 section 5) from public keys, e.g. to publish them on a jwks_uri
+#[derive(Debug, Args)]
+pub struct JwksConfig {
+    /// files containing the keys, for private keys only the public part is used
+    #[arg(short, long = "keys", value_name = "KEY_FILE", num_args = 1.., required = true)]
+    key_files: Vec<String>,
+    /// value of the 'alg' member of every key, e.g. RS256, omitted if not given
+    #[arg(short, long)]
+    alg: Option<String>,
+    /// save the JWK Set to file, otherwise it is printed
+    #[arg(short, long, value_name = "FILE_NAME")]
+    file: Option<String>,
+    /// overwrite FILE_NAME if it exists
+    #[arg(long, requires = "file")]
+    force: bool,
+}
+
+/// JWK Set with the public keys of all files, using their thumbprints as `kid`
+pub fn build_jwk_set(key_files: &[String], alg: Option<&str>) -> Result<String> {
+    let keys = key_files
+        .iter()
+        .map(|file| Ok(jwk::public_key_to_jwk(&util::read_public_key(file)?, alg)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(jwk::to_string(&jwk::jwk_set(keys)))
+}
+
+pub fn run(config: JwksConfig) -> Result<()> {
+    let jwks = build_jwk_set(&config.key_files, config.alg.as_deref())?;
+    match config.file {
+        Some(file_name) => {
+            output::write_file(&file_name, jwks.as_bytes(), FileMode::Default, config.force)?;
+            println!("Wrote JWK Set with {} keys to file: {}", config.key_files.len(), file_name);
+        },
+        None => std::io::stdout().write_all(jwks.as_bytes())?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_jwk_set, JwksConfig};
+    use crate::cli::{parse, Command};
+    use crate::formats::jwk;
+
+    fn parse_jwks(args: &[&str]) -> Result<JwksConfig, clap::Error> {
+        match parse(&[&["jwks"], args].concat())? {
+            Command::Jwks(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_init() {
+        let config = parse_jwks(&["-k", "pk1", "pk2", "-a", "RS256"]).unwrap();
+        assert_eq!(config.key_files, vec!["pk1", "pk2"]);
+        assert_eq!(config.alg.as_deref(), Some("RS256"));
+        assert!(parse_jwks(&["-a", "RS256"]).is_err());
+    }
+
+    #[test]
+    fn test_build_jwk_set() {
+        let files = ["pk_22-06-06T20:51", "sk_22-06-06T20:51"].map(String::from);
+        let keys = jwk::from_json(&build_jwk_set(&files, Some("RS256")).unwrap()).unwrap();
+        assert_eq!(keys.len(), 2);
+        // the private key only contributes its public part, so both entries are the same key
+        assert_eq!(keys[0].kid, keys[1].kid);
+        assert!(matches!(keys[1].key, crate::formats::LoadedKey::Public(_)));
+        assert_eq!(keys[0].alg.as_deref(), Some("RS256"));
+    }
+}
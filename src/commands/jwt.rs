@@ -0,0 +1,154 @@
+use clap::{Args, Subcommand};
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+use crate::formats::jwk;
+use crate::jwt::{self, JwtAlgorithm, Validation};
+use crate::output::{self, FileMode};
+
+use super::util;
+
+#[derive(Debug, Subcommand)]
+pub enum JwtCommand {
+    /// Sign claims as JWT
+    Sign(JwtSignConfig),
+    /// Verify a JWT and print its claims
+    Verify(JwtVerifyConfig),
+}
+
+/// Reads `input` from a file if `from_file` is set, otherwise it is the value itself
+fn read_input(input: &str, from_file: bool) -> Result<String> {
+    match from_file {
+        true => Ok(std::fs::read_to_string(input)?.trim().to_string()),
+        false => Ok(input.to_string()),
+    }
+}
+
+/// Sign claims as JWT
+#[derive(Debug, Args)]
+#[command(allow_negative_numbers = true)]
+pub struct JwtSignConfig {
+    /// signature algorithm: RS256, RS384, RS512 or PS256
+    #[arg(short, long, default_value = "RS256")]
+    alg: JwtAlgorithm,
+    /// set 'iat' to now and 'exp' to now + SECONDS
+    #[arg(short, long, value_name = "SECONDS")]
+    expires: Option<i64>,
+    /// claims come from file, otherwise they are the argument
+    #[arg(short = 'F', long = "from")]
+    from_file: bool,
+    /// save token to file, otherwise it is printed
+    #[arg(short, long, value_name = "FILE_NAME")]
+    file: Option<String>,
+    /// overwrite FILE_NAME if it exists
+    #[arg(long, requires = "file")]
+    force: bool,
+    /// file containing the private key
+    key_file: String,
+    /// JSON object with the claims (can be from file, too)
+    claims: String,
+}
+
+/// The thumbprint of the key is used as `kid`, so verifiers can find the key in a JWK Set
+pub fn run_sign(config: JwtSignConfig) -> Result<()> {
+    let key = util::read_private_key(&config.key_file)?;
+    let mut claims: Value = serde_json::from_str(&read_input(&config.claims, config.from_file)?)
+        .map_err(|e| Error::InvalidArgs(format!("Error, claims are not valid JSON: {}", e)))?;
+    if let (Some(seconds), Some(object)) = (config.expires, claims.as_object_mut()) {
+        let now = chrono::Utc::now().timestamp();
+        object.insert("iat".to_string(), json!(now));
+        let exp = now.checked_add(seconds).ok_or_else(|| Error::InvalidArgs(format!("Error, --expires {} is out of range", seconds)))?;
+        object.insert("exp".to_string(), json!(exp));
+    }
+    let token = jwt::sign(&claims, &key, config.alg, Some(&jwk::thumbprint(&key)))?;
+
+    match config.file {
+        Some(file_name) => {
+            output::write_file(&file_name, token.as_bytes(), FileMode::Default, config.force)?;
+            println!("Stored token to {}", file_name);
+        },
+        None => println!("{}", token),
+    }
+    Ok(())
+}
+
+/// Verify a JWT and print its claims
+#[derive(Debug, Args)]
+#[command(allow_negative_numbers = true)]
+pub struct JwtVerifyConfig {
+    /// accepted algorithms, separated by commas
+    #[arg(short = 'a', long = "alg", value_delimiter = ',', default_value = "RS256")]
+    algorithms: Vec<JwtAlgorithm>,
+    /// tolerated clock skew for 'exp' and 'nbf'
+    #[arg(short, long, value_name = "SECONDS", default_value_t = 0)]
+    leeway: i64,
+    /// token comes from file, otherwise it is the argument
+    #[arg(short = 'F', long = "from")]
+    from_file: bool,
+    /// file containing the public key
+    key_file: String,
+    /// the JWT (can be from file, too)
+    token: String,
+}
+
+pub fn run_verify(config: JwtVerifyConfig) -> Result<()> {
+    let key = util::read_public_key(&config.key_file)?;
+    let token = read_input(&config.token, config.from_file)?;
+    let mut validation = Validation::new(config.algorithms);
+    validation.leeway = config.leeway;
+    let claims = jwt::verify(&token, &key, &validation)?;
+    println!("Token is valid, claims:\n{}", serde_json::to_string_pretty(&claims).unwrap_or_default());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+
+    use super::{JwtCommand, JwtSignConfig, JwtVerifyConfig};
+    use crate::cli::{parse, Command};
+    use crate::jwt::JwtAlgorithm;
+
+    fn parse_jwt(args: &[&str]) -> Result<JwtCommand, clap::Error> {
+        match parse(&[&["jwt"], args].concat())? {
+            Command::Jwt(command) => Ok(command),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    fn parse_sign(args: &[&str]) -> Result<JwtSignConfig, clap::Error> {
+        match parse_jwt(&[&["sign"], args].concat())? {
+            JwtCommand::Sign(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    fn parse_verify(args: &[&str]) -> Result<JwtVerifyConfig, clap::Error> {
+        match parse_jwt(&[&["verify"], args].concat())? {
+            JwtCommand::Verify(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_init_sign() {
+        let config = parse_sign(&["-a", "PS256", "-e", "3600", "sk_file", "{\"sub\":\"me\"}"]).unwrap();
+        assert_eq!(config.alg, JwtAlgorithm::Ps256);
+        assert_eq!(config.expires, Some(3600));
+        assert_eq!(config.key_file, "sk_file");
+        assert_eq!(config.claims, "{\"sub\":\"me\"}");
+        assert_eq!(parse_sign(&["--expires=-60", "sk_file", "{}"]).unwrap().expires, Some(-60));
+        assert!(parse_sign(&["-a", "none", "sk_file", "{}"]).is_err());
+        assert_eq!(parse_sign(&["-h"]).unwrap_err().kind(), ErrorKind::DisplayHelp);
+    }
+
+    #[test]
+    fn test_init_verify() {
+        let config = parse_verify(&["-a", "RS256,PS256", "-l", "30", "-F", "pk_file", "token_file"]).unwrap();
+        assert_eq!(config.algorithms, vec![JwtAlgorithm::Rs256, JwtAlgorithm::Ps256]);
+        assert_eq!(config.leeway, 30);
+        assert!(config.from_file);
+        assert_eq!(parse_verify(&["pk_file", "token"]).unwrap().algorithms, vec![JwtAlgorithm::Rs256]);
+        assert!(parse_verify(&["pk_file"]).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use clap::{Args, Subcommand};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use crate::formats::{Encoding, KeyFormat, LoadedKey};
+use crate::input_module::to_hex;
+use crate::key_gen::{self, RsaKey};
+use crate::keyring::{KeyMetadata, Keyring, Revocation, Usage};
+use crate::output::{self, FileMode};
+use crate::settings::Settings;
+
+use super::{convert, info, util};
+
+#[derive(Debug, Subcommand)]
+pub enum KeysCommand {
+    /// List the keys in the keyring
+    List,
+    /// Show the metadata and fingerprints of a key
+    Show(KeysShowConfig),
+    /// Add a key file in any supported format to the keyring
+    Import(KeysImportConfig),
+    /// Write the public key, or the private key with --private
+    Export(KeysExportConfig),
+    /// Remove a key from the keyring
+    Delete(KeysDeleteConfig),
+    /// Mark a key as revoked, encrypt and verify refuse it without --allow-invalid-key
+    Revoke(KeysRevokeConfig),
+    /// Replace a private key by a new one with the same name, size, usage and comment.
+    /// The old key is renamed to NAME.<n> and expires, it can still decrypt old messages
+    Rotate(KeysRotateConfig),
+}
+
+#[derive(Debug, Args)]
+pub struct KeysShowConfig {
+    /// print the metadata as json
+    #[arg(short, long)]
+    json: bool,
+    /// name of the key, or the start of its SPKI SHA-256 fingerprint
+    key: String,
+}
+
+#[derive(Debug, Args)]
+pub struct KeysImportConfig {
+    /// name of the key in the keyring, defaults to the name of KEY_FILE
+    #[arg(short, long)]
+    name: Option<String>,
+    /// comment shown in the list of keys
+    #[arg(short, long, default_value = "")]
+    comment: String,
+    /// what the key may be used for, separated by commas
+    #[arg(short, long, value_enum, value_delimiter = ',', default_value = "encrypt,sign")]
+    usage: Vec<Usage>,
+    /// number of days until the key expires, at most 36500, it does not expire if not given
+    #[arg(short = 'x', long, value_name = "DAYS", value_parser = clap::value_parser!(i64).range(1..=36500))]
+    expires: Option<i64>,
+    /// file containing the key
+    key_file: String,
+}
+
+#[derive(Debug, Args)]
+pub struct KeysExportConfig {
+    /// write the private key instead of the public key
+    #[arg(short, long)]
+    private: bool,
+    /// format to write: legacy, pkcs1, pkcs8, spki, openssh or jwk, defaults to pkcs8 for private and spki for public keys
+    #[arg(short, long = "out-format")]
+    out_format: Option<KeyFormat>,
+    /// encoding for pkcs1, pkcs8 and spki, defaults to output_format of the config
+    #[arg(short, long)]
+    encoding: Option<Encoding>,
+    /// save the key to file, otherwise it is printed
+    #[arg(short, long, value_name = "FILE_NAME")]
+    file: Option<String>,
+    /// overwrite FILE_NAME if it exists
+    #[arg(long, requires = "file")]
+    force: bool,
+    /// name of the key, or the start of its SPKI SHA-256 fingerprint
+    key: String,
+}
+
+#[derive(Debug, Args)]
+pub struct KeysDeleteConfig {
+    /// name of the key, or the start of its SPKI SHA-256 fingerprint
+    key: String,
+}
+
+#[derive(Debug, Args)]
+pub struct KeysRevokeConfig {
+    /// why the key is revoked, e.g. compromised or retired
+    #[arg(short, long)]
+    reason: String,
+    /// name of the key, or the start of its SPKI SHA-256 fingerprint
+    key: String,
+}
+
+#[derive(Debug, Args)]
+pub struct KeysRotateConfig {
+    /// length of the primes of the new key in bits, defaults to the size of the old key if generate can create it
+    #[arg(short, long, value_parser = util::parse_bit_size)]
+    size: Option<u32>,
+    /// number of days until the new key expires, at most 36500, defaults to the validity period of the old key
+    #[arg(short = 'x', long, value_name = "DAYS", value_parser = clap::value_parser!(i64).range(1..=36500))]
+    expires: Option<i64>,
+    /// name of the key
+    name: String,
+}
+
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map_or("never".to_string(), |time| time.format("%Y-%m-%d %H:%M").to_string())
+}
+
+fn format_usage(usage: &[Usage]) -> String {
+    usage.iter().map(ToString::to_string).collect::<Vec<String>>().join(",")
+}
+
+/// One line per key, with a header
+pub fn key_list(keys: &[KeyMetadata]) -> String {
+    let width = keys.iter().map(|key| key.name.len()).max().unwrap_or(0).max("NAME".len());
+    let now = Utc::now();
+    let mut list = format!(
+        "{:width$}  {:7}  {:5}  {:16}  {:16}  {:16}  {:7}  {:12}  COMMENT",
+        "NAME", "TYPE", "SIZE", "FINGERPRINT", "CREATED", "EXPIRES", "STATUS", "USAGE"
+    );
+    for key in keys {
+        let line = format!(
+            "\n{:width$}  {:7}  {:5}  {:16}  {:16}  {:16}  {:7}  {:12}  {}",
+            key.name,
+            if key.private { "private" } else { "public" },
+            key.size,
+            &to_hex(&key.fingerprint)[..16],
+            format_time(Some(key.created)),
+            format_time(key.expires),
+            key.status(now),
+            format_usage(&key.usage),
+            key.comment,
+        );
+        list += line.trim_end();
+    }
+    list
+}
+
+fn show(keyring: &Keyring, config: KeysShowConfig) -> Result<()> {
+    let metadata = keyring.find(&config.key)?;
+    if config.json {
+        println!("{}", serde_json::to_string_pretty(&metadata.to_json()).unwrap_or_default());
+        return Ok(());
+    }
+    println!("Name:            {}", metadata.name);
+    println!("Algorithm:       {}", metadata.algorithm);
+    println!("Created:         {}", format_time(Some(metadata.created)));
+    println!("Expires:         {}", format_time(metadata.expires));
+    println!("Usage:           {}", format_usage(&metadata.usage));
+    println!("Comment:         {}", metadata.comment);
+    println!("Status:          {}", metadata.status(Utc::now()));
+    if let Some(revocation) = &metadata.revoked {
+        println!("Revoked:         {} ({})", format_time(Some(revocation.time)), revocation.reason);
+    }
+    for (label, link) in [("Predecessor:", &metadata.predecessor), ("Successor:", &metadata.successor)] {
+        if let Some(fingerprint) = link {
+            let name = keyring.find_by_fingerprint(fingerprint)?.map_or("not in keyring".to_string(), |key| key.name);
+            println!("{:16} {} ({})", label, to_hex(fingerprint), name);
+        }
+    }
+    let path = keyring.public_key_path(&metadata);
+    match metadata.private {
+        true => println!("{}", info::key_info(&keyring.private_key_path(&metadata)?.to_string_lossy(), &keyring.read_private_key(&metadata)?, false)),
+        false => println!("{}", info::key_info(&path.to_string_lossy(), &keyring.read_public_key(&metadata)?, false)),
+    }
+    Ok(())
+}
+
+fn import(keyring: &Keyring, config: KeysImportConfig) -> Result<()> {
+    let key = util::read_key(&config.key_file, None)?;
+    let name = match config.name {
+        Some(name) => name,
+        None => std::path::Path::new(&config.key_file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    };
+    let expires = config.expires.map(|days| Utc::now() + Duration::days(days));
+    let metadata = match &key {
+        LoadedKey::Private(key) => KeyMetadata::new(&name, key, config.usage, &config.comment, expires),
+        LoadedKey::Public(key) => KeyMetadata::new(&name, key, config.usage, &config.comment, expires),
+    };
+    keyring.add(&key, &metadata)?;
+    println!("Imported {} key {} with fingerprint {}", if metadata.private { "private" } else { "public" }, name, to_hex(&metadata.fingerprint));
+    Ok(())
+}
+
+fn export(keyring: &Keyring, config: KeysExportConfig, settings: &Settings) -> Result<()> {
+    let metadata = keyring.find(&config.key)?;
+    let key = match config.private {
+        true => LoadedKey::Private(keyring.read_private_key(&metadata)?),
+        false => LoadedKey::Public(keyring.read_public_key(&metadata)?),
+    };
+    let encoded = Zeroizing::new(convert::convert(&key, config.out_format, config.encoding.unwrap_or(settings.output_format))?);
+    match config.file {
+        Some(file_name) => {
+            let mode = if config.private { FileMode::Private } else { FileMode::Default };
+            output::write_file(&file_name, &encoded, mode, config.force)?;
+            println!("Wrote key {} to file: {}", metadata.name, file_name);
+        },
+        None => std::io::stdout().write_all(&encoded)?,
+    }
+    Ok(())
+}
+
+fn rotate(keyring: &Keyring, config: KeysRotateConfig, settings: &Settings) -> Result<()> {
+    let metadata = keyring.find(&config.name)?;
+    let size = match config.size {
+        Some(size) => size,
+        None => successor_size(metadata.size)?,
+    };
+    let expires = match (config.expires, metadata.expires) {
+        (Some(days), _) => Some(Utc::now() + Duration::days(days)),
+        // the metadata can be edited, so the period may be out of range
+        (None, Some(expires)) => Some(Utc::now().checked_add_signed(expires - metadata.created).ok_or_else(|| {
+            Error::InvalidArgs(format!("Error, the validity period of {} is out of range, choose one with --expires", metadata.name))
+        })?),
+        (None, None) => None,
+    };
+    println!("Generating {} bit key pair...", size);
+    let (successor, _) = key_gen::generate_key_pair_with_exponent(size, num_cpus::get_physical(), settings.exponent);
+    let new = keyring.rotate(&metadata, successor, expires)?;
+    println!("Rotated key {} to fingerprint {}, the old key is now {}", new.name, to_hex(&new.fingerprint), keyring.find(&to_hex(&metadata.fingerprint))?.name);
+    Ok(())
+}
+
+/// Size of the primes of a successor for a key with a modulus of `modulus_size` bits. The size of generated keys
+/// is the size of their primes, their modulus can be one bit shorter than twice that. Other sizes can't be generated.
+fn successor_size(modulus_size: u32) -> Result<u32> {
+    let size = modulus_size.div_ceil(2);
+    match util::is_valid_bit_size(size) {
+        true => Ok(size),
+        false => Err(Error::InvalidArgs(format!("Error, {} bit keys can't be generated, choose the size of the new key with --size", modulus_size))),
+    }
+}
+
+/// Refuses keys that are expired or revoked in the keyring, unless `allow_invalid` is set. Keys that are not in the keyring are not checked.
+pub fn check_valid<K: RsaKey>(keyring_dir: &Path, key: &K, allow_invalid: bool) -> Result<()> {
+    if allow_invalid {
+        return Ok(());
+    }
+    match Keyring::open(keyring_dir).find_by_fingerprint(&key.fingerprint())? {
+        Some(metadata) => metadata.check_valid(Utc::now()).map_err(|e| match e {
+            Error::InvalidKey(message) => Error::InvalidKey(format!("{}, --allow-invalid-key uses it anyway", message)),
+            e => e,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Keys of the keyring given with --key to encrypt or decrypt, which have to allow encryption
+pub fn find_encryption_key(settings: &Settings, name: &str) -> Result<(Keyring, KeyMetadata)> {
+    let keyring = Keyring::open(&settings.keyring_dir);
+    let metadata = keyring.find(name)?;
+    if !metadata.allows(Usage::Encrypt) {
+        return Err(Error::InvalidKey(format!("the usage of {} is {}, not encrypt", metadata.name, format_usage(&metadata.usage))));
+    }
+    Ok((keyring, metadata))
+}
+
+pub fn run(command: KeysCommand, settings: &Settings) -> Result<()> {
+    let keyring = Keyring::open(&settings.keyring_dir);
+    match command {
+        KeysCommand::List => println!("{}", key_list(&keyring.list()?)),
+        KeysCommand::Show(config) => show(&keyring, config)?,
+        KeysCommand::Import(config) => import(&keyring, config)?,
+        KeysCommand::Export(config) => export(&keyring, config, settings)?,
+        KeysCommand::Delete(config) => {
+            let metadata = keyring.find(&config.key)?;
+            keyring.delete(&metadata)?;
+            println!("Deleted key {} with fingerprint {}", metadata.name, to_hex(&metadata.fingerprint));
+        },
+        KeysCommand::Revoke(config) => {
+            let mut metadata = keyring.find(&config.key)?;
+            metadata.revoked = Some(Revocation { time: Utc::now(), reason: config.reason });
+            keyring.update(&metadata)?;
+            println!("Revoked key {} with fingerprint {}", metadata.name, to_hex(&metadata.fingerprint));
+        },
+        KeysCommand::Rotate(config) => rotate(&keyring, config, settings)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{check_valid, key_list, successor_size, KeysCommand};
+    use crate::error::Error;
+    use crate::formats::LoadedKey;
+    use crate::cli::{parse, Command};
+    use crate::key_gen::generate_key_pair;
+    use crate::keyring::{KeyMetadata, Keyring, Revocation, Usage};
+
+    fn parse_keys(args: &[&str]) -> Result<KeysCommand, clap::Error> {
+        match parse(&[&["keys"], args].concat())? {
+            Command::Keys(command) => Ok(command),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_init_import() {
+        match parse_keys(&["import", "-n", "alice", "-u", "encrypt", "--expires=30", "sk_file"]).unwrap() {
+            KeysCommand::Import(config) => {
+                assert_eq!(config.name.as_deref(), Some("alice"));
+                assert_eq!(config.usage, vec![Usage::Encrypt]);
+                assert_eq!(config.expires, Some(30));
+                assert_eq!(config.key_file, "sk_file");
+            },
+            command => panic!("parsed {:?}", command),
+        }
+        match parse_keys(&["import", "sk_file"]).unwrap() {
+            KeysCommand::Import(config) => assert_eq!(config.usage, vec![Usage::Encrypt, Usage::Sign]),
+            command => panic!("parsed {:?}", command),
+        }
+        assert!(parse_keys(&["import", "-u", "wrap", "sk_file"]).is_err());
+        assert!(parse_keys(&["import", "-x", "0", "sk_file"]).is_err());
+        assert!(parse_keys(&["import", "-x", "36501", "sk_file"]).is_err());
+        assert!(parse_keys(&["rotate", "-x", "9223372036854775807", "alice"]).is_err());
+        assert!(parse_keys(&["show"]).is_err());
+        assert!(parse_keys(&["revoke", "alice"]).is_err());
+        match parse_keys(&["rotate", "alice", "-x", "365"]).unwrap() {
+            KeysCommand::Rotate(config) => assert_eq!((config.name.as_str(), config.size, config.expires), ("alice", None, Some(365))),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_check_valid() {
+        let dir = std::env::temp_dir().join(format!("rust_rsa_keyring_check_{}", std::process::id()));
+        let keyring = Keyring::open(&dir);
+        let (sk, pk) = generate_key_pair(256, 2);
+        let mut metadata = KeyMetadata::new("alice", &sk, vec![Usage::Encrypt], "", None);
+        keyring.add(&LoadedKey::Private(sk), &metadata).unwrap();
+        assert!(check_valid(&dir, &pk, false).is_ok());
+
+        metadata.revoked = Some(Revocation { time: Utc::now(), reason: "compromised".to_string() });
+        keyring.update(&metadata).unwrap();
+        assert!(matches!(check_valid(&dir, &pk, false), Err(Error::InvalidKey(_))));
+        assert!(check_valid(&dir, &pk, true).is_ok());
+        // keys that are not in the keyring are not checked
+        let (_, other) = generate_key_pair(256, 2);
+        assert!(check_valid(&dir, &other, false).is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_successor_size() {
+        assert_eq!(successor_size(2048).unwrap(), 1024);
+        assert_eq!(successor_size(2047).unwrap(), 1024);
+        assert!(matches!(successor_size(3072), Err(Error::InvalidArgs(_))));
+        assert!(successor_size(2049).is_err());
+    }
+
+    #[test]
+    fn test_key_list() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let list = key_list(&[KeyMetadata::new("alice", &sk, vec![Usage::Encrypt, Usage::Sign], "work key", None)]);
+        let lines: Vec<&str> = list.lines().collect();
+        assert!(lines[0].starts_with("NAME   TYPE     SIZE "));
+        assert!(lines[1].starts_with("alice  private  "));
+        assert!(lines[1].ends_with("never             valid    encrypt,sign  work key"));
+    }
+}
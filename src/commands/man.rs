@@ -0,0 +1,112 @@
+use std::io::Write;
+use std::path::Path;
+
+use clap::{Args, CommandFactory};
+use clap_mangen::Man;
+
+use crate::cli::Cli;
+use crate::error::{Error, Result};
+use crate::output::{self, FileMode};
+
+/// Print the man page of a command, or write the pages of all commands to a directory
+#[derive(Debug, Args)]
+pub struct ManConfig {
+    /// write a page for every command to DIR, e.g. rsa_arbitray_precision-encrypt.1
+    #[arg(short, long, value_name = "DIR", conflicts_with = "command")]
+    dir: Option<String>,
+    /// overwrite pages in DIR that exist
+    #[arg(long, requires = "dir")]
+    force: bool,
+    /// command to print the page of, e.g. 'encrypt' or 'jwt sign', the page of the program if not given
+    command: Vec<String>,
+}
+
+/// Roff page of the command at `path`, the names of the subcommands leading to it
+pub fn man_page(path: &[String]) -> Result<Vec<u8>> {
+    // the help subcommand is no command of its own, clap_mangen leaves it out as well
+    let mut command = Cli::command().disable_help_subcommand(true);
+    // sets the full names, e.g. 'rsa_arbitray_precision jwt sign' in the synopsis
+    command.build();
+    for name in path {
+        command = command
+            .find_subcommand(name)
+            .cloned()
+            .ok_or_else(|| Error::InvalidArgs(format!("Error, unknown command: {}", path.join(" "))))?;
+    }
+    let mut page = vec![];
+    Man::new(command).render(&mut page)?;
+    Ok(page)
+}
+
+/// Subcommand names leading to every command that gets a page, like clap_mangen::generate_to: the program and all visible commands
+fn command_paths(command: &clap::Command, path: Vec<String>, paths: &mut Vec<Vec<String>>) {
+    for subcommand in command.get_subcommands().filter(|subcommand| !subcommand.is_hide_set()) {
+        let mut subcommand_path = path.clone();
+        subcommand_path.push(subcommand.get_name().to_string());
+        command_paths(subcommand, subcommand_path, paths);
+    }
+    paths.push(path);
+}
+
+/// Writes a page for every command to `dir`, named like the command, e.g. rsa_arbitray_precision-jwt-sign.1
+fn write_pages(dir: &Path, overwrite: bool) -> Result<()> {
+    let command = Cli::command().disable_help_subcommand(true);
+    let mut paths = vec![];
+    command_paths(&command, vec![], &mut paths);
+    let file_name = |path: &[String]| dir.join(format!("{}.1", [&[command.get_name().to_string()], path].concat().join("-")));
+    // refuse before anything is written, so existing pages aren't replaced partially
+    for path in &paths {
+        output::check_overwrite(file_name(path), overwrite)?;
+    }
+    for path in &paths {
+        output::write_file(file_name(path), &man_page(path)?, FileMode::Default, overwrite)?;
+    }
+    Ok(())
+}
+
+pub fn run(config: ManConfig) -> Result<()> {
+    match config.dir {
+        Some(dir) => {
+            write_pages(Path::new(&dir), config.force)?;
+            println!("Wrote man pages to directory: {}", dir);
+        },
+        None => std::io::stdout().write_all(&man_page(&config.command)?)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{man_page, write_pages};
+    use crate::error::Error;
+
+    #[test]
+    fn test_man_page() {
+        let page = String::from_utf8(man_page(&[]).unwrap()).unwrap();
+        assert!(page.starts_with(".ie \\n(.g .ds Aq"));
+        assert!(page.contains("check\\-key"));
+
+        let page = String::from_utf8(man_page(&["jwt".to_string(), "verify".to_string()]).unwrap()).unwrap();
+        assert!(page.contains("\\-\\-leeway"));
+        assert!(man_page(&["decipher".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_write_pages() {
+        let dir = std::env::temp_dir().join(format!("rust_rsa_man_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        write_pages(&dir, false).unwrap();
+        let page = std::fs::read(dir.join("rsa_arbitray_precision-jwt-sign.1")).unwrap();
+        assert_eq!(page, man_page(&["jwt".to_string(), "sign".to_string()]).unwrap());
+        assert!(dir.join("rsa_arbitray_precision.1").is_file());
+        assert!(!dir.join("rsa_arbitray_precision-help.1").exists());
+
+        std::fs::write(dir.join("rsa_arbitray_precision-encrypt.1"), "old").unwrap();
+        assert!(matches!(write_pages(&dir, false), Err(Error::InvalidArgs(_))));
+        assert_eq!(std::fs::read(dir.join("rsa_arbitray_precision-encrypt.1")).unwrap(), b"old");
+        write_pages(&dir, true).unwrap();
+        assert_ne!(std::fs::read(dir.join("rsa_arbitray_precision-encrypt.1")).unwrap(), b"old");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
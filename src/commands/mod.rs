@@ -1,5 +1,18 @@
 pub mod benchmark;
+pub mod cert;
+pub mod check_key;
+pub mod completions;
+pub mod config;
+pub mod convert;
+pub mod export_public;
+pub mod info;
+pub mod jwks;
+pub mod jwt;
+pub mod keys;
+pub mod man;
+pub mod sign;
 pub mod util;
+pub mod verify_cert;
 // pub mod decrypt;
 pub mod crypto;
 pub mod generate;
\ No newline at end of file
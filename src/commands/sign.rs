@@ -0,0 +1,212 @@
+use std::io::Write;
+
+use chrono::Utc;
+use clap::Args;
+
+use crate::cms::{self, Signer};
+use crate::error::{Error, Result};
+use crate::formats::{pem, Encoding};
+use crate::input_module;
+use crate::output::{self, FileMode};
+use crate::settings::Settings;
+use crate::signature::SignatureScheme;
+use crate::x509::{Certificate, TrustStore};
+
+use super::{keys, util};
+
+/// Sign a message
+#[derive(Debug, Args)]
+pub struct SignConfig {
+    /// signature scheme: pkcs1 or pss, optionally followed by the hash, e.g. pss-sha384
+    #[arg(short = 'S', long, default_value = "pkcs1-sha256")]
+    scheme: SignatureScheme,
+    /// the message is a file, otherwise it is the argument itself
+    #[arg(short = 'F', long = "from")]
+    from_file: bool,
+    /// write a CMS SignedData instead of the hex signature
+    #[arg(short, long)]
+    cms: bool,
+    /// do not include the message in the SignedData
+    #[arg(short, long, requires = "cms")]
+    detached: bool,
+    /// include the certificate of the key in the SignedData and identify the signer by it
+    #[arg(short = 'C', long, value_name = "CERT_FILE", requires = "cms")]
+    cert: Option<String>,
+    /// encoding of the SignedData, defaults to output_format of the config
+    #[arg(short, long)]
+    encoding: Option<Encoding>,
+    /// save signature to file, otherwise it is printed
+    #[arg(short, long, value_name = "FILE_NAME")]
+    file: Option<String>,
+    /// overwrite FILE_NAME if it exists
+    #[arg(long, requires = "file")]
+    force: bool,
+    /// file containing the private key
+    key_file: String,
+    /// message to be signed
+    message: String,
+}
+
+pub fn run_sign(config: SignConfig, settings: &Settings) -> Result<()> {
+    let encoding = config.encoding.unwrap_or(settings.output_format);
+    let key = util::read_private_key(&config.key_file)?;
+    let message = match config.from_file {
+        true => std::fs::read(&config.message)?,
+        false => config.message.into_bytes(),
+    };
+
+    let output = match config.cms {
+        true => {
+            let certificate = config.cert.as_deref().map(Certificate::read).transpose()?;
+            let signer = Signer { key: &key, scheme: config.scheme, certificate: certificate.as_ref() };
+            let der = cms::sign(&message, &signer, config.detached)?;
+            match encoding {
+                Encoding::Pem => pem::encode(cms::PEM_LABEL, &der).into_bytes(),
+                Encoding::Der => der,
+            }
+        },
+        false => {
+            let mut hex = input_module::to_hex(&config.scheme.sign(&key, &message)?);
+            hex.push('\n');
+            hex.into_bytes()
+        },
+    };
+
+    match config.file {
+        Some(file_name) => {
+            output::write_file(&file_name, &output, FileMode::Default, config.force)?;
+            println!("Stored signature to {}", file_name);
+        },
+        None => std::io::stdout().write_all(&output)?,
+    }
+    Ok(())
+}
+
+/// Verify a signature
+#[derive(Debug, Args)]
+pub struct VerifyConfig {
+    /// file containing the signed message, needed unless the CMS SignedData includes it, otherwise both have to match
+    #[arg(short, long, value_name = "FILE", required_unless_present = "cms")]
+    message: Option<String>,
+    /// signature scheme of hex signatures: pkcs1 or pss, optionally followed by the hash, e.g. pss-sha384
+    #[arg(short = 'S', long, default_value = "pkcs1-sha256")]
+    scheme: SignatureScheme,
+    /// SIGNATURE_FILE is a CMS SignedData, KEY_FILE can be '-' to use the certificate included in it, see --trust
+    #[arg(short, long)]
+    cms: bool,
+    /// verify the certificate of the signer against the trust anchors in DIR
+    #[arg(short, long, value_name = "DIR", requires = "cms")]
+    trust: Option<String>,
+    /// save the message of a CMS SignedData to file
+    #[arg(short, long, value_name = "FILE_NAME", requires = "cms")]
+    file: Option<String>,
+    /// overwrite FILE_NAME if it exists
+    #[arg(long, requires = "file")]
+    force: bool,
+    /// verify even with keys that are expired or revoked in the keyring
+    #[arg(long)]
+    allow_invalid_key: bool,
+    /// file containing the public key or a certificate
+    key_file: String,
+    /// hex signature or CMS SignedData
+    signature_file: String,
+}
+
+pub fn run_verify(config: VerifyConfig, settings: &Settings) -> Result<()> {
+    // '-' as key file means the key comes from the certificate in the SignedData
+    let (key, certificate) = match (config.key_file.as_str(), config.cms) {
+        ("-", false) => return Err(Error::InvalidArgs("Error, a key file is needed to verify a hex signature".to_string())),
+        ("-", true) => (None, None),
+        (key_file, _) => {
+            let (key, certificate) = util::read_public_key_or_certificate(key_file)?;
+            (Some(key), certificate)
+        },
+    };
+    if config.trust.is_some() && key.is_some() && certificate.is_none() {
+        return Err(Error::InvalidArgs("Error, --trust needs a certificate, use '-' or a certificate as KEY_FILE".to_string()));
+    }
+    if let Some(key) = &key {
+        keys::check_valid(&settings.keyring_dir, key, config.allow_invalid_key)?;
+    }
+    let message = config.message.as_deref().map(std::fs::read).transpose()?;
+    let signature = std::fs::read(&config.signature_file)?;
+
+    if !config.cms {
+        let signature = input_module::from_hex(String::from_utf8_lossy(&signature).trim())?;
+        config.scheme.verify(key.as_ref().unwrap(), message.as_deref().unwrap_or_default(), &signature)?;
+        println!("Signature is valid");
+        return Ok(());
+    }
+
+    let verified = cms::verify(&signature, message.as_deref(), key.as_ref())?;
+    match (config.trust, certificate, &verified.signer) {
+        (Some(dir), certificate, signer) => {
+            let store = TrustStore::from_dir(&dir)?;
+            if store.is_empty() {
+                return Err(Error::InvalidArgs(format!("Error, no certificates found in {}", dir)));
+            }
+            let chain: Vec<Certificate> = certificate.or_else(|| signer.clone()).into_iter()
+                .chain(verified.certificates.iter().cloned())
+                .collect();
+            let path = store.verify(&chain, Utc::now())?;
+            println!("Signature is valid, signed by '{}', trusted through '{}'", path[0].subject(), path[path.len() - 1].subject());
+        },
+        // anyone can include a certificate, only its key was checked
+        (None, _, Some(signer)) => {
+            println!("Signature is valid, but the certificate of the signer '{}' is not trusted, use --trust to verify it", signer.subject());
+        },
+        (None, _, None) => println!("Signature is valid"),
+    }
+    if let Some(file_name) = config.file {
+        output::write_file(&file_name, &verified.content, FileMode::Default, config.force)?;
+        println!("Stored message to {}", file_name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SignConfig, VerifyConfig};
+    use crate::cli::{parse, Command};
+    use crate::hash::HashAlgorithm;
+    use crate::signature::SignatureScheme;
+
+    fn parse_sign(args: &[&str]) -> Result<SignConfig, clap::Error> {
+        match parse(&[&["sign"], args].concat())? {
+            Command::Sign(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    fn parse_verify(args: &[&str]) -> Result<VerifyConfig, clap::Error> {
+        match parse(&[&["verify"], args].concat())? {
+            Command::Verify(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_init_sign() {
+        let config = parse_sign(&["-S", "pss-sha384", "-c", "-d", "-C", "cert_file", "-F", "sk_file", "message_file"]).unwrap();
+        assert_eq!(config.scheme, SignatureScheme::Pss(HashAlgorithm::Sha384));
+        assert!(config.cms && config.detached && config.from_file);
+        assert_eq!(config.cert.as_deref(), Some("cert_file"));
+        assert_eq!(config.message, "message_file");
+        assert!(parse_sign(&["-d", "sk_file", "message"]).is_err());
+    }
+
+    #[test]
+    fn test_init_verify() {
+        let config = parse_verify(&["-c", "-", "signed.pem"]).unwrap();
+        assert!(config.cms);
+        assert_eq!(config.key_file, "-");
+        assert!(parse_verify(&["pk_file", "signature"]).is_err());
+        assert!(parse_verify(&["-m", "message", "pk_file", "signature"]).is_ok());
+        let config = parse_verify(&["-c", "-f", "out", "--force", "pk_file", "signed.pem"]).unwrap();
+        assert!(config.force && !config.allow_invalid_key);
+        assert!(parse_verify(&["-c", "--allow-invalid-key", "pk_file", "signed.pem"]).unwrap().allow_invalid_key);
+        assert!(parse_verify(&["-c", "--force", "pk_file", "signed.pem"]).is_err());
+        assert_eq!(parse_verify(&["-c", "-t", "trusted", "-", "signed.pem"]).unwrap().trust.as_deref(), Some("trusted"));
+        assert!(parse_verify(&["-m", "message", "-t", "trusted", "pk_file", "signature"]).is_err());
+    }
+}
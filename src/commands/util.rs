@@ -1,74 +1,104 @@
-use chrono::{self, Datelike, Timelike};
+use crate::error::{Error, Result};
+use crate::formats::{self, KeyFormat};
+use crate::key_gen::{RsaKey, RsaPrivateKey, RsaPublicKey};
+use crate::x509::Certificate;
 
-// TODO group error types with trait maybe?
-type Result<T> = std::result::Result<T, InitConfigError>;
+pub use crate::formats::LoadedKey;
 
-#[derive(Debug)]
-pub struct InitConfigError {
-    pub msg: String,
-}
+/// Environment variable read instead of prompting for a passphrase, for scripts
+pub const PASSPHRASE_ENV: &str = "RUST_RSA_PASSPHRASE";
 
-impl InitConfigError {
-    pub fn get_msg(&self) -> &str {
-        &self.msg
+/// Reads a passphrase from `RUST_RSA_PASSPHRASE` or from the terminal without echoing it
+pub fn read_passphrase(prompt: &str) -> Result<String> {
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => Ok(passphrase),
+        Err(_) => Ok(rpassword::prompt_password(prompt)?),
     }
 }
 
-#[derive(Debug)]
-pub struct ParseCommandError {
-    err_type: ErrorType,
+fn prompt_passphrase(file_name: &str) -> Result<String> {
+    read_passphrase(&format!("Enter passphrase for {}: ", file_name))
 }
 
-impl From<ErrorType> for ParseCommandError {
-    fn from(err_type: ErrorType) -> Self {
-        ParseCommandError { err_type }
-    }
+/// Reads a key in the given format or detects it, asking for the passphrase of encrypted keys
+pub fn read_key(file_name: &str, format: Option<KeyFormat>) -> Result<LoadedKey> {
+    formats::read_key(file_name, format, Some(&prompt_passphrase))
+}
+
+pub fn read_private_key(file_name: &str) -> Result<RsaPrivateKey> {
+    formats::read_private_key(file_name, Some(&prompt_passphrase))
+}
+
+/// Reads a public key, or the public part of a private key
+pub fn read_public_key(file_name: &str) -> Result<RsaPublicKey> {
+    formats::read_public_key(file_name, Some(&prompt_passphrase))
 }
 
-impl ParseCommandError {
-    pub fn get_message(&self) -> &str {
-        match &self.err_type {
-            ErrorType::InvalidFlag(msg) => msg,
-            ErrorType::HelpFlag(msg) => msg,
-            ErrorType::InvalidBitSize(msg) => msg,
-            ErrorType::InvalidArgs(msg) => msg,
-            ErrorType::Other(msg) => msg,
+/// Reads the private key in `path`, or if `path` is a directory, the key in it with one of the given fingerprints.
+/// Encrypted keys in a directory are skipped, to not ask for the passphrase of every key.
+pub fn read_private_key_matching(path: &str, fingerprints: &[&[u8]]) -> Result<RsaPrivateKey> {
+    if !std::path::Path::new(path).is_dir() {
+        return read_private_key(path);
+    }
+    let mut entries = std::fs::read_dir(path)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in entries {
+        let data = match std::fs::read(entry.path()) {
+            Ok(data) => data,
+            Err(_) => continue,
+        };
+        if let Ok(LoadedKey::Private(key)) = formats::decode_key(&data, None) {
+            if fingerprints.contains(&key.fingerprint().as_slice()) {
+                return Ok(key);
+            }
         }
     }
+    let fingerprints: Vec<String> = fingerprints.iter().map(|fingerprint| crate::input_module::to_hex(fingerprint)).collect();
+    Err(Error::KeyMismatch(format!("no private key in {} has one of the fingerprints {}", path, fingerprints.join(", "))))
 }
 
-#[derive(Debug)]
-pub enum ErrorType {
-    InvalidFlag(String),
-    HelpFlag(String),
-    InvalidBitSize(String),
-    InvalidArgs(String),
-    Other(String),
+/// Reads a certificate, or a public key in any supported format if the file is no certificate
+pub fn read_public_key_or_certificate(file_name: &str) -> Result<(RsaPublicKey, Option<Certificate>)> {
+    match Certificate::parse(&std::fs::read(file_name)?) {
+        Ok(certificate) => Ok((certificate.public_key().clone(), Some(certificate))),
+        Err(_) => Ok((read_public_key(file_name)?, None)),
+    }
 }
 
-// check if file already exists and return error if so
-#[inline(always)]
-pub fn verify_file_name(file_name: &str) -> Result<String> {
-    if let Ok(_) = std::fs::File::open(&file_name) {
-        return Err(InitConfigError { msg: format!("File {} already exists.", file_name)});
+/// Loads a key in any supported format and checks its type, unless `use_private` is None
+pub fn load_key(file_name: &str, use_private: Option<bool>) -> Result<LoadedKey> {
+    match (read_key(file_name, None)?, use_private) {
+        (LoadedKey::Private(_), Some(false)) => Err(Error::KeyFormat(format!("{} contains a private key, expected a public key", file_name))),
+        (LoadedKey::Public(_), Some(true)) => Err(Error::KeyFormat(format!("{} contains a public key, expected a private key", file_name))),
+        (key, _) => Ok(key),
+    }
+}
+
+/// Type of a key, the argument of -k/--key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum KeyType {
+    Private,
+    Public,
+}
+
+impl KeyType {
+    pub fn is_private(self) -> bool {
+        self == KeyType::Private
+    }
+}
+
+/// Parses a key size in bits, for -s/--size
+pub fn parse_bit_size(size: &str) -> Result<u32> {
+    match size.parse::<u32>() {
+        Ok(n) if is_valid_bit_size(n) => Ok(n),
+        Ok(n) => Err(Error::InvalidArgs(format!("Invalid bit size: {}, needs to be in range of 128 to 8192 and power of 2.", n))),
+        Err(_) => Err(Error::InvalidArgs(format!("Unable to parse bit size: {}", size))),
     }
-    Ok(file_name.to_string())
 }
 
 #[inline(always)]
 pub fn is_valid_bit_size(n: u32) -> bool {
     // power of two will have one bit set
     // check if n & n - 1 == 0, (100 & 011 == 000)
-    n <= 8196 && n >= 128 && (n & (n - 1) == 0) 
+    (128..=8196).contains(&n) && (n & (n - 1) == 0)
 }
-
-fn parse_file_name(args: &[String]) -> String {
-    let mut args_iter = args.iter();
-    args_iter.next();
-    if let Some(name) = args_iter.next() {
-        String::from(name)
-    } else {
-        let now = chrono::Utc::now();
-        format!("{}-{}-{}T{}:{}", now.day(), now.month(), now.year(), now.hour(), now.minute())
-    }
-}
\ No newline at end of file
@@ -0,0 +1,104 @@
+use chrono::Utc;
+use clap::Args;
+
+use crate::error::{Error, Result};
+use crate::key_gen::RsaKey;
+use crate::x509::{Certificate, TrustStore};
+
+use super::util;
+
+/// Show a certificate and verify its signature or chain
+#[derive(Debug, Args)]
+pub struct VerifyCertConfig {
+    /// verify the signature with the key of the issuer
+    #[arg(short, long, value_name = "KEY_FILE")]
+    key: Option<String>,
+    /// verify the signature with the key of the issuer certificate
+    #[arg(short, long, value_name = "CERT_FILE")]
+    issuer: Option<String>,
+    /// verify the chain against the trust anchors in DIR
+    #[arg(short, long, value_name = "DIR")]
+    trust: Option<String>,
+    /// additional intermediate certificates for -t
+    #[arg(short, long, value_name = "FILE", requires = "trust")]
+    chain: Option<String>,
+    /// PEM or DER certificate, a PEM file may contain intermediates after the certificate
+    cert_file: String,
+}
+
+fn print_certificate(cert: &Certificate) {
+    println!("Subject: {}", cert.subject());
+    println!("Issuer: {}", cert.issuer());
+    println!("Serial: {}", cert.serial().to_string_radix(16));
+    println!("Valid from: {}", cert.not_before());
+    println!("Valid until: {}", cert.not_after());
+    println!("Key size: {} bits", cert.public_key().modulus().significant_bits());
+    match cert.signature_scheme() {
+        Some(scheme) => println!("Signature: {}", scheme),
+        None => println!("Signature: unsupported algorithm"),
+    }
+    if let Some(constraints) = cert.basic_constraints().filter(|c| c.ca) {
+        match constraints.path_len {
+            Some(path_len) => println!("CA: yes, path length {}", path_len),
+            None => println!("CA: yes"),
+        }
+    }
+    if !cert.subject_alt_names().is_empty() {
+        let names: Vec<String> = cert.subject_alt_names().iter().map(ToString::to_string).collect();
+        println!("Subject alternative names: {}", names.join(", "));
+    }
+}
+
+pub fn run(config: VerifyCertConfig) -> Result<()> {
+    let mut chain = Certificate::read_all(&config.cert_file)?;
+    print_certificate(&chain[0]);
+
+    if let Some(key_file) = config.key {
+        chain[0].verify_signature(&util::read_public_key(&key_file)?)?;
+        println!("Signature verified with key from {}", key_file);
+    }
+    if let Some(issuer_file) = config.issuer {
+        let issuer = Certificate::read(&issuer_file)?;
+        if issuer.subject_der() != chain[0].issuer_der() {
+            return Err(Error::Verification(format!("certificate was issued by '{}', not by '{}'", chain[0].issuer(), issuer.subject())));
+        }
+        chain[0].verify_signature(issuer.public_key())?;
+        println!("Signature verified with key of '{}'", issuer.subject());
+    }
+    if let Some(dir) = config.trust {
+        if let Some(chain_file) = config.chain {
+            chain.extend(Certificate::read_all(&chain_file)?);
+        }
+        let store = TrustStore::from_dir(&dir)?;
+        if store.is_empty() {
+            return Err(Error::InvalidArgs(format!("Error, no certificates found in {}", dir)));
+        }
+        let path = store.verify(&chain, Utc::now())?;
+        let subjects: Vec<String> = path.iter().map(|cert| format!("'{}'", cert.subject())).collect();
+        println!("Chain verified: {}", subjects.join(" <- "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VerifyCertConfig;
+    use crate::cli::{parse, Command};
+
+    fn parse_verify_cert(args: &[&str]) -> Result<VerifyCertConfig, clap::Error> {
+        match parse(&[&["verify-cert"], args].concat())? {
+            Command::VerifyCert(config) => Ok(config),
+            command => panic!("parsed {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_init() {
+        let config = parse_verify_cert(&["-t", "trusted", "-c", "chain.pem", "cert.pem"]).unwrap();
+        assert_eq!(config.trust.unwrap(), "trusted");
+        assert_eq!(config.chain.unwrap(), "chain.pem");
+        assert_eq!(config.cert_file, "cert.pem");
+        assert!(parse_verify_cert(&["-c", "chain.pem", "cert.pem"]).is_err());
+        assert!(parse_verify_cert(&["-k", "key"]).is_err());
+    }
+}
@@ -0,0 +1,513 @@
+//! Self describing container for RSA ciphertexts. The header names the padding, the hash used by
+//! the padding and the fingerprints of the keys, so the right key and scheme can be picked for decryption.
+//!
+//! Binary layout of version 1, the message is encrypted with RSA directly. Integers are big endian:
+//! magic "RSAC" | version (1 byte) | padding (1 byte) | hash (1 byte) | fingerprint (32 bytes) | payload length (4 bytes) | payload
+//!
+//! Version 2 encrypts the message with AES-256-GCM and the content key with RSA for every recipient:
+//! magic "RSAC" | version (1 byte) | padding (1 byte) | hash (1 byte) | recipient count (2 bytes) |
+//! recipients: fingerprint (32 bytes) | encrypted key length (2 bytes) | encrypted key |
+//! nonce prefix (7 bytes) | chunk size (4 bytes) | chunks
+//!
+//! The message is split into chunks of chunk size bytes, the last one may be shorter or empty. Every chunk is
+//! encrypted on its own, with the nonce prefix, the chunk counter (4 bytes) and a flag for the last chunk
+//! as nonce and the header as associated data, like the STREAM construction of Hoang et al.
+//! Reordered, truncated or appended chunks and modified headers are detected.
+//!
+//! The armored form is the binary form in a PEM block labelled `RSA ENCRYPTED MESSAGE`.
+use std::io::{Read, Write};
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::RngCore;
+
+use crate::error::{Error, Result};
+use crate::formats::pem;
+use crate::hash::HashAlgorithm;
+use crate::input_module::to_hex;
+use crate::key_gen::{RsaKey, RsaPrivateKey, RsaPublicKey};
+use crate::padding::Padding;
+
+pub const MAGIC: &[u8; 4] = b"RSAC";
+pub const VERSION: u8 = 1;
+pub const VERSION_HYBRID: u8 = 2;
+pub const PEM_LABEL: &str = "RSA ENCRYPTED MESSAGE";
+/// Chunk size of version 2 messages, in bytes
+pub const CHUNK_SIZE: u32 = 64 * 1024;
+
+const FINGERPRINT_LEN: usize = 32;
+const HEADER_LEN: usize = MAGIC.len() + 3 + FINGERPRINT_LEN + 4;
+const NONCE_PREFIX_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+
+/// An encrypted message together with the information needed to decrypt it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    pub padding: Padding,
+    /// `RsaKey::fingerprint` of the key the payload was encrypted with
+    pub fingerprint: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+
+fn padding_id(padding: Padding) -> (u8, u8) {
+    match padding {
+        Padding::None => (0, 0),
+        Padding::Pkcs1 => (1, 0),
+        Padding::Oaep(HashAlgorithm::Sha256) => (2, 1),
+        Padding::Oaep(HashAlgorithm::Sha384) => (2, 2),
+        Padding::Oaep(HashAlgorithm::Sha512) => (2, 3),
+    }
+}
+
+fn padding_from_id(padding: u8, hash: u8) -> Result<Padding> {
+    let hash = match hash {
+        0 => None,
+        1 => Some(HashAlgorithm::Sha256),
+        2 => Some(HashAlgorithm::Sha384),
+        3 => Some(HashAlgorithm::Sha512),
+        unknown => return Err(Error::Encoding(format!("unknown hash algorithm {} in message header", unknown))),
+    };
+    match (padding, hash) {
+        (0, None) => Ok(Padding::None),
+        (1, None) => Ok(Padding::Pkcs1),
+        (2, Some(hash)) => Ok(Padding::Oaep(hash)),
+        _ => Err(Error::Encoding(format!("unknown padding {} in message header", padding))),
+    }
+}
+
+impl Container {
+    /// Encrypts `msg` for `key`
+    pub fn seal(key: &RsaPublicKey, padding: Padding, msg: &[u8]) -> Result<Self> {
+        Ok(Container { padding, fingerprint: key.fingerprint(), payload: padding.encrypt(key, msg)? })
+    }
+
+    /// Decrypts the payload, after checking that it was encrypted for `key`
+    pub fn open(&self, key: &RsaPrivateKey) -> Result<Vec<u8>> {
+        if !self.matches(key) {
+            return Err(Error::KeyMismatch(format!(
+                "message was encrypted for the key with fingerprint {}, not for {}",
+                to_hex(&self.fingerprint), to_hex(&key.fingerprint())
+            )));
+        }
+        self.padding.decrypt(key, &self.payload)
+    }
+
+    /// True if the payload was encrypted with `key`
+    pub fn matches<K: RsaKey>(&self, key: &K) -> bool {
+        self.fingerprint == key.fingerprint()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (padding, hash) = padding_id(self.padding);
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.payload.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&[VERSION, padding, hash]);
+        bytes.extend_from_slice(&self.fingerprint);
+        bytes.extend_from_slice(&(self.payload.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if !Self::is_container(bytes) {
+            return Err(Error::Encoding("not an encrypted message, the magic is missing".to_string()));
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(Error::Encoding("message header is truncated".to_string()));
+        }
+        if bytes[4] != VERSION {
+            return Err(Error::Encoding(format!("unsupported message version {}, only version {} is supported", bytes[4], VERSION)));
+        }
+        let padding = padding_from_id(bytes[5], bytes[6])?;
+        let fingerprint = bytes[7..7 + FINGERPRINT_LEN].to_vec();
+        let len_bytes: [u8; 4] = bytes[HEADER_LEN - 4..HEADER_LEN].try_into().unwrap_or_default();
+        let payload = &bytes[HEADER_LEN..];
+        if payload.len() != u32::from_be_bytes(len_bytes) as usize {
+            return Err(Error::Encoding("payload length does not match the message header".to_string()));
+        }
+        Ok(Container { padding, fingerprint, payload: payload.to_vec() })
+    }
+
+    pub fn to_armor(&self) -> String {
+        pem::encode(PEM_LABEL, &self.to_bytes())
+    }
+
+    /// Decodes the binary or the armored form
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        Self::from_bytes(&unarmor(data)?)
+    }
+
+    /// True if `data` starts like a binary or armored container
+    pub fn is_container(data: &[u8]) -> bool {
+        data.starts_with(MAGIC) || String::from_utf8_lossy(data).trim_start().starts_with(&format!("-----BEGIN {}-----", PEM_LABEL))
+    }
+}
+
+/// Removes the armor, binary data is returned as is
+fn unarmor(data: &[u8]) -> Result<Vec<u8>> {
+    if !pem::is_pem(data) {
+        return Ok(data.to_vec());
+    }
+    match pem::decode(&String::from_utf8_lossy(data))? {
+        (label, bytes) if label == PEM_LABEL => Ok(bytes),
+        (label, _) => Err(Error::Encoding(format!("expected a PEM block labelled {}, found '{}'", PEM_LABEL, label))),
+    }
+}
+
+/// The content key of a version 2 message, encrypted for one recipient
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipientEntry {
+    /// `RsaKey::fingerprint` of the key of the recipient
+    pub fingerprint: Vec<u8>,
+    pub encrypted_key: Vec<u8>,
+}
+
+/// Encrypts or decrypts the chunks of a version 2 message in order
+struct ChunkCipher {
+    cipher: Aes256Gcm,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    header: Vec<u8>,
+}
+
+impl ChunkCipher {
+    fn new(key: &[u8], nonce_prefix: [u8; NONCE_PREFIX_LEN], header: Vec<u8>) -> Result<Self> {
+        let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| Error::Padding("decryption error".to_string()))?;
+        Ok(ChunkCipher { cipher, nonce_prefix, counter: 0, header })
+    }
+
+    fn next_nonce(&mut self, last: bool) -> Result<[u8; 12]> {
+        let mut nonce = [0; 12];
+        nonce[..NONCE_PREFIX_LEN].copy_from_slice(&self.nonce_prefix);
+        nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&self.counter.to_be_bytes());
+        nonce[11] = last as u8;
+        self.counter = self.counter.checked_add(1).ok_or_else(|| Error::SizeLimit("message has too many chunks".to_string()))?;
+        Ok(nonce)
+    }
+
+    fn seal(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>> {
+        let nonce = self.next_nonce(last)?;
+        self.cipher.encrypt(Nonce::from_slice(&nonce), Payload { msg: chunk, aad: &self.header })
+            .map_err(|_| Error::SizeLimit("chunk is too large".to_string()))
+    }
+
+    fn open(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>> {
+        let nonce = self.next_nonce(last)?;
+        self.cipher.decrypt(Nonce::from_slice(&nonce), Payload { msg: chunk, aad: &self.header })
+            .map_err(|_| Error::Padding("decryption error, the message was modified or truncated".to_string()))
+    }
+}
+
+/// A version 2 message, readable by several recipients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub padding: Padding,
+    pub recipients: Vec<RecipientEntry>,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    chunk_size: u32,
+    /// The encrypted chunks
+    payload: Vec<u8>,
+}
+
+impl Envelope {
+    /// Encrypts `msg` with a new content key, which is encrypted for every key in `recipients`
+    pub fn seal(recipients: &[RsaPublicKey], padding: Padding, msg: &[u8]) -> Result<Self> {
+        let mut bytes = vec![];
+        Self::seal_stream_with_chunk_size(recipients, padding, &mut &msg[..], &mut bytes, CHUNK_SIZE)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Like `seal`, but reads the message from `reader` and writes the binary form to `writer`,
+    /// one chunk at a time
+    pub fn seal_stream<R: Read, W: Write>(recipients: &[RsaPublicKey], padding: Padding, reader: &mut R, writer: &mut W) -> Result<()> {
+        Self::seal_stream_with_chunk_size(recipients, padding, reader, writer, CHUNK_SIZE)
+    }
+
+    fn seal_stream_with_chunk_size<R: Read, W: Write>(
+        recipients: &[RsaPublicKey], padding: Padding, reader: &mut R, writer: &mut W, chunk_size: u32
+    ) -> Result<()> {
+        if recipients.is_empty() || recipients.len() > u16::MAX as usize {
+            return Err(Error::InvalidArgs(format!("Error, between 1 and {} recipients are needed", u16::MAX)));
+        }
+        if padding == Padding::None {
+            return Err(Error::InvalidArgs("Error, content keys need pkcs1 or oaep padding".to_string()));
+        }
+        let mut content_key = [0u8; 32];
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        rand::thread_rng().fill_bytes(&mut content_key);
+        rand::thread_rng().fill_bytes(&mut nonce_prefix);
+        let recipients = recipients.iter()
+            .map(|key| Ok(RecipientEntry { fingerprint: key.fingerprint(), encrypted_key: padding.encrypt(key, &content_key)? }))
+            .collect::<Result<Vec<RecipientEntry>>>()?;
+
+        let envelope = Envelope { padding, recipients, nonce_prefix, chunk_size, payload: vec![] };
+        let header = envelope.header();
+        writer.write_all(&header)?;
+        let mut cipher = ChunkCipher::new(&content_key, nonce_prefix, header)?;
+        let mut chunk = vec![0; chunk_size as usize];
+        // the last chunk is always shorter than chunk size, so a message filling the last chunk is ended with an empty chunk
+        loop {
+            let len = read_full(reader, &mut chunk)?;
+            let last = len < chunk.len();
+            writer.write_all(&cipher.seal(&chunk[..len], last)?)?;
+            if last {
+                return Ok(writer.flush()?);
+            }
+        }
+    }
+
+    /// Decrypts the message with the content key of the entry for `key`
+    pub fn open(&self, key: &RsaPrivateKey) -> Result<Vec<u8>> {
+        let mut msg = Vec::with_capacity(self.payload.len());
+        self.open_stream(key, &mut &self.payload[..], &mut msg)?;
+        Ok(msg)
+    }
+
+    /// Decrypts the chunks read from `reader` and writes the message to `writer`, one chunk at a time.
+    /// The header has to be read with `read_header` first.
+    pub fn open_stream<R: Read, W: Write>(&self, key: &RsaPrivateKey, reader: &mut R, writer: &mut W) -> Result<()> {
+        let entry = self.recipients.iter()
+            .find(|entry| entry.fingerprint == key.fingerprint())
+            .ok_or_else(|| Error::KeyMismatch(format!("the key with fingerprint {} is not a recipient of the message", to_hex(&key.fingerprint()))))?;
+        let content_key = self.padding.decrypt(key, &entry.encrypted_key)?;
+        let mut cipher = ChunkCipher::new(&content_key, self.nonce_prefix, self.header())?;
+
+        let mut chunk = vec![0; self.chunk_size as usize + TAG_LEN];
+        loop {
+            let len = read_full(reader, &mut chunk)?;
+            let last = len < chunk.len();
+            // written before the following chunks are checked, a truncated message is only detected at its end
+            writer.write_all(&cipher.open(&chunk[..len], last)?)?;
+            // a short read means the end of the reader, so there can not be data after the last chunk
+            if last {
+                return Ok(writer.flush()?);
+            }
+        }
+    }
+
+    /// Fingerprints of the keys that can decrypt the message
+    pub fn fingerprints(&self) -> Vec<&[u8]> {
+        self.recipients.iter().map(|entry| entry.fingerprint.as_slice()).collect()
+    }
+
+    /// Everything before the chunks, also the associated data of every chunk
+    fn header(&self) -> Vec<u8> {
+        let (padding, hash) = padding_id(self.padding);
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&[VERSION_HYBRID, padding, hash]);
+        header.extend_from_slice(&(self.recipients.len() as u16).to_be_bytes());
+        for entry in &self.recipients {
+            header.extend_from_slice(&entry.fingerprint);
+            header.extend_from_slice(&(entry.encrypted_key.len() as u16).to_be_bytes());
+            header.extend_from_slice(&entry.encrypted_key);
+        }
+        header.extend_from_slice(&self.nonce_prefix);
+        header.extend_from_slice(&self.chunk_size.to_be_bytes());
+        header
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header();
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = bytes;
+        let mut envelope = Self::read_header(&mut reader)?;
+        envelope.payload = reader.to_vec();
+        Ok(envelope)
+    }
+
+    /// Reads the header of the binary form, the chunks are left in `reader`
+    pub fn read_header<R: Read>(reader: &mut R) -> Result<Self> {
+        let magic: [u8; 4] = read_array(reader)?;
+        if &magic != MAGIC {
+            return Err(Error::Encoding("not an encrypted message, the magic is missing".to_string()));
+        }
+        let [version, padding, hash] = read_array(reader)?;
+        if version != VERSION_HYBRID {
+            return Err(Error::Encoding(format!("expected a message of version {}, found version {}", VERSION_HYBRID, version)));
+        }
+        let padding = padding_from_id(padding, hash)?;
+        let count = u16::from_be_bytes(read_array(reader)?);
+        let mut recipients = vec![];
+        for _ in 0..count {
+            let fingerprint = read_array::<FINGERPRINT_LEN, R>(reader)?.to_vec();
+            let mut encrypted_key = vec![0; u16::from_be_bytes(read_array(reader)?) as usize];
+            if read_full(reader, &mut encrypted_key)? < encrypted_key.len() {
+                return Err(Error::Encoding("message header is truncated".to_string()));
+            }
+            recipients.push(RecipientEntry { fingerprint, encrypted_key });
+        }
+        let nonce_prefix = read_array(reader)?;
+        let chunk_size = u32::from_be_bytes(read_array(reader)?);
+        // chunks are held in memory while they are decrypted
+        if chunk_size == 0 || chunk_size > 16 * CHUNK_SIZE {
+            return Err(Error::Encoding(format!("chunk size of the message is {}, it has to be between 1 and {}", chunk_size, 16 * CHUNK_SIZE)));
+        }
+        Ok(Envelope { padding, recipients, nonce_prefix, chunk_size, payload: vec![] })
+    }
+
+    pub fn to_armor(&self) -> String {
+        pem::encode(PEM_LABEL, &self.to_bytes())
+    }
+
+    /// Decodes the binary or the armored form
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        Self::from_bytes(&unarmor(data)?)
+    }
+}
+
+/// Reads until `buf` is full or the end of `reader`, returns the number of bytes read
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(len)
+}
+
+fn read_array<const N: usize, R: Read>(reader: &mut R) -> Result<[u8; N]> {
+    let mut array = [0; N];
+    match read_full(reader, &mut array)? {
+        n if n == N => Ok(array),
+        _ => Err(Error::Encoding("message header is truncated".to_string())),
+    }
+}
+
+/// A message of any version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Direct(Container),
+    Hybrid(Envelope),
+}
+
+impl Message {
+    /// Decodes the binary or the armored form of any version
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let bytes = unarmor(data)?;
+        match bytes.get(MAGIC.len()) {
+            Some(&VERSION_HYBRID) => Ok(Message::Hybrid(Envelope::from_bytes(&bytes)?)),
+            _ => Ok(Message::Direct(Container::from_bytes(&bytes)?)),
+        }
+    }
+
+    pub fn padding(&self) -> Padding {
+        match self {
+            Message::Direct(container) => container.padding,
+            Message::Hybrid(envelope) => envelope.padding,
+        }
+    }
+
+    /// Fingerprints of the keys that can decrypt the message
+    pub fn fingerprints(&self) -> Vec<&[u8]> {
+        match self {
+            Message::Direct(container) => vec![container.fingerprint.as_slice()],
+            Message::Hybrid(envelope) => envelope.fingerprints(),
+        }
+    }
+
+    pub fn open(&self, key: &RsaPrivateKey) -> Result<Vec<u8>> {
+        match self {
+            Message::Direct(container) => container.open(key),
+            Message::Hybrid(envelope) => envelope.open(key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Container, Envelope, Message};
+    use crate::error::Error;
+    use crate::hash::HashAlgorithm;
+    use crate::key_gen::generate_key_pair;
+    use crate::key_gen::RsaPublicKey;
+    use crate::padding::Padding;
+
+    fn seal_with_chunk_size(recipients: &[RsaPublicKey], padding: Padding, msg: &[u8], chunk_size: u32) -> Envelope {
+        let mut bytes = vec![];
+        Envelope::seal_stream_with_chunk_size(recipients, padding, &mut &msg[..], &mut bytes, chunk_size).unwrap();
+        Envelope::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_seal_open() {
+        let (sk, pk) = generate_key_pair(512, 2);
+        for padding in [Padding::None, Padding::Pkcs1, Padding::Oaep(HashAlgorithm::Sha384)] {
+            let container = Container::seal(&pk, padding, b"hello").unwrap();
+            let decoded = Container::decode(&container.to_bytes()).unwrap();
+            assert_eq!(decoded, container);
+            assert_eq!(Container::decode(container.to_armor().as_bytes()).unwrap(), container);
+            assert_eq!(decoded.open(&sk).unwrap(), b"hello");
+        }
+    }
+
+    #[test]
+    fn test_key_mismatch() {
+        let (_, pk) = generate_key_pair(512, 2);
+        let (other_sk, _) = generate_key_pair(512, 2);
+        let container = Container::seal(&pk, Padding::Pkcs1, b"hello").unwrap();
+        assert!(matches!(container.open(&other_sk), Err(Error::KeyMismatch(_))));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let (_, pk) = generate_key_pair(512, 2);
+        let bytes = Container::seal(&pk, Padding::Pkcs1, b"hello").unwrap().to_bytes();
+        assert!(Container::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Container::decode(&bytes[..20]).is_err());
+        assert!(Container::decode(b"deadbeef").is_err());
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 2;
+        assert!(Container::decode(&wrong_version).is_err());
+        let mut wrong_padding = bytes;
+        wrong_padding[5] = 7;
+        assert!(Container::decode(&wrong_padding).is_err());
+    }
+
+    #[test]
+    fn test_envelope() {
+        let (sk, pk) = generate_key_pair(512, 2);
+        let (other_sk, other_pk) = generate_key_pair(512, 2);
+        let (third_sk, _) = generate_key_pair(512, 2);
+        let recipients = [pk, other_pk];
+        for len in [0, 1, 15, 16, 17, 48, 100] {
+            let msg: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let envelope = seal_with_chunk_size(&recipients, Padding::Oaep(HashAlgorithm::Sha256), &msg, 16);
+            let decoded = Message::decode(envelope.to_armor().as_bytes()).unwrap();
+            assert_eq!(decoded, Message::Hybrid(envelope.clone()));
+            assert_eq!(decoded.fingerprints().len(), 2);
+            assert_eq!(decoded.open(&sk).unwrap(), msg);
+            assert_eq!(Envelope::decode(&envelope.to_bytes()).unwrap().open(&other_sk).unwrap(), msg);
+            assert!(matches!(envelope.open(&third_sk), Err(Error::KeyMismatch(_))));
+        }
+        assert!(Envelope::seal(&recipients, Padding::None, b"hello").is_err());
+        assert!(Envelope::seal(&[], Padding::Pkcs1, b"hello").is_err());
+    }
+
+    #[test]
+    fn test_envelope_modified() {
+        let (sk, pk) = generate_key_pair(512, 2);
+        let (_, other_pk) = generate_key_pair(512, 2);
+        let bytes = seal_with_chunk_size(&[pk, other_pk], Padding::Pkcs1, &[7; 40], 16).to_bytes();
+        let open = |bytes: &[u8]| Envelope::from_bytes(bytes).and_then(|envelope| envelope.open(&sk));
+        assert_eq!(open(&bytes).unwrap(), vec![7; 40]);
+        // truncated to the first two chunks, which are both full
+        assert!(open(&bytes[..bytes.len() - 24]).is_err());
+        assert!(open(&bytes[..bytes.len() - 1]).is_err());
+        let mut modified = bytes.clone();
+        modified[bytes.len() - 30] ^= 1;
+        assert!(open(&modified).is_err());
+        // the header is authenticated, including the key of the other recipient at byte 9 + 162 + 34
+        let mut header = bytes;
+        header[210] ^= 1;
+        assert!(open(&header).is_err());
+        assert!(matches!(Message::decode(&Container::seal(&generate_key_pair(512, 2).1, Padding::Pkcs1, b"a").unwrap().to_bytes()), Ok(Message::Direct(_))));
+    }
+}
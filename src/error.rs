@@ -0,0 +1,144 @@
+use std::fmt;
+use std::io;
+use std::string::FromUtf8Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Every error that can be returned by this crate.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// A key could not be parsed, e.g. because the file is truncated or contains invalid hex.
+    KeyFormat(String),
+    /// Malformed DER, PEM or base64 data.
+    Encoding(String),
+    /// A message could not be padded, or a padded message could not be decoded.
+    Padding(String),
+    /// A key failed validation, contains the description of the failed check.
+    InvalidKey(String),
+    /// A value exceeds what the key or the program supports.
+    SizeLimit(String),
+    /// The input of RSAEP or RSASP1 is not in the range of 0 to n - 1.
+    MessageRepresentativeOutOfRange,
+    /// The input of RSADP is not in the range of 0 to n - 1.
+    CiphertextRepresentativeOutOfRange,
+    /// The input of RSAVP1 is not in the range of 0 to n - 1.
+    SignatureRepresentativeOutOfRange,
+    /// A message was encrypted for a different key or padding than the one given.
+    KeyMismatch(String),
+    /// A signature, token or certificate could not be verified.
+    Verification(String),
+    /// Invalid command line arguments, the message is meant to be shown to the user as is.
+    InvalidArgs(String),
+    /// A message is not valid utf8, so it can't be printed.
+    Utf8(FromUtf8Error),
+}
+
+impl Error {
+    /// Exit code of the program for this error, grouped by what went wrong:
+    /// 2 invalid arguments or input, 3 I/O, 4 bad key, 5 decryption failed, 6 verification failed, 1 anything else
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::InvalidArgs(_) | Error::Utf8(_) => 2,
+            Error::Io(_) => 3,
+            Error::KeyFormat(_) | Error::InvalidKey(_) => 4,
+            Error::Padding(_) | Error::KeyMismatch(_) | Error::CiphertextRepresentativeOutOfRange => 5,
+            Error::Verification(_) | Error::SignatureRepresentativeOutOfRange => 6,
+            Error::Encoding(_) | Error::SizeLimit(_) | Error::MessageRepresentativeOutOfRange => 1,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::KeyFormat(msg) => write!(f, "Invalid key format: {}", msg),
+            Error::Encoding(msg) => write!(f, "Invalid encoding: {}", msg),
+            Error::Padding(msg) => write!(f, "Padding error: {}", msg),
+            Error::InvalidKey(check) => write!(f, "Invalid key, check failed: {}", check),
+            Error::SizeLimit(msg) => write!(f, "Size limit exceeded: {}", msg),
+            Error::MessageRepresentativeOutOfRange => write!(f, "message representative out of range"),
+            Error::CiphertextRepresentativeOutOfRange => write!(f, "ciphertext representative out of range"),
+            Error::SignatureRepresentativeOutOfRange => write!(f, "signature representative out of range"),
+            Error::KeyMismatch(msg) => write!(f, "Key mismatch: {}", msg),
+            Error::Verification(msg) => write!(f, "Verification failed: {}", msg),
+            Error::InvalidArgs(msg) => write!(f, "{}", msg),
+            Error::Utf8(e) => write!(f, "Message is not valid utf8, write it to a file with -f: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Utf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Readers like `PemReader` wrap errors of the data in an `io::Error`, those are unwrapped
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        e.downcast::<Error>().unwrap_or_else(Error::Io)
+    }
+}
+
+impl From<FromUtf8Error> for Error {
+    fn from(e: FromUtf8Error) -> Self {
+        Error::Utf8(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::Error;
+    use crate::formats::pem::PemReader;
+
+    #[test]
+    fn test_invalid_args_display_is_message() {
+        let e = Error::InvalidArgs("Invalid option: --henlo".to_string());
+        assert_eq!(e.to_string(), "Invalid option: --henlo");
+    }
+
+    /// The codes are documented in the help, scripts depend on them
+    #[test]
+    fn test_exit_code() {
+        let utf8 = String::from_utf8(vec![0xff]).unwrap_err();
+        let expected = [
+            (Error::Encoding(String::new()), 1),
+            (Error::SizeLimit(String::new()), 1),
+            (Error::MessageRepresentativeOutOfRange, 1),
+            (Error::InvalidArgs(String::new()), 2),
+            (Error::Utf8(utf8), 2),
+            (Error::Io(std::io::ErrorKind::NotFound.into()), 3),
+            (Error::KeyFormat(String::new()), 4),
+            (Error::InvalidKey(String::new()), 4),
+            (Error::Padding(String::new()), 5),
+            (Error::KeyMismatch(String::new()), 5),
+            (Error::CiphertextRepresentativeOutOfRange, 5),
+            (Error::Verification(String::new()), 6),
+            (Error::SignatureRepresentativeOutOfRange, 6),
+        ];
+        for (error, code) in expected {
+            assert_eq!(error.exit_code(), code, "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn test_malformed_armor_is_not_io() {
+        for armor in [&b"-----BEGIN CMS-----\n%%%%\n"[..], b"-----BEGIN CMS-----\nAAAA\n", b"-----BEGIN CMS-----\n\xff\n"] {
+            let mut reader = PemReader::new(armor).unwrap();
+            let error = Error::from(reader.read_to_end(&mut vec![]).unwrap_err());
+            assert!(matches!(error, Error::Encoding(_)), "{:?}", error);
+            assert_eq!(error.exit_code(), 1);
+        }
+        assert!(matches!(PemReader::new(&b"\xff\n"[..]), Err(Error::Encoding(_))));
+        assert_eq!(Error::from(std::io::Error::from(std::io::ErrorKind::InvalidData)).exit_code(), 3);
+    }
+}
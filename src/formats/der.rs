@@ -0,0 +1,385 @@
+//! Minimal DER encoder and decoder (ITU-T X.690), only supporting the types needed for RSA keys and certificates.
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use rug::Integer;
+use rug::integer::Order;
+
+use crate::error::{Error, Result};
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTF8_STRING: u8 = 0x0c;
+pub const TAG_PRINTABLE_STRING: u8 = 0x13;
+pub const TAG_IA5_STRING: u8 = 0x16;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// rsaEncryption, 1.2.840.113549.1.1.1
+pub const OID_RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 1];
+pub const OID_SHA256: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 1];
+pub const OID_SHA384: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 2];
+pub const OID_SHA512: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 3];
+/// id-mgf1, RFC 8017 appendix B.2.1
+pub const OID_MGF1: &[u64] = &[1, 2, 840, 113549, 1, 1, 8];
+/// id-RSASSA-PSS, RFC 8017 appendix A.2.3
+pub const OID_RSASSA_PSS: &[u64] = &[1, 2, 840, 113549, 1, 1, 10];
+pub const OID_SHA256_WITH_RSA: &[u64] = &[1, 2, 840, 113549, 1, 1, 11];
+pub const OID_SHA384_WITH_RSA: &[u64] = &[1, 2, 840, 113549, 1, 1, 12];
+pub const OID_SHA512_WITH_RSA: &[u64] = &[1, 2, 840, 113549, 1, 1, 13];
+
+pub fn encode_length(len: usize) -> Vec<u8> {
+    if len < 0x80 {
+        return vec![len as u8];
+    }
+    let bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+    let mut result = vec![0x80 | bytes.len() as u8];
+    result.extend(bytes);
+    result
+}
+
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut result = vec![tag];
+    result.extend(encode_length(content.len()));
+    result.extend_from_slice(content);
+    result
+}
+
+/// Encodes a non negative integer
+pub fn integer(n: &Integer) -> Vec<u8> {
+    let mut content = n.to_digits::<u8>(Order::Msf);
+    // the highest bit is the sign bit, so a leading zero is needed if it is set
+    if content.first().is_none_or(|b| b & 0x80 != 0) {
+        content.insert(0, 0);
+    }
+    tlv(TAG_INTEGER, &content)
+}
+
+pub fn sequence(elements: &[Vec<u8>]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &elements.concat())
+}
+
+/// Encodes a SET OF, DER requires the elements to be sorted by their encoding
+pub fn set(elements: &[Vec<u8>]) -> Vec<u8> {
+    let mut sorted = elements.to_vec();
+    sorted.sort();
+    tlv(TAG_SET, &sorted.concat())
+}
+
+/// Constructed context specific tag `[n]`, used for EXPLICIT tagging
+pub fn context(n: u8, content: &[u8]) -> Vec<u8> {
+    tlv(0xa0 | n, content)
+}
+
+pub fn boolean(value: bool) -> Vec<u8> {
+    tlv(TAG_BOOLEAN, &[if value { 0xff } else { 0x00 }])
+}
+
+/// UTCTime for the years 1950 to 2049 and GeneralizedTime otherwise, as required by RFC 5280
+pub fn time(t: &DateTime<Utc>) -> Vec<u8> {
+    match t.year() {
+        1950..=2049 => tlv(TAG_UTC_TIME, t.format("%y%m%d%H%M%SZ").to_string().as_bytes()),
+        _ => tlv(TAG_GENERALIZED_TIME, t.format("%Y%m%d%H%M%SZ").to_string().as_bytes()),
+    }
+}
+
+pub fn null() -> Vec<u8> {
+    tlv(TAG_NULL, &[])
+}
+
+pub fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    // first content byte is the number of unused bits
+    let mut content = vec![0];
+    content.extend_from_slice(bytes);
+    tlv(TAG_BIT_STRING, &content)
+}
+
+pub fn octet_string(bytes: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, bytes)
+}
+
+pub fn oid(arcs: &[u64]) -> Vec<u8> {
+    let mut content = vec![];
+    if arcs.len() >= 2 {
+        encode_base128(arcs[0] * 40 + arcs[1], &mut content);
+    }
+    for arc in arcs.iter().skip(2) {
+        encode_base128(*arc, &mut content);
+    }
+    tlv(TAG_OID, &content)
+}
+
+fn encode_base128(mut n: u64, out: &mut Vec<u8>) {
+    let mut bytes = vec![(n & 0x7f) as u8];
+    n >>= 7;
+    while n > 0 {
+        bytes.push((n & 0x7f) as u8 | 0x80);
+        n >>= 7;
+    }
+    out.extend(bytes.into_iter().rev());
+}
+
+/// Reads DER encoded values one after another
+pub struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        DerReader { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// Reads the next value, returning its tag and content
+    pub fn read_tlv(&mut self) -> Result<(u8, &'a [u8])> {
+        let (tag, content, rest) = split_tlv(self.data)?;
+        self.data = rest;
+        Ok((tag, content))
+    }
+
+    /// Reads the next value, which has to be of type `tag`, returning its content
+    pub fn read(&mut self, tag: u8) -> Result<&'a [u8]> {
+        let (found, content) = self.read_tlv()?;
+        if found != tag {
+            return Err(Error::Encoding(format!("expected tag 0x{:02x}, found 0x{:02x}", tag, found)));
+        }
+        Ok(content)
+    }
+
+    /// Reads the next value including tag and length, e.g. to verify a signature over it
+    pub fn read_raw(&mut self) -> Result<&'a [u8]> {
+        let (_, _, rest) = split_tlv(self.data)?;
+        let raw = &self.data[..self.data.len() - rest.len()];
+        self.data = rest;
+        Ok(raw)
+    }
+
+    /// Reads the next value only if it has type `tag`, for OPTIONAL and DEFAULT fields
+    pub fn read_optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>> {
+        match self.peek_tag() == Some(tag) {
+            true => self.read(tag).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    pub fn read_sequence(&mut self) -> Result<DerReader<'a>> {
+        Ok(DerReader::new(self.read(TAG_SEQUENCE)?))
+    }
+
+    /// Reads a non negative integer
+    pub fn read_integer(&mut self) -> Result<Integer> {
+        let content = self.read(TAG_INTEGER)?;
+        if content.is_empty() || content[0] & 0x80 != 0 {
+            return Err(Error::Encoding("expected non negative integer".to_string()));
+        }
+        Ok(Integer::from_digits(content, Order::Msf))
+    }
+
+    pub fn read_octet_string(&mut self) -> Result<&'a [u8]> {
+        self.read(TAG_OCTET_STRING)
+    }
+
+    /// Reads a bit string, which has to consist of whole bytes
+    pub fn read_bit_string(&mut self) -> Result<&'a [u8]> {
+        match self.read(TAG_BIT_STRING)?.split_first() {
+            Some((0, bytes)) => Ok(bytes),
+            _ => Err(Error::Encoding("expected bit string without unused bits".to_string())),
+        }
+    }
+
+    pub fn read_boolean(&mut self) -> Result<bool> {
+        match self.read(TAG_BOOLEAN)? {
+            [0x00] => Ok(false),
+            [0xff] => Ok(true),
+            _ => Err(Error::Encoding("invalid boolean".to_string())),
+        }
+    }
+
+    /// Reads a UTCTime or GeneralizedTime in the form RFC 5280 requires, seconds and UTC
+    pub fn read_time(&mut self) -> Result<DateTime<Utc>> {
+        let (tag, content) = self.read_tlv()?;
+        let text = std::str::from_utf8(content).unwrap_or_default();
+        let parsed = match tag {
+            // two digit years from 50 on are 19xx
+            TAG_UTC_TIME if text.len() == 13 => {
+                let century = if text < "50" { "20" } else { "19" };
+                NaiveDateTime::parse_from_str(&format!("{}{}", century, text), "%Y%m%d%H%M%SZ")
+            },
+            TAG_GENERALIZED_TIME if text.len() == 15 => NaiveDateTime::parse_from_str(text, "%Y%m%d%H%M%SZ"),
+            _ => return Err(Error::Encoding(format!("expected UTCTime or GeneralizedTime, found tag 0x{:02x} '{}'", tag, text))),
+        };
+        parsed.map(|t| t.and_utc()).map_err(|_| Error::Encoding(format!("invalid time '{}'", text)))
+    }
+
+    /// Reads any of the string types used in names
+    pub fn read_string(&mut self) -> Result<String> {
+        let (tag, content) = self.read_tlv()?;
+        match tag {
+            TAG_UTF8_STRING | TAG_PRINTABLE_STRING | TAG_IA5_STRING => String::from_utf8(content.to_vec())
+                .map_err(|_| Error::Encoding("invalid string".to_string())),
+            // TeletexString, treated as latin-1 like most implementations do
+            0x14 => Ok(content.iter().map(|b| *b as char).collect()),
+            // BMPString, UTF-16 big endian
+            0x1e if content.len() % 2 == 0 => {
+                let units: Vec<u16> = content.chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+                String::from_utf16(&units).map_err(|_| Error::Encoding("invalid string".to_string()))
+            },
+            _ => Err(Error::Encoding(format!("unsupported string type 0x{:02x}", tag))),
+        }
+    }
+
+    pub fn read_null(&mut self) -> Result<()> {
+        match self.read(TAG_NULL)?.is_empty() {
+            true => Ok(()),
+            false => Err(Error::Encoding("null with content".to_string())),
+        }
+    }
+
+    pub fn read_oid(&mut self) -> Result<Vec<u64>> {
+        let content = self.read(TAG_OID)?;
+        let mut arcs = vec![];
+        let mut value: u64 = 0;
+        for (i, byte) in content.iter().enumerate() {
+            if value > u64::MAX >> 7 {
+                return Err(Error::Encoding("object identifier arc too large".to_string()));
+            }
+            value = (value << 7) | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0 {
+                if arcs.is_empty() {
+                    let first = (value / 40).min(2);
+                    arcs.push(first);
+                    arcs.push(value - first * 40);
+                } else {
+                    arcs.push(value);
+                }
+                value = 0;
+            } else if i == content.len() - 1 {
+                return Err(Error::Encoding("truncated object identifier".to_string()));
+            }
+        }
+        if arcs.is_empty() {
+            return Err(Error::Encoding("empty object identifier".to_string()));
+        }
+        Ok(arcs)
+    }
+
+    /// Returns an error if there is data left
+    pub fn finish(&self) -> Result<()> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(Error::Encoding(format!("{} bytes of trailing data", self.data.len()))),
+        }
+    }
+}
+
+/// Splits the first value off `data`, returning tag, content and the remaining data
+fn split_tlv(data: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let truncated = || Error::Encoding("truncated data".to_string());
+    let (&tag, rest) = data.split_first().ok_or_else(truncated)?;
+    if tag & 0x1f == 0x1f {
+        return Err(Error::Encoding("multi byte tags are not supported".to_string()));
+    }
+    let (&first, mut rest) = rest.split_first().ok_or_else(truncated)?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let n_bytes = (first & 0x7f) as usize;
+        if n_bytes == 0 || n_bytes > 4 {
+            return Err(Error::Encoding("unsupported length encoding".to_string()));
+        }
+        if rest.len() < n_bytes {
+            return Err(truncated());
+        }
+        let len = rest[..n_bytes].iter().fold(0usize, |len, b| (len << 8) | *b as usize);
+        rest = &rest[n_bytes..];
+        len
+    };
+    if rest.len() < len {
+        return Err(truncated());
+    }
+    Ok((tag, &rest[..len], &rest[len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+    use super::*;
+
+    #[test]
+    fn test_reader_roundtrip() {
+        let n = Integer::from_str_radix("00ff00ff00ff00ff00ff00ff00ff00ff00ff", 16).unwrap();
+        let encoded = sequence(&[integer(&n), oid(OID_RSA_ENCRYPTION), null(), octet_string(&[1, 2, 3]), bit_string(&[4, 5])]);
+        let mut outer = DerReader::new(&encoded);
+        let mut reader = outer.read_sequence().unwrap();
+        assert!(outer.finish().is_ok());
+        assert_eq!(reader.read_integer().unwrap(), n);
+        assert_eq!(reader.read_oid().unwrap(), OID_RSA_ENCRYPTION.to_vec());
+        assert!(reader.read_null().is_ok());
+        assert_eq!(reader.read_octet_string().unwrap(), &[1, 2, 3]);
+        assert_eq!(reader.read_bit_string().unwrap(), &[4, 5]);
+        assert!(reader.finish().is_ok());
+    }
+
+    #[test]
+    fn test_reader_malformed() {
+        assert!(DerReader::new(&[]).read_tlv().is_err());
+        assert!(DerReader::new(&[0x30, 0x05, 0x00]).read_tlv().is_err());
+        assert!(DerReader::new(&[0x30, 0x80, 0x00, 0x00]).read_tlv().is_err());
+        assert!(DerReader::new(&[0x30, 0x89, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).read_tlv().is_err());
+        assert!(DerReader::new(&[0x02, 0x01, 0x80]).read_integer().is_err());
+        assert!(DerReader::new(&[0x06, 0x01, 0x81]).read_oid().is_err());
+        assert!(DerReader::new(&[0x04, 0x00]).read_integer().is_err());
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(integer(&Integer::from(0)), vec![0x02, 0x01, 0x00]);
+        assert_eq!(integer(&Integer::from(127)), vec![0x02, 0x01, 0x7f]);
+        assert_eq!(integer(&Integer::from(128)), vec![0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(integer(&Integer::from(65537)), vec![0x02, 0x03, 0x01, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_length() {
+        assert_eq!(encode_length(0x7f), vec![0x7f]);
+        assert_eq!(encode_length(0x80), vec![0x81, 0x80]);
+        assert_eq!(encode_length(0x0123), vec![0x82, 0x01, 0x23]);
+    }
+
+    #[test]
+    fn test_set_time() {
+        assert_eq!(set(&[vec![0x02, 0x01, 0x02], vec![0x02, 0x01, 0x01]]), vec![0x31, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02]);
+        let t = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(time(&t), tlv(TAG_UTC_TIME, b"231114221320Z"));
+        let t = DateTime::from_timestamp(2_600_000_000, 0).unwrap();
+        assert_eq!(time(&t), tlv(TAG_GENERALIZED_TIME, b"20520522141320Z"));
+    }
+
+    #[test]
+    fn test_read_time_string() {
+        let t = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(DerReader::new(&time(&t)).read_time().unwrap(), t);
+        let t = DateTime::from_timestamp(2_600_000_000, 0).unwrap();
+        assert_eq!(DerReader::new(&time(&t)).read_time().unwrap(), t);
+        assert_eq!(DerReader::new(&tlv(TAG_UTC_TIME, b"991231235959Z")).read_time().unwrap().timestamp(), 946_684_799);
+        assert!(DerReader::new(&tlv(TAG_UTC_TIME, b"9912312359Z")).read_time().is_err());
+        assert_eq!(DerReader::new(&[0x1e, 0x04, 0x00, b'h', 0x00, b'i']).read_string().unwrap(), "hi");
+        assert_eq!(DerReader::new(&tlv(TAG_UTF8_STRING, "ä".as_bytes())).read_string().unwrap(), "ä");
+    }
+
+    #[test]
+    fn test_oid() {
+        assert_eq!(oid(OID_RSA_ENCRYPTION), vec![0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01]);
+    }
+}
@@ -0,0 +1,201 @@
+//! JSON Web Keys (RFC 7517, RSA members from RFC 7518 section 6.3) and thumbprints (RFC 7638)
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rug::Integer;
+use rug::integer::Order;
+use serde_json::{json, Map, Value};
+
+use crate::error::{Error, Result};
+use crate::hash::HashAlgorithm;
+use crate::key_gen::{CrtParams, RsaKey, RsaPrivateKey, RsaPublicKey};
+
+use super::LoadedKey;
+
+/// A key read from a JWK, with the optional members this crate understands
+pub struct Jwk {
+    pub key: LoadedKey,
+    pub kid: Option<String>,
+    pub alg: Option<String>,
+}
+
+/// Base64url encoding of the unsigned big endian value without leading zeros
+fn encode_integer(n: &Integer) -> String {
+    let bytes = if *n == 0 { vec![0] } else { n.to_digits::<u8>(Order::Msf) };
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn decode_integer(jwk: &Map<String, Value>, member: &str) -> Result<Option<Integer>> {
+    let encoded = match jwk.get(member) {
+        Some(Value::String(encoded)) => encoded,
+        Some(_) => return Err(Error::KeyFormat(format!("JWK member '{}' has to be a string", member))),
+        None => return Ok(None),
+    };
+    let bytes = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|e| Error::Encoding(format!("invalid base64url in JWK member '{}': {}", member, e)))?;
+    Ok(Some(Integer::from_digits(&bytes, Order::Msf)))
+}
+
+fn required_integer(jwk: &Map<String, Value>, member: &str) -> Result<Integer> {
+    decode_integer(jwk, member)?.ok_or_else(|| Error::KeyFormat(format!("JWK is missing member '{}'", member)))
+}
+
+/// RFC 7638 thumbprint, SHA-256 over the required public members in lexicographic order
+pub fn thumbprint<K: RsaKey>(key: &K) -> String {
+    let canonical = format!(
+        r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+        encode_integer(key.public_exponent()), encode_integer(key.modulus())
+    );
+    URL_SAFE_NO_PAD.encode(HashAlgorithm::Sha256.digest(canonical.as_bytes()))
+}
+
+/// JWK with the public members of `key`, the thumbprint is used as `kid`
+pub fn public_key_to_jwk<K: RsaKey>(key: &K, alg: Option<&str>) -> Value {
+    let mut jwk = json!({
+        "kty": "RSA",
+        "n": encode_integer(key.modulus()),
+        "e": encode_integer(key.public_exponent()),
+        "kid": thumbprint(key),
+    });
+    if let Some(alg) = alg {
+        jwk["alg"] = json!(alg);
+    }
+    jwk
+}
+
+/// JWK with all private members, the primes are recovered for keys that don't contain them
+pub fn private_key_to_jwk(key: &RsaPrivateKey, alg: Option<&str>) -> Result<Value> {
+    let crt = key.crt_params_or_recover()?;
+    let mut jwk = public_key_to_jwk(key, alg);
+    jwk["d"] = json!(encode_integer(key.private_exponent()));
+    jwk["p"] = json!(encode_integer(&crt.p));
+    jwk["q"] = json!(encode_integer(&crt.q));
+    jwk["dp"] = json!(encode_integer(&crt.dp));
+    jwk["dq"] = json!(encode_integer(&crt.dq));
+    jwk["qi"] = json!(encode_integer(&crt.qinv));
+    Ok(jwk)
+}
+
+/// JWK Set containing `keys`
+pub fn jwk_set(keys: Vec<Value>) -> Value {
+    json!({ "keys": keys })
+}
+
+pub fn to_string(value: &Value) -> String {
+    // serializing a Value can't fail
+    serde_json::to_string_pretty(value).unwrap_or_default() + "\n"
+}
+
+fn optional_string(jwk: &Map<String, Value>, member: &str) -> Result<Option<String>> {
+    match jwk.get(member) {
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(Error::KeyFormat(format!("JWK member '{}' has to be a string", member))),
+        None => Ok(None),
+    }
+}
+
+pub fn from_value(value: &Value) -> Result<Jwk> {
+    let jwk = value.as_object().ok_or_else(|| Error::KeyFormat("JWK has to be a JSON object".to_string()))?;
+    match jwk.get("kty").and_then(Value::as_str) {
+        Some("RSA") => {},
+        Some(kty) => return Err(Error::KeyFormat(format!("unsupported JWK key type '{}'", kty))),
+        None => return Err(Error::KeyFormat("JWK is missing member 'kty'".to_string())),
+    }
+    if jwk.contains_key("oth") {
+        return Err(Error::KeyFormat("multi prime JWKs are not supported".to_string()));
+    }
+
+    let n = required_integer(jwk, "n")?;
+    let e = required_integer(jwk, "e")?;
+    let key = match decode_integer(jwk, "d")? {
+        None => LoadedKey::Public(RsaPublicKey::new(n, e)),
+        // the CRT members are optional, but if one is present all have to be (RFC 7518 section 6.3.2)
+        Some(d) if jwk.contains_key("p") => {
+            let crt = CrtParams::from_parts(
+                &n,
+                required_integer(jwk, "p")?,
+                required_integer(jwk, "q")?,
+                required_integer(jwk, "dp")?,
+                required_integer(jwk, "dq")?,
+                required_integer(jwk, "qi")?,
+            )?;
+            LoadedKey::Private(RsaPrivateKey::from_components(n, e, d, Some(crt)))
+        },
+        Some(d) => LoadedKey::Private(RsaPrivateKey::from_components(n, e, d, None)),
+    };
+    Ok(Jwk { key, kid: optional_string(jwk, "kid")?, alg: optional_string(jwk, "alg")? })
+}
+
+/// Parses a JWK Set, or a single JWK as a set with one key
+pub fn from_json(text: &str) -> Result<Vec<Jwk>> {
+    let value: Value = serde_json::from_str(text).map_err(|e| Error::Encoding(format!("invalid JSON: {}", e)))?;
+    match value.get("keys") {
+        Some(Value::Array(keys)) => keys.iter().map(from_value).collect(),
+        Some(_) => Err(Error::KeyFormat("member 'keys' of a JWK Set has to be an array".to_string())),
+        None => Ok(vec![from_value(&value)?]),
+    }
+}
+
+pub fn is_json(data: &[u8]) -> bool {
+    data.trim_ascii_start().starts_with(b"{")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_gen::generate_key_pair;
+
+    #[test]
+    fn test_thumbprint() {
+        // example from RFC 7638 section 3.1
+        let n = "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw";
+        let jwk = from_value(&json!({ "kty": "RSA", "n": n, "e": "AQAB", "alg": "RS256", "kid": "2011-04-29" })).unwrap();
+        assert_eq!(jwk.kid.as_deref(), Some("2011-04-29"));
+        assert_eq!(jwk.alg.as_deref(), Some("RS256"));
+        match jwk.key {
+            LoadedKey::Public(key) => {
+                assert_eq!(thumbprint(&key), "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs");
+                assert_eq!(public_key_to_jwk(&key, None)["n"], n);
+            },
+            LoadedKey::Private(_) => panic!("public JWK decoded as private key"),
+        }
+    }
+
+    #[test]
+    fn test_private_key_roundtrip() {
+        let (sk, pk) = generate_key_pair(256, 2);
+        let text = to_string(&private_key_to_jwk(&sk, Some("PS256")).unwrap());
+        let mut keys = from_json(&text).unwrap();
+        assert_eq!(keys.len(), 1);
+        let jwk = keys.remove(0);
+        assert_eq!(jwk.kid, Some(thumbprint(&pk)));
+        match jwk.key {
+            LoadedKey::Private(key) => assert_eq!(key.serialize(), sk.serialize()),
+            LoadedKey::Public(_) => panic!("private JWK decoded as public key"),
+        }
+    }
+
+    #[test]
+    fn test_primes_not_matching() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let mut value = private_key_to_jwk(&sk, None).unwrap();
+        value["p"] = Value::from(encode_integer(&Integer::from(&sk.crt_params().unwrap().p + 2)));
+        assert!(matches!(from_value(&value), Err(Error::KeyFormat(_))));
+        value["p"] = Value::from(encode_integer(&Integer::from(1)));
+        value["q"] = Value::from(encode_integer(sk.modulus()));
+        assert!(matches!(from_value(&value), Err(Error::KeyFormat(_))));
+    }
+
+    #[test]
+    fn test_jwk_set() {
+        let (_, pk1) = generate_key_pair(256, 2);
+        let (_, pk2) = generate_key_pair(256, 2);
+        let set = jwk_set(vec![public_key_to_jwk(&pk1, Some("RS256")), public_key_to_jwk(&pk2, Some("RS256"))]);
+        let keys = from_json(&to_string(&set)).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[1].kid, Some(thumbprint(&pk2)));
+        assert!(from_json(r#"{"kty":"EC","crv":"P-256"}"#).is_err());
+        assert!(from_json(r#"{"kty":"RSA","n":"AQAB"}"#).is_err());
+        assert!(from_json(r#"{"keys":{}}"#).is_err());
+    }
+}
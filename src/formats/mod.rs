@@ -0,0 +1,291 @@
+//! Encodings of keys in standard formats, used for fingerprints and to exchange keys with other tools.
+use std::fmt;
+use std::str::FromStr;
+
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::error::{Error, Result};
+use crate::key_gen::{RsaKey, RsaPrivateKey, RsaPublicKey};
+
+pub mod der;
+pub mod jwk;
+pub mod openssh;
+pub mod pem;
+pub mod pkcs1;
+pub mod pkcs8;
+pub mod spki;
+
+/// Formats keys can be read from and written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// Hex encoded parts separated by `=======`, as written by `generate`
+    Legacy,
+    /// RSAPublicKey or RSAPrivateKey (RFC 8017)
+    Pkcs1,
+    /// PrivateKeyInfo (RFC 5208), private keys only
+    Pkcs8,
+    /// SubjectPublicKeyInfo (RFC 5280), public keys only
+    Spki,
+    /// `ssh-rsa` public key lines and openssh-key-v1 private keys
+    OpenSsh,
+    /// JSON Web Key (RFC 7517)
+    Jwk,
+}
+
+/// A key read from a file whose type was not known beforehand
+pub enum LoadedKey {
+    Private(RsaPrivateKey),
+    Public(RsaPublicKey),
+}
+
+impl KeyFormat {
+    fn from_pem_label(label: &str) -> Result<Self> {
+        match label {
+            "RSA PRIVATE KEY" | "RSA PUBLIC KEY" => Ok(KeyFormat::Pkcs1),
+            "PRIVATE KEY" => Ok(KeyFormat::Pkcs8),
+            "PUBLIC KEY" => Ok(KeyFormat::Spki),
+            openssh::PRIVATE_KEY_LABEL => Ok(KeyFormat::OpenSsh),
+            other => Err(Error::KeyFormat(format!("unsupported PEM block '{}'", other))),
+        }
+    }
+}
+
+/// How DER based formats are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Der,
+    Pem,
+}
+
+impl Encoding {
+    fn apply(&self, label: &str, der: Vec<u8>) -> Vec<u8> {
+        match self {
+            Encoding::Der => der,
+            Encoding::Pem => pem::encode(label, &der).into_bytes(),
+        }
+    }
+}
+
+/// Encodes the public key in `format`, `encoding` is ignored for text based formats
+pub fn encode_public_key(key: &RsaPublicKey, format: KeyFormat, encoding: Encoding) -> Result<Vec<u8>> {
+    match format {
+        KeyFormat::Legacy => Ok(key.serialize().into_bytes()),
+        KeyFormat::Pkcs1 => Ok(encoding.apply("RSA PUBLIC KEY", pkcs1::public_key_to_der(key))),
+        KeyFormat::Spki => Ok(encoding.apply("PUBLIC KEY", spki::to_der(key))),
+        KeyFormat::OpenSsh => Ok(openssh::public_key_to_line(key, "").into_bytes()),
+        KeyFormat::Jwk => Ok(jwk::to_string(&jwk::public_key_to_jwk(key, None)).into_bytes()),
+        KeyFormat::Pkcs8 => Err(Error::KeyFormat("pkcs8 can only hold private keys".to_string())),
+    }
+}
+
+/// Encodes the private key in `format`, `encoding` is ignored for text based formats
+pub fn encode_private_key(key: &RsaPrivateKey, format: KeyFormat, encoding: Encoding) -> Result<Vec<u8>> {
+    match format {
+        KeyFormat::Legacy => Ok(key.serialize().into_bytes()),
+        KeyFormat::Pkcs1 => Ok(encoding.apply("RSA PRIVATE KEY", pkcs1::private_key_to_der(key)?)),
+        KeyFormat::Pkcs8 => Ok(encoding.apply("PRIVATE KEY", pkcs8::to_der(key)?)),
+        KeyFormat::OpenSsh => Ok(openssh::private_key_to_pem(key, "", None)?.into_bytes()),
+        KeyFormat::Jwk => Ok(jwk::to_string(&jwk::private_key_to_jwk(key, None)?).into_bytes()),
+        KeyFormat::Spki => Err(Error::KeyFormat("spki can only hold public keys".to_string())),
+    }
+}
+
+/// Decodes DER, the content of a PEM block or text in the given format
+fn decode_as(format: KeyFormat, data: &[u8], passphrase: Option<&str>) -> Result<LoadedKey> {
+    match format {
+        KeyFormat::Legacy => {
+            let text = Zeroizing::new(String::from_utf8(data.to_vec()).map_err(|e| {
+                e.into_bytes().zeroize();
+                Error::KeyFormat("legacy key files have to be text".to_string())
+            })?);
+            RsaPrivateKey::deserialize(text.to_string())
+                .map(LoadedKey::Private)
+                .or_else(|_| RsaPublicKey::deserialize(text.to_string()).map(LoadedKey::Public))
+        },
+        KeyFormat::Pkcs1 => pkcs1::private_key_from_der(data)
+            .map(LoadedKey::Private)
+            .or_else(|_| pkcs1::public_key_from_der(data).map(LoadedKey::Public)),
+        KeyFormat::Pkcs8 => pkcs8::from_der(data).map(LoadedKey::Private),
+        KeyFormat::Spki => spki::from_der(data).map(LoadedKey::Public),
+        KeyFormat::OpenSsh if openssh::is_private_key(data) => {
+            openssh::private_key_from_binary(data, passphrase).map(|(key, _)| LoadedKey::Private(key))
+        },
+        KeyFormat::OpenSsh => openssh::public_key_from_line(&String::from_utf8_lossy(data)).map(|(key, _)| LoadedKey::Public(key)),
+        KeyFormat::Jwk => {
+            let mut keys = jwk::from_json(&String::from_utf8_lossy(data))?;
+            match keys.len() {
+                1 => Ok(keys.remove(0).key),
+                n => Err(Error::KeyFormat(format!("expected a single JWK, found a JWK Set with {} keys", n))),
+            }
+        },
+    }
+}
+
+/// Reads a private or public key, detecting the format if `format` is None.
+/// PEM files are recognized by their label, DER by trying every DER based format.
+pub fn decode_key(data: &[u8], format: Option<KeyFormat>) -> Result<LoadedKey> {
+    decode_key_with_passphrase(data, format, None)
+}
+
+/// Like `decode_key`, `passphrase` is used to decrypt encrypted OpenSSH private keys
+pub fn decode_key_with_passphrase(data: &[u8], format: Option<KeyFormat>, passphrase: Option<&str>) -> Result<LoadedKey> {
+    if pem::is_pem(data) {
+        let (label, der) = pem::decode(&String::from_utf8_lossy(data))?;
+        let detected = KeyFormat::from_pem_label(&label)?;
+        if let Some(format) = format.filter(|f| *f != detected) {
+            return Err(Error::KeyFormat(format!("expected a {} key, found a {} key", format, detected)));
+        }
+        return decode_as(detected, &der, passphrase);
+    }
+
+    match format {
+        Some(format) => decode_as(format, data, passphrase),
+        None if openssh::is_public_key_line(data) => decode_as(KeyFormat::OpenSsh, data, passphrase),
+        None if jwk::is_json(data) => decode_as(KeyFormat::Jwk, data, passphrase),
+        // legacy files starting with the hex digit '0' look like a DER sequence, so they are tried last
+        None if data.first() == Some(&der::TAG_SEQUENCE) => [KeyFormat::Pkcs8, KeyFormat::Pkcs1, KeyFormat::Spki, KeyFormat::Legacy]
+            .into_iter()
+            .find_map(|format| decode_as(format, data, passphrase).ok())
+            .ok_or_else(|| Error::KeyFormat("unknown key format".to_string())),
+        None => decode_as(KeyFormat::Legacy, data, passphrase),
+    }
+}
+
+/// Asks for the passphrase of an encrypted key, gets the name of the key file
+pub type PassphrasePrompt<'a> = &'a dyn Fn(&str) -> Result<String>;
+
+/// Reads a key in the given format or detects it. The passphrase of encrypted keys is asked for with `passphrase`,
+/// without it they can't be read.
+pub fn read_key(file_name: &str, format: Option<KeyFormat>, passphrase: Option<PassphrasePrompt>) -> Result<LoadedKey> {
+    let data = Zeroizing::new(std::fs::read(file_name)?);
+    let passphrase = match passphrase {
+        Some(prompt) if openssh::is_encrypted(&data) => Some(prompt(file_name)?),
+        _ => None,
+    };
+    decode_key_with_passphrase(&data, format, passphrase.as_deref())
+}
+
+/// Reads a private key in any supported format
+pub fn decode_private_key(data: &[u8]) -> Result<RsaPrivateKey> {
+    match decode_key(data, None)? {
+        LoadedKey::Private(key) => Ok(key),
+        LoadedKey::Public(_) => Err(Error::KeyFormat("expected a private key, found a public key".to_string())),
+    }
+}
+
+pub fn read_private_key(file_name: &str, passphrase: Option<PassphrasePrompt>) -> Result<RsaPrivateKey> {
+    match read_key(file_name, None, passphrase)? {
+        LoadedKey::Private(key) => Ok(key),
+        LoadedKey::Public(_) => Err(Error::KeyFormat(format!("{} contains a public key, expected a private key", file_name))),
+    }
+}
+
+/// Reads a public key in any supported format, or the public part of a private key
+pub fn read_public_key(file_name: &str, passphrase: Option<PassphrasePrompt>) -> Result<RsaPublicKey> {
+    match read_key(file_name, None, passphrase)? {
+        LoadedKey::Private(key) => Ok(key.to_public_key()),
+        LoadedKey::Public(key) => Ok(key),
+    }
+}
+
+impl fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            KeyFormat::Legacy => "legacy",
+            KeyFormat::Pkcs1 => "pkcs1",
+            KeyFormat::Pkcs8 => "pkcs8",
+            KeyFormat::Spki => "spki",
+            KeyFormat::OpenSsh => "openssh",
+            KeyFormat::Jwk => "jwk",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for KeyFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "legacy" => Ok(KeyFormat::Legacy),
+            "pkcs1" => Ok(KeyFormat::Pkcs1),
+            "pkcs8" => Ok(KeyFormat::Pkcs8),
+            "spki" => Ok(KeyFormat::Spki),
+            "openssh" | "ssh" => Ok(KeyFormat::OpenSsh),
+            "jwk" => Ok(KeyFormat::Jwk),
+            invalid => Err(Error::InvalidArgs(format!("Invalid key format: {}, has to be 'legacy', 'pkcs1', 'pkcs8', 'spki', 'openssh' or 'jwk'.", invalid))),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "der" => Ok(Encoding::Der),
+            "pem" => Ok(Encoding::Pem),
+            invalid => Err(Error::InvalidArgs(format!("Invalid encoding: {}, has to be 'der' or 'pem'.", invalid))),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Der => write!(f, "der"),
+            Encoding::Pem => write!(f, "pem"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_key, encode_private_key, Encoding, KeyFormat, LoadedKey};
+    use crate::key_gen::{generate_key_pair, RsaKey};
+
+    #[test]
+    fn test_detect_formats() {
+        let (sk, pk) = generate_key_pair(256, 2);
+        for format in [KeyFormat::Legacy, KeyFormat::Pkcs1, KeyFormat::Pkcs8, KeyFormat::OpenSsh, KeyFormat::Jwk] {
+            for encoding in [Encoding::Der, Encoding::Pem] {
+                let encoded = encode_private_key(&sk, format, encoding).unwrap();
+                match decode_key(&encoded, None).unwrap() {
+                    LoadedKey::Private(key) => assert_eq!(key.serialize(), sk.serialize()),
+                    LoadedKey::Public(_) => panic!("{} {:?} decoded as public key", format, encoding),
+                }
+            }
+        }
+        for format in [KeyFormat::Legacy, KeyFormat::Pkcs1, KeyFormat::Spki, KeyFormat::OpenSsh, KeyFormat::Jwk] {
+            let encoded = super::encode_public_key(&pk, format, Encoding::Der).unwrap();
+            assert!(matches!(decode_key(&encoded, None).unwrap(), LoadedKey::Public(_)));
+        }
+    }
+
+    #[test]
+    fn test_format_mismatch() {
+        let (sk, pk) = generate_key_pair(256, 2);
+        let pem = encode_private_key(&sk, KeyFormat::Pkcs8, Encoding::Pem).unwrap();
+        assert!(decode_key(&pem, Some(KeyFormat::Pkcs1)).is_err());
+        assert!(encode_private_key(&sk, KeyFormat::Spki, Encoding::Der).is_err());
+        assert!(super::encode_public_key(&pk, KeyFormat::Pkcs8, Encoding::Der).is_err());
+        assert!(decode_key(b"garbage", None).is_err());
+        assert!(decode_key(&[0x30, 0x03, 0x02, 0x01, 0x00], None).is_err());
+    }
+
+    #[test]
+    fn test_read_encrypted_key() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let path = std::env::temp_dir().join(format!("rust_rsa_formats_encrypted_{}", std::process::id()));
+        std::fs::write(&path, super::openssh::private_key_to_pem(&sk, "", Some("secret")).unwrap()).unwrap();
+        let file_name = path.to_string_lossy();
+        assert!(super::read_private_key(&file_name, None).is_err());
+        let prompt = |name: &str| {
+            assert_eq!(name, file_name);
+            Ok("secret".to_string())
+        };
+        assert_eq!(super::read_private_key(&file_name, Some(&prompt)).unwrap().serialize(), sk.serialize());
+        assert!(matches!(super::read_public_key(&file_name, Some(&prompt)), Ok(pk) if pk.fingerprint() == sk.fingerprint()));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,236 @@
+//! PEM encoding of DER structures (RFC 7468)
+use std::io::{self, BufRead, Read, Write};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use crate::error::{Error, Result};
+
+pub fn encode(label: &str, der: &[u8]) -> String {
+    encode_with_width(label, der, 64)
+}
+
+/// Like `encode`, with lines of `width` characters. OpenSSH uses 70 instead of 64.
+pub fn encode_with_width(label: &str, der: &[u8], width: usize) -> String {
+    let encoded = STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    // base64 only contains ascii so splitting at any byte is fine
+    for line in encoded.as_bytes().chunks(width) {
+        pem += std::str::from_utf8(line).unwrap_or_default();
+        pem.push('\n');
+    }
+    pem += &format!("-----END {}-----\n", label);
+    pem
+}
+
+pub fn is_pem(data: &[u8]) -> bool {
+    String::from_utf8_lossy(data).trim_start().starts_with("-----BEGIN ")
+}
+
+/// Decodes all PEM blocks in `text`, returning their labels and content.
+/// Text outside of the blocks is ignored.
+pub fn decode_all(text: &str) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut blocks = vec![];
+    let mut lines = text.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        let label = match line.strip_prefix("-----BEGIN ").and_then(|l| l.strip_suffix("-----")) {
+            Some(label) => label.to_string(),
+            None => continue,
+        };
+        let end = format!("-----END {}-----", label);
+        let mut encoded = String::new();
+        loop {
+            match lines.next() {
+                Some(line) if line == end => break,
+                // headers are only used by encrypted legacy keys, e.g. "Proc-Type: 4,ENCRYPTED"
+                Some(line) if line.contains(':') => {
+                    return Err(Error::Encoding(format!("PEM headers are not supported in {}", label)));
+                },
+                Some(line) => encoded += line,
+                None => return Err(Error::Encoding(format!("missing end of PEM block {}", label))),
+            }
+        }
+        let der = STANDARD.decode(encoded).map_err(|e| Error::Encoding(format!("invalid base64 in PEM block: {}", e)))?;
+        blocks.push((label, der));
+    }
+    Ok(blocks)
+}
+
+/// Decodes the first PEM block in `text`
+pub fn decode(text: &str) -> Result<(String, Vec<u8>)> {
+    decode_all(text)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::Encoding("no PEM block found".to_string()))
+}
+
+/// Writes a PEM block of everything written to it, line by line. `finish` has to be called to write the end.
+pub struct PemWriter<W: Write> {
+    inner: W,
+    label: String,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> PemWriter<W> {
+    /// Bytes per line of 64 base64 characters
+    const LINE_BYTES: usize = 48;
+
+    pub fn new(mut inner: W, label: &str) -> io::Result<Self> {
+        writeln!(inner, "-----BEGIN {}-----", label)?;
+        Ok(PemWriter { inner, label: label.to_string(), buffer: Vec::with_capacity(Self::LINE_BYTES) })
+    }
+
+    /// Writes the last line and the end of the block, returns the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.buffer.is_empty() {
+            writeln!(self.inner, "{}", STANDARD.encode(&self.buffer))?;
+        }
+        writeln!(self.inner, "-----END {}-----", self.label)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for PemWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(Self::LINE_BYTES - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+        if self.buffer.len() == Self::LINE_BYTES {
+            writeln!(self.inner, "{}", STANDARD.encode(&self.buffer))?;
+            self.buffer.clear();
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// `read_line` fails with `InvalidData` for input that is not utf8
+fn not_text(e: io::Error) -> Error {
+    match e.kind() {
+        io::ErrorKind::InvalidData => Error::Encoding("input is not PEM, it is not text".to_string()),
+        _ => Error::Io(e),
+    }
+}
+
+/// Reads the content of the first PEM block, line by line
+pub struct PemReader<R: BufRead> {
+    inner: R,
+    label: String,
+    end: String,
+    buffer: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: BufRead> PemReader<R> {
+    /// Skips everything before the first PEM block
+    pub fn new(mut inner: R) -> Result<Self> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if inner.read_line(&mut line).map_err(not_text)? == 0 {
+                return Err(Error::Encoding("no PEM block found".to_string()));
+            }
+            if let Some(label) = line.trim().strip_prefix("-----BEGIN ").and_then(|l| l.strip_suffix("-----")) {
+                let end = format!("-----END {}-----", label);
+                return Ok(PemReader { inner, label: label.to_string(), end, buffer: vec![], pos: 0, done: false });
+            }
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Decodes the next line into the buffer, returns false at the end of the block.
+    /// Malformed blocks are reported as `Error::Encoding` inside the `io::Error`, which `?` unwraps again.
+    fn next_line(&mut self) -> io::Result<bool> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, Error::Encoding(msg));
+        let mut line = String::new();
+        let read = self.inner.read_line(&mut line).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData => invalid(format!("PEM block {} is not text", self.label)),
+            _ => e,
+        })?;
+        if read == 0 {
+            return Err(invalid(format!("missing end of PEM block {}", self.label)));
+        }
+        let line = line.trim();
+        if line == self.end {
+            self.done = true;
+            return Ok(false);
+        }
+        if line.contains(':') {
+            return Err(invalid(format!("PEM headers are not supported in {}", self.label)));
+        }
+        self.buffer = STANDARD.decode(line).map_err(|e| invalid(format!("invalid base64 in PEM block: {}", e)))?;
+        self.pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Read for PemReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buffer.len() {
+            if self.done || !self.next_line()? {
+                return Ok(0);
+            }
+        }
+        let len = buf.len().min(self.buffer.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buffer[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_encode() {
+        let pem = super::encode("PUBLIC KEY", &[0; 60]);
+        let lines: Vec<&str> = pem.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "-----BEGIN PUBLIC KEY-----");
+        assert_eq!(lines[1].len(), 64);
+        assert_eq!(lines[3], "-----END PUBLIC KEY-----");
+    }
+
+    #[test]
+    fn test_decode() {
+        let pem = super::encode("PRIVATE KEY", &[1, 2, 3, 4]);
+        let text = format!("comment\n{}{}", pem, super::encode("CERTIFICATE", &[5]));
+        let (label, der) = super::decode(&text).unwrap();
+        assert_eq!(label, "PRIVATE KEY");
+        assert_eq!(der, vec![1, 2, 3, 4]);
+        assert_eq!(super::decode_all(&text).unwrap().len(), 2);
+        assert!(super::decode("-----BEGIN X-----\nAAAA\n").is_err());
+        assert!(super::decode("no pem").is_err());
+    }
+
+    #[test]
+    fn test_stream() {
+        use std::io::{Read, Write};
+        for len in [0, 1, 47, 48, 49, 200] {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut writer = super::PemWriter::new(vec![], "TEST").unwrap();
+            // written in pieces that do not line up with the lines
+            for piece in data.chunks(7) {
+                writer.write_all(piece).unwrap();
+            }
+            let pem = writer.finish().unwrap();
+            assert_eq!(String::from_utf8(pem.clone()).unwrap(), super::encode("TEST", &data));
+
+            let text = [b"comment\n".to_vec(), pem].concat();
+            let mut reader = super::PemReader::new(&text[..]).unwrap();
+            assert_eq!(reader.label(), "TEST");
+            let mut decoded = vec![];
+            reader.read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, data);
+        }
+        let mut truncated = super::PemReader::new(&b"-----BEGIN X-----\nAAAA\n"[..]).unwrap();
+        assert!(truncated.read_to_end(&mut vec![]).is_err());
+        assert!(super::PemReader::new(&b"no pem"[..]).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+//! RSA keys as defined in PKCS#1 (RFC 8017 appendix A.1)
+use rug::Integer;
+
+use crate::error::{Error, Result};
+use crate::key_gen::{CrtParams, RsaKey, RsaPrivateKey, RsaPublicKey};
+
+use super::der::{self, DerReader};
+
+/// DER encoded RSAPublicKey, the public part of `key`
+pub fn public_key_to_der<K: RsaKey>(key: &K) -> Vec<u8> {
+    der::sequence(&[der::integer(key.modulus()), der::integer(key.public_exponent())])
+}
+
+pub fn public_key_from_der(data: &[u8]) -> Result<RsaPublicKey> {
+    let mut outer = DerReader::new(data);
+    let mut seq = outer.read_sequence()?;
+    let n = seq.read_integer()?;
+    let e = seq.read_integer()?;
+    seq.finish()?;
+    outer.finish()?;
+    Ok(RsaPublicKey::new(n, e))
+}
+
+/// DER encoded two prime RSAPrivateKey.
+/// The format requires the primes, so they are recovered from n, e and d for legacy keys without them.
+pub fn private_key_to_der(key: &RsaPrivateKey) -> Result<Vec<u8>> {
+    let crt = key.crt_params_or_recover()?;
+    Ok(der::sequence(&[
+        der::integer(&Integer::ZERO),
+        der::integer(key.modulus()),
+        der::integer(key.public_exponent()),
+        der::integer(key.private_exponent()),
+        der::integer(&crt.p),
+        der::integer(&crt.q),
+        der::integer(&crt.dp),
+        der::integer(&crt.dq),
+        der::integer(&crt.qinv),
+    ]))
+}
+
+pub fn private_key_from_der(data: &[u8]) -> Result<RsaPrivateKey> {
+    let mut outer = DerReader::new(data);
+    let mut seq = outer.read_sequence()?;
+    // version 1 is used for multi prime keys
+    if seq.read_integer()? != 0 {
+        return Err(Error::KeyFormat("only two prime RSAPrivateKey (version 0) is supported".to_string()));
+    }
+    let mut parts = Vec::with_capacity(8);
+    for _ in 0..8 {
+        parts.push(seq.read_integer()?);
+    }
+    seq.finish()?;
+    outer.finish()?;
+
+    let mut parts = parts.into_iter();
+    let mut next = || parts.next().unwrap_or_default();
+    let (n, e, d) = (next(), next(), next());
+    let crt = CrtParams::from_parts(&n, next(), next(), next(), next(), next())?;
+    Ok(RsaPrivateKey::from_components(n, e, d, Some(crt)))
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+
+    use crate::error::Error;
+    use crate::key_gen::{generate_key_pair, RsaKey, RsaPrivateKey};
+
+    #[test]
+    fn test_private_key_roundtrip() {
+        let (sk, pk) = generate_key_pair(256, 2);
+        let der = super::private_key_to_der(&sk).unwrap();
+        let decoded = super::private_key_from_der(&der).unwrap();
+        assert_eq!(decoded.serialize(), sk.serialize());
+        assert!(super::private_key_from_der(&super::public_key_to_der(&pk)).is_err());
+        assert_eq!(super::public_key_from_der(&super::public_key_to_der(&pk)).unwrap().serialize(), pk.serialize());
+    }
+
+    #[test]
+    fn test_primes_not_matching() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let n = Integer::from(sk.modulus());
+        for (p, q) in [(Integer::from(1), n.clone()), (Integer::from(3), Integer::from(&n / 3u32))] {
+            let mut crt = sk.crt_params().unwrap().clone();
+            (crt.p, crt.q) = (p, q);
+            let key = RsaPrivateKey::from_components(n.clone(), Integer::from(sk.public_exponent()), Integer::from(sk.private_exponent()), Some(crt));
+            let der = super::private_key_to_der(&key).unwrap();
+            assert!(matches!(super::private_key_from_der(&der), Err(Error::KeyFormat(_))));
+        }
+    }
+
+    #[test]
+    fn test_legacy_key_recovers_primes() {
+        let sk = RsaPrivateKey::from_file("sk_22-06-06T20:51").unwrap();
+        let decoded = super::private_key_from_der(&super::private_key_to_der(&sk).unwrap()).unwrap();
+        assert!(decoded.validate().is_ok());
+        assert_eq!(decoded.fingerprint(), sk.fingerprint());
+    }
+}
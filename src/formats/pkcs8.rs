@@ -0,0 +1,69 @@
+//! PrivateKeyInfo (RFC 5208, RFC 5958) containing a PKCS#1 RSAPrivateKey
+use crate::error::{Error, Result};
+use crate::key_gen::RsaPrivateKey;
+
+use super::der::{self, DerReader};
+use super::{pkcs1, spki};
+
+pub fn to_der(key: &RsaPrivateKey) -> Result<Vec<u8>> {
+    Ok(der::sequence(&[
+        der::integer(&rug::Integer::ZERO),
+        spki::rsa_algorithm_identifier(),
+        der::octet_string(&pkcs1::private_key_to_der(key)?),
+    ]))
+}
+
+pub fn from_der(data: &[u8]) -> Result<RsaPrivateKey> {
+    let mut outer = DerReader::new(data);
+    let mut seq = outer.read_sequence()?;
+    // version 1 (OneAsymmetricKey) only adds optional fields after the private key
+    let version = seq.read_integer()?;
+    if version > 1 {
+        return Err(Error::KeyFormat("unsupported PrivateKeyInfo version".to_string()));
+    }
+    spki::read_rsa_algorithm_identifier(&mut seq)?;
+    let key = pkcs1::private_key_from_der(seq.read_octet_string()?)?;
+    // [0] attributes, and in version 1 the [1] public key, are skipped
+    seq.read_optional(0xa0)?;
+    if version == 1 {
+        seq.read_optional(0x81)?;
+    }
+    seq.finish()?;
+    outer.finish()?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+
+    use crate::error::Error;
+    use crate::formats::der::{self, DerReader};
+    use crate::key_gen::{generate_key_pair, RsaKey};
+
+    #[test]
+    fn test_roundtrip() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let decoded = super::from_der(&super::to_der(&sk).unwrap()).unwrap();
+        assert_eq!(decoded.serialize(), sk.serialize());
+        assert!(super::from_der(&crate::formats::pkcs1::private_key_to_der(&sk).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_trailing_data() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let der = super::to_der(&sk).unwrap();
+        let (mut reader, mut parts) = (DerReader::new(&der), vec![]);
+        let mut seq = reader.read_sequence().unwrap();
+        while !seq.is_empty() {
+            parts.push(seq.read_raw().unwrap().to_vec());
+        }
+        parts.push(der::integer(&Integer::from(7)));
+        assert!(matches!(super::from_der(&der::sequence(&parts)), Err(Error::Encoding(_))));
+
+        // the optional fields of version 1 are skipped
+        parts[0] = der::integer(&Integer::from(1));
+        *parts.last_mut().unwrap() = der::tlv(0x81, &[0, 1, 2]);
+        assert_eq!(super::from_der(&der::sequence(&parts)).unwrap().serialize(), sk.serialize());
+    }
+}
@@ -0,0 +1,56 @@
+//! SubjectPublicKeyInfo (RFC 5280 section 4.1) containing a PKCS#1 RSAPublicKey
+use crate::error::{Error, Result};
+use crate::key_gen::{RsaKey, RsaPublicKey};
+
+use super::der::{self, DerReader};
+use super::pkcs1;
+
+/// AlgorithmIdentifier for rsaEncryption, with NULL parameters
+pub fn rsa_algorithm_identifier() -> Vec<u8> {
+    der::sequence(&[der::oid(der::OID_RSA_ENCRYPTION), der::null()])
+}
+
+/// Reads an AlgorithmIdentifier and checks that it is rsaEncryption
+pub fn read_rsa_algorithm_identifier(reader: &mut DerReader) -> Result<()> {
+    let mut algorithm = reader.read_sequence()?;
+    let oid = algorithm.read_oid()?;
+    if oid != der::OID_RSA_ENCRYPTION {
+        let oid = oid.iter().map(u64::to_string).collect::<Vec<String>>().join(".");
+        return Err(Error::KeyFormat(format!("not an RSA key, algorithm is {}", oid)));
+    }
+    // the parameters have to be NULL, but some encoders omit them
+    if !algorithm.is_empty() {
+        algorithm.read_null()?;
+    }
+    algorithm.finish()
+}
+
+/// DER encoded SubjectPublicKeyInfo of the public part of `key`
+pub fn to_der<K: RsaKey>(key: &K) -> Vec<u8> {
+    der::sequence(&[rsa_algorithm_identifier(), der::bit_string(&pkcs1::public_key_to_der(key))])
+}
+
+pub fn from_der(data: &[u8]) -> Result<RsaPublicKey> {
+    let mut outer = DerReader::new(data);
+    let mut seq = outer.read_sequence()?;
+    read_rsa_algorithm_identifier(&mut seq)?;
+    let key = pkcs1::public_key_from_der(seq.read_bit_string()?)?;
+    seq.finish()?;
+    outer.finish()?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+
+    use crate::key_gen::{RsaKey, RsaPublicKey};
+
+    #[test]
+    fn test_roundtrip() {
+        let pk = RsaPublicKey::new(Integer::from(3233), Integer::from(17));
+        let decoded = super::from_der(&super::to_der(&pk)).unwrap();
+        assert_eq!(decoded.serialize(), pk.serialize());
+        assert!(super::from_der(&crate::formats::pkcs1::public_key_to_der(&pk)).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::error::{Error, Result};
+use crate::formats::der;
+
+/// Hash functions that can be used for padding and signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+            HashAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    /// Length of the digest in bytes
+    pub fn output_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// Object identifier from RFC 8017 appendix B.1
+    pub fn oid(&self) -> &'static [u64] {
+        match self {
+            HashAlgorithm::Sha256 => der::OID_SHA256,
+            HashAlgorithm::Sha384 => der::OID_SHA384,
+            HashAlgorithm::Sha512 => der::OID_SHA512,
+        }
+    }
+
+    /// AlgorithmIdentifier with NULL parameters
+    pub fn algorithm_identifier(&self) -> Vec<u8> {
+        der::sequence(&[der::oid(self.oid()), der::null()])
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha384 => "sha384",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha256" | "sha-256" => Ok(HashAlgorithm::Sha256),
+            "sha384" | "sha-384" => Ok(HashAlgorithm::Sha384),
+            "sha512" | "sha-512" => Ok(HashAlgorithm::Sha512),
+            invalid => Err(Error::InvalidArgs(format!("Unknown hash algorithm: {}", invalid))),
+        }
+    }
+}
+
+/// Mask generation function MGF1, RFC 8017 B.2.1
+pub fn mgf1(hash: HashAlgorithm, seed: &[u8], mask_len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(mask_len + hash.output_len());
+    let mut counter: u32 = 0;
+    while mask.len() < mask_len {
+        let mut input = seed.to_vec();
+        input.extend_from_slice(&counter.to_be_bytes());
+        mask.extend_from_slice(&hash.digest(&input));
+        counter += 1;
+    }
+    mask.truncate(mask_len);
+    mask
+}
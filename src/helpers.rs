@@ -1,7 +1,8 @@
 use rug::Integer;
+use zeroize::Zeroize;
 
 pub fn pow_mod(base: &Integer, exp: &Integer, modulo: &Integer) -> Integer {
-    if &0 == exp {
+    if *exp == 0 {
         return Integer::from(1);
     }
 
@@ -9,12 +10,12 @@ pub fn pow_mod(base: &Integer, exp: &Integer, modulo: &Integer) -> Integer {
     let mut result = Integer::from(1);
     let mut exponent = Integer::from(exp);
 
-    while &0 < &exponent {
-        if Integer::from(&exponent % &2) == 1 {
-            result = Integer::from(Integer::from(result * &cur_base) % modulo);
+    while exponent > 0 {
+        if exponent.is_odd() {
+            result = (result * &cur_base) % modulo;
         }
         exponent >>= 1;
-        cur_base = Integer::from(Integer::from(&cur_base * &cur_base) % modulo);     
+        cur_base = Integer::from(&cur_base * &cur_base) % modulo;     
     }
 
     result
@@ -30,13 +31,26 @@ pub fn gcd(a: &Integer, b: &Integer) -> Integer {
     loop {
         tmp = Integer::from(&b);
         b = Integer::from(&a % &b);
-        a = Integer::from(tmp);
-        if &b == &0 {
-            return Integer::from(a);
+        a = tmp;
+        if b == 0 {
+            return a;
         }
     }
 }
 
+/// Overwrites all limbs allocated by `n` with zeros and sets it to 0.
+/// Copies GMP made while reallocating during earlier computations are not reached.
+pub fn zeroize_integer(n: &mut Integer) {
+    // SAFETY: d points to alloc limbs owned by n, which stay allocated while n is borrowed.
+    // A size of 0 is the valid representation of 0.
+    unsafe {
+        let raw = n.as_raw_mut();
+        let limbs = std::slice::from_raw_parts_mut((*raw).d.as_ptr(), (*raw).alloc.max(0) as usize);
+        limbs.zeroize();
+        (*raw).size = 0;
+    }
+}
+
 pub fn find_inverse(e: &Integer, n_phi: &Integer) -> Integer {
     let mut old_r = Integer::from(n_phi);
     let mut r = Integer::from(e);
@@ -48,20 +62,24 @@ pub fn find_inverse(e: &Integer, n_phi: &Integer) -> Integer {
     let mut r_tmp: Integer;
     let mut s_tmp: Integer;
 
-    while &r != &0 {
+    while r != 0 {
         q = Integer::from(&old_r / &r);
-        r_tmp = Integer::from(&old_r - Integer::from(&q * &r));
-        old_r = Integer::from(&r);
-        r = Integer::from(&r_tmp);
+        r_tmp = &old_r - Integer::from(&q * &r);
+        old_r = r;
+        r = r_tmp;
 
-        s_tmp = Integer::from(&old_s - Integer::from(&q * &s));
-        old_s = Integer::from(&s);
-        s = Integer::from(&s_tmp);
+        s_tmp = &old_s - Integer::from(&q * &s);
+        old_s = s;
+        s = s_tmp;
 
         
     }
-    if &old_s < &0 {
-        return Integer::from(&old_s + n_phi);
+    // the coefficients lead to the private exponent
+    zeroize_integer(&mut s);
+    if old_s < 0 {
+        let inverse = Integer::from(&old_s + n_phi);
+        zeroize_integer(&mut old_s);
+        return inverse;
     }
     old_s
 }
@@ -72,6 +90,19 @@ pub mod test {
     use super::*;
     use rug::Integer;
 
+    #[test]
+    pub fn test_zeroize_integer() {
+        let mut n = Integer::from(u128::MAX) << 200;
+        zeroize_integer(&mut n);
+        assert_eq!(n, 0);
+        // SAFETY: the limbs are allocated by n
+        let limbs = unsafe { std::slice::from_raw_parts((*n.as_raw()).d.as_ptr(), (*n.as_raw()).alloc as usize) };
+        assert!(!limbs.is_empty() && limbs.iter().all(|limb| *limb == 0));
+        // the allocated limbs are still usable
+        n += 7;
+        assert_eq!(n, 7);
+    }
+
     #[test]
     pub fn gcd_16_4() {
         let result = gcd(&Integer::from(16), &Integer::from(4));
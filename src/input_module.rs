@@ -1,10 +1,14 @@
 use std::string::FromUtf8Error;
-use rug::{Integer, Complete};
+use rug::Integer;
+use rug::integer::Order;
+use crate::error::{Error, Result};
+#[cfg(test)]
+use rug::Complete;
 
 pub fn string_to_number(input: String) -> Integer {
     let mut result = Integer::new();
 
-    for (i, byte) in input.bytes().into_iter().enumerate() {
+    for (i, byte) in input.bytes().enumerate() {
         let mut new_int = Integer::from(byte);
         new_int <<= i * 8;
         result += new_int;
@@ -14,9 +18,9 @@ pub fn string_to_number(input: String) -> Integer {
 
 }
 
-pub fn number_to_string(n: Integer) -> Result<String, FromUtf8Error>  {
+pub fn number_to_string(n: Integer) -> std::result::Result<String, FromUtf8Error>  {
     let ptr = n.as_raw();
-    let mut raw_string = String::new();
+    let mut raw_bytes = Vec::new();
 
     // SAFETY: Accessing the pointer is safe, since n will be a valid integer,
     // and the pointer only accesses memory in mpz.size, which must be valid
@@ -24,14 +28,65 @@ pub fn number_to_string(n: Integer) -> Result<String, FromUtf8Error>  {
         let mpz = *ptr;
         for i in 0..mpz.size {
             let part = *mpz.d.as_ptr().add(i as usize);
-            raw_string += &String::from_utf8(part.to_le_bytes().to_vec())?;
+            raw_bytes.extend_from_slice(&part.to_le_bytes());
         }
     }
 
+    // multi byte characters may span two limbs, so only convert once all bytes are collected
+    let raw_string = String::from_utf8(raw_bytes)?;
+
     // remove trailing zeroes on end of string
     Ok(raw_string.trim_end_matches(char::from(0)).to_string())
 }
 
+/// Converts a byte string into a non negative integer (big endian), RFC 8017 section 4.2
+pub fn os2ip(bytes: &[u8]) -> Integer {
+    Integer::from_digits(bytes, Order::Msf)
+}
+
+/// Converts a non negative integer into a byte string of length `len` (big endian), RFC 8017 section 4.1
+pub fn i2osp(x: &Integer, len: usize) -> Result<Vec<u8>> {
+    if *x < 0 || x.significant_digits::<u8>() > len {
+        return Err(Error::SizeLimit(format!("integer too large for {} bytes", len)));
+    }
+    let digits = x.to_digits::<u8>(Order::Msf);
+    let mut result = vec![0; len - digits.len()];
+    result.extend_from_slice(&digits);
+    Ok(result)
+}
+
+/// Encodes bytes as lowercase hex string
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decodes a hex string, upper or lower case
+pub fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err(Error::Encoding("hex string has to consist of pairs of hex digits".to_string()));
+    }
+    (0..hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| Error::Encoding(format!("invalid hex digits: {}", &hex[i..i + 2]))))
+        .collect()
+}
+
+#[test]
+fn test_i2osp_os2ip() {
+    let x = Integer::from(0x0102_0304);
+    assert_eq!(i2osp(&x, 6).unwrap(), vec![0, 0, 1, 2, 3, 4]);
+    assert_eq!(os2ip(&[0, 0, 1, 2, 3, 4]), x);
+    assert!(i2osp(&x, 3).is_err());
+    assert_eq!(i2osp(&Integer::new(), 2).unwrap(), vec![0, 0]);
+}
+
+#[test]
+fn test_hex() {
+    assert_eq!(from_hex(&to_hex(&[0, 0xab, 0x10])).unwrap(), vec![0, 0xab, 0x10]);
+    assert_eq!(from_hex("0AbC").unwrap(), vec![0x0a, 0xbc]);
+    assert!(from_hex("abc").is_err());
+    assert!(from_hex("zz").is_err());
+}
+
 #[test]
 fn test_string_to_number_number_to_string() {
     let string = "Sed ut perspiciatis unde omnis iste natus error sit voluptatem accusantium doloremque laudantium, totam rem aperiam, eaque ipsa quae ab illo inventore veritatis et quasi architecto beatae vitae dicta sunt explicabo. Nemo enim ipsam voluptatem quia voluptas sit aspernatur aut odit aut fugit, sed quia consequuntur magni dolores eos qui ratione voluptatem sequi nesciunt. Neque porro quisquam est, qui dolorem ipsum quia dolor sit amet, consectetur, adipisci velit, sed quia non numquam eius modi tempora incidunt ut labore et dolore magnam aliquam quaerat voluptatem. Ut enim ad minima veniam, quis nostrum exercitationem ullam corporis suscipit laboriosam, nisi ut aliquid ex ea commodi consequatur? Quis autem vel eum iure reprehenderit qui in ea voluptate velit esse quam nihil molestiae consequatur, vel illum qui dolorem eum fugiat quo voluptas nulla pariatur?\r\n".to_string();
@@ -52,5 +107,5 @@ fn test_number_to_string() {
 fn test_number_to_string_2() {
     let n = Integer::parse("644676918490704224893746076702864758815769362018048444027513970491065913879320857727963628610186314853493450087587915128223179999006733731225350994123001866792969966398401700200");
     let n = n.unwrap().complete();
-    let s = number_to_string(n).unwrap();
+    assert!(number_to_string(n).is_ok());
 }
\ No newline at end of file
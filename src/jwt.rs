@@ -0,0 +1,250 @@
+//! JSON Web Tokens (RFC 7519) in JWS compact serialization (RFC 7515),
+//! signed with the RSA algorithms of RFC 7518 sections 3.3 and 3.5
+use std::fmt;
+use std::str::FromStr;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+use crate::hash::HashAlgorithm;
+use crate::key_gen::{RsaPrivateKey, RsaPublicKey};
+use crate::signature::SignatureScheme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JwtAlgorithm {
+    Rs256,
+    Rs384,
+    Rs512,
+    Ps256,
+}
+
+impl JwtAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JwtAlgorithm::Rs256 => "RS256",
+            JwtAlgorithm::Rs384 => "RS384",
+            JwtAlgorithm::Rs512 => "RS512",
+            JwtAlgorithm::Ps256 => "PS256",
+        }
+    }
+
+    /// The algorithm with exactly this `alg` name, header values are case sensitive (RFC 7515 section 4.1.1)
+    pub fn from_name(name: &str) -> Option<Self> {
+        [JwtAlgorithm::Rs256, JwtAlgorithm::Rs384, JwtAlgorithm::Rs512, JwtAlgorithm::Ps256]
+            .into_iter()
+            .find(|alg| alg.name() == name)
+    }
+
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            JwtAlgorithm::Rs256 => SignatureScheme::Pkcs1(HashAlgorithm::Sha256),
+            JwtAlgorithm::Rs384 => SignatureScheme::Pkcs1(HashAlgorithm::Sha384),
+            JwtAlgorithm::Rs512 => SignatureScheme::Pkcs1(HashAlgorithm::Sha512),
+            JwtAlgorithm::Ps256 => SignatureScheme::Pss(HashAlgorithm::Sha256),
+        }
+    }
+}
+
+/// What a token has to fulfill to be accepted by `verify`
+#[derive(Debug, Clone)]
+pub struct Validation {
+    /// Algorithms the token may be signed with. The `alg` header is chosen by whoever created the token,
+    /// so it is only used to look up the algorithm, never to decide which algorithms are acceptable.
+    pub algorithms: Vec<JwtAlgorithm>,
+    /// Seconds of clock skew tolerated when checking `exp` and `nbf`
+    pub leeway: i64,
+    /// Current time in seconds since the epoch
+    pub now: i64,
+}
+
+impl Validation {
+    pub fn new(algorithms: Vec<JwtAlgorithm>) -> Self {
+        Validation { algorithms, leeway: 0, now: chrono::Utc::now().timestamp() }
+    }
+}
+
+fn encode_part(value: &Value) -> String {
+    URL_SAFE_NO_PAD.encode(value.to_string())
+}
+
+fn decode_part(part: &str, name: &str) -> Result<Value> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| Error::Encoding(format!("invalid base64url in JWT {}: {}", name, e)))?;
+    match serde_json::from_slice(&bytes) {
+        Ok(value @ Value::Object(_)) => Ok(value),
+        _ => Err(Error::Encoding(format!("JWT {} is not a JSON object", name))),
+    }
+}
+
+/// Signs `claims`, which have to be a JSON object. `kid` is added to the header if given.
+pub fn sign(claims: &Value, key: &RsaPrivateKey, alg: JwtAlgorithm, kid: Option<&str>) -> Result<String> {
+    if !claims.is_object() {
+        return Err(Error::InvalidArgs("JWT claims have to be a JSON object".to_string()));
+    }
+    let mut header = json!({ "alg": alg.name(), "typ": "JWT" });
+    if let Some(kid) = kid {
+        header["kid"] = json!(kid);
+    }
+    let signing_input = format!("{}.{}", encode_part(&header), encode_part(claims));
+    let signature = alg.scheme().sign(key, signing_input.as_bytes())?;
+    Ok(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature)))
+}
+
+/// Splits a compact JWS into header, payload and signature
+fn split(token: &str) -> Result<[&str; 3]> {
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    match parts[..] {
+        [header, payload, signature] => Ok([header, payload, signature]),
+        _ => Err(Error::Encoding(format!("JWT has to consist of 3 parts, found {}", parts.len()))),
+    }
+}
+
+/// Returns the header without verifying anything, e.g. to find the key by `kid`
+pub fn decode_header(token: &str) -> Result<Value> {
+    decode_part(split(token)?[0], "header")
+}
+
+/// Verifies the signature and the `exp` and `nbf` claims, returning the claims
+pub fn verify(token: &str, key: &RsaPublicKey, validation: &Validation) -> Result<Value> {
+    let [header, payload, signature] = split(token)?;
+    let header_value = decode_part(header, "header")?;
+    let alg = match header_value.get("alg") {
+        Some(Value::String(alg)) => alg.clone(),
+        _ => return Err(Error::Verification("JWT header has no 'alg'".to_string())),
+    };
+    let alg = JwtAlgorithm::from_name(&alg)
+        .filter(|alg| validation.algorithms.contains(alg))
+        .ok_or_else(|| Error::Verification(format!("algorithm '{}' is not allowed", alg)))?;
+    if header_value.get("crit").is_some() {
+        return Err(Error::Verification("critical header parameters are not supported".to_string()));
+    }
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|e| Error::Encoding(format!("invalid base64url in JWT signature: {}", e)))?;
+    alg.scheme().verify(key, format!("{}.{}", header, payload).as_bytes(), &signature)?;
+
+    let claims = decode_part(payload, "payload")?;
+    if let Some(exp) = numeric_date(&claims, "exp")? {
+        if validation.now >= exp.saturating_add(validation.leeway) {
+            return Err(Error::Verification(format!("token expired at {}", format_date(exp))));
+        }
+    }
+    if let Some(nbf) = numeric_date(&claims, "nbf")? {
+        if validation.now.saturating_add(validation.leeway) < nbf {
+            return Err(Error::Verification(format!("token is not valid before {}", format_date(nbf))));
+        }
+    }
+    Ok(claims)
+}
+
+/// Reads a NumericDate claim, fractional seconds are truncated
+fn numeric_date(claims: &Value, name: &str) -> Result<Option<i64>> {
+    match claims.get(name) {
+        None => Ok(None),
+        Some(value) => match value.as_f64() {
+            // `as` would saturate dates out of range, so they are rejected instead
+            Some(date) if date.abs() < i64::MAX as f64 => Ok(Some(date as i64)),
+            Some(_) => Err(Error::Verification(format!("claim '{}' is out of range", name))),
+            None => Err(Error::Verification(format!("claim '{}' is not a number", name))),
+        },
+    }
+}
+
+fn format_date(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0).map_or(timestamp.to_string(), |date| date.to_rfc3339())
+}
+
+impl fmt::Display for JwtAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for JwtAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "RS256" => Ok(JwtAlgorithm::Rs256),
+            "RS384" => Ok(JwtAlgorithm::Rs384),
+            "RS512" => Ok(JwtAlgorithm::Rs512),
+            "PS256" => Ok(JwtAlgorithm::Ps256),
+            _ => Err(Error::InvalidArgs(format!("Invalid JWT algorithm: {}, has to be 'RS256', 'RS384', 'RS512' or 'PS256'.", s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::error::Error;
+    use crate::key_gen::generate_key_pair;
+
+    #[test]
+    fn test_sign_verify() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        let claims = json!({ "sub": "1234567890", "exp": 2000, "nbf": 1000 });
+        for alg in [JwtAlgorithm::Rs256, JwtAlgorithm::Rs384, JwtAlgorithm::Rs512, JwtAlgorithm::Ps256] {
+            let token = sign(&claims, &sk, alg, Some("key-1")).unwrap();
+            let mut validation = Validation::new(vec![alg]);
+            validation.now = 1500;
+            assert_eq!(verify(&token, &pk, &validation).unwrap(), claims);
+            assert_eq!(decode_header(&token).unwrap()["kid"], "key-1");
+        }
+    }
+
+    #[test]
+    fn test_time_claims() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        let token = sign(&json!({ "exp": 2000, "nbf": 1000 }), &sk, JwtAlgorithm::Rs256, None).unwrap();
+        let mut validation = Validation::new(vec![JwtAlgorithm::Rs256]);
+        validation.now = 2000;
+        assert!(matches!(verify(&token, &pk, &validation), Err(Error::Verification(_))));
+        validation.leeway = 10;
+        assert!(verify(&token, &pk, &validation).is_ok());
+        validation.now = 900;
+        assert!(verify(&token, &pk, &validation).is_err());
+        let token = sign(&json!({ "exp": "tomorrow" }), &sk, JwtAlgorithm::Rs256, None).unwrap();
+        assert!(verify(&token, &pk, &Validation::new(vec![JwtAlgorithm::Rs256])).is_err());
+
+        // dates and leeway at the limits of i64 don't overflow
+        let token = sign(&json!({ "exp": 1e300 }), &sk, JwtAlgorithm::Rs256, None).unwrap();
+        assert!(matches!(verify(&token, &pk, &Validation::new(vec![JwtAlgorithm::Rs256])), Err(Error::Verification(_))));
+        let token = sign(&json!({ "exp": 4_000_000_000_000_000_000i64, "nbf": 0 }), &sk, JwtAlgorithm::Rs256, None).unwrap();
+        validation.leeway = i64::MAX;
+        assert!(verify(&token, &pk, &validation).is_ok());
+        validation.leeway = 0;
+        validation.now = i64::MAX;
+        assert!(matches!(verify(&token, &pk, &validation), Err(Error::Verification(_))));
+    }
+
+    #[test]
+    fn test_algorithm_pinning() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        let token = sign(&json!({ "sub": "a" }), &sk, JwtAlgorithm::Ps256, None).unwrap();
+        assert!(verify(&token, &pk, &Validation::new(vec![JwtAlgorithm::Rs256])).is_err());
+
+        // alg none and HMAC with the public key as secret are never accepted
+        let [_, payload, _] = split(&token).unwrap();
+        let all = Validation::new(vec![JwtAlgorithm::Rs256, JwtAlgorithm::Rs384, JwtAlgorithm::Rs512, JwtAlgorithm::Ps256]);
+        for alg in ["none", "HS256"] {
+            let header = encode_part(&json!({ "alg": alg, "typ": "JWT" }));
+            let forged = format!("{}.{}.", header, payload);
+            assert!(matches!(verify(&forged, &pk, &all), Err(Error::Verification(_))), "{}", alg);
+        }
+        assert!(verify("a.b", &pk, &all).is_err());
+
+        // alg is case sensitive, even with a valid signature
+        let header = encode_part(&json!({ "alg": "rs256", "typ": "JWT" }));
+        let signing_input = format!("{}.{}", header, payload);
+        let signature = JwtAlgorithm::Rs256.scheme().sign(&sk, signing_input.as_bytes()).unwrap();
+        let token = format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature));
+        assert!(matches!(verify(&token, &pk, &all), Err(Error::Verification(message)) if message.contains("'rs256' is not allowed")));
+    }
+}
@@ -1,39 +1,70 @@
 use rug::{Integer, Complete};
-use rug::integer::ParseIntegerError;
+use rug::integer::Order;
 use rug::ops::Pow;
 use rug::rand::RandState;
-use std::fs::File;
-use std::io::{Write, Read, ErrorKind};
+use std::borrow::Cow;
+use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc};
-use rand;
-use std::{thread};
-use crate::helpers::{gcd, find_inverse, pow_mod};
+use std::thread;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use crate::error::{Error, Result};
+use crate::formats::{openssh, spki};
+use crate::hash::HashAlgorithm;
+use crate::output::{self, FileMode};
+use zeroize::{Zeroize, Zeroizing};
+use crate::helpers::{gcd, find_inverse, zeroize_integer};
+use crate::primitives;
 use crate::prime_gen::is_prime;
 use crate::prime_gen::sieve_of_eratosthenes::Sieve;
 
 const SEPARATOR: &str = "\n=======\n";
 
 pub trait RsaKey {
-    fn from_file(file_name: &str) -> std::io::Result<Self> where Self: Sized {
-        let mut buffer = String::new();
-        let _ = File::open(file_name)?.read_to_string(&mut buffer)?;
-        let key = Self::deserialize(buffer);
-        match key {
-            Ok(k) => Ok(k),
-            Err(e) => Err(std::io::Error::new(ErrorKind::Other, e.to_string())),
-        }
+    fn from_file(file_name: &str) -> Result<Self> where Self: Sized {
+        // read at once instead of growing a buffer, which would leave copies of private keys in freed memory
+        let text = String::from_utf8(std::fs::read(file_name)?).map_err(|e| {
+            e.into_bytes().zeroize();
+            Error::KeyFormat("legacy key files have to be text".to_string())
+        })?;
+        Self::deserialize(text)
     }
     fn get_parts(&self) -> Vec<&Integer>;
-    fn deserialize(key: String) -> Result<Self, ParseIntegerError> where Self: Sized;
-    fn write_to_file(&self, file_name: String) -> std::io::Result<()> {
-        let s = self.serialize();
-        let mut file = File::create(file_name)?;
-        file.write_all(&s.as_bytes())?;
-        Ok(())
+    fn deserialize(key: String) -> Result<Self> where Self: Sized;
+    /// Writes the serialized key, private keys are only readable by the owner
+    fn write_to_file(&self, file_name: &str, overwrite: bool) -> Result<()> {
+        let mode = if self.is_private() { FileMode::Private } else { FileMode::Default };
+        output::write_file(file_name, Zeroizing::new(self.serialize()).as_bytes(), mode, overwrite)
     }
     fn serialize(&self) -> String;
-    fn into_hex(n: &Integer ) -> String {
+    fn modulus(&self) -> &Integer;
+    fn public_exponent(&self) -> &Integer;
+    fn is_private(&self) -> bool;
+
+    /// Length of the modulus in bytes
+    #[inline(always)]
+    fn size(&self) -> usize {
+        (self.modulus().significant_bits() as usize).div_ceil(8)
+    }
+
+    fn has_crt_params(&self) -> bool {
+        false
+    }
+
+    /// SHA-256 of the DER encoded SubjectPublicKeyInfo, the same for a private key and its public key
+    fn fingerprint(&self) -> Vec<u8> where Self: Sized {
+        HashAlgorithm::Sha256.digest(&spki::to_der(self))
+    }
+
+    /// Fingerprint as shown by `ssh-keygen -l`
+    fn ssh_fingerprint(&self) -> String where Self: Sized {
+        let digest = HashAlgorithm::Sha256.digest(&openssh::public_key_blob(self));
+        format!("SHA256:{}", STANDARD_NO_PAD.encode(digest))
+    }
+
+    fn into_hex(n: &Integer ) -> String where Self: Sized {
+        use std::fmt::Write;
         let n_ptr = n.as_raw();
         let mut raw_string = String::new();
     
@@ -41,11 +72,13 @@ pub trait RsaKey {
         // and the pointer only accesses memory in mpz.size, which must be valid
         unsafe {
             let mpz = *n_ptr;
+            // allocated once and written without temporary strings, so no copies of private values are left behind
+            raw_string.reserve(mpz.size.unsigned_abs() as usize * 16);
             // we need to go backwarts, since the limbs(64 bit) of the number are stored in reverse order apparently
             for i in (0..mpz.size).rev() {
                 let part = *mpz.d.as_ptr().add(i as usize);
                 for byte in part.to_be_bytes(){
-                    raw_string += &format!("{:0>2x}", byte);
+                    let _ = write!(raw_string, "{:0>2x}", byte);
                 }
             }
         }
@@ -54,22 +87,196 @@ pub trait RsaKey {
     }
 }
 
-#[derive(Debug)]
+/// The private exponent and CRT parameters are wiped on drop and left out of Debug
 pub struct RsaPrivateKey {
     d: Integer,
     n: Integer,
     public_key_part: Integer,
+    crt: Option<CrtParams>,
+}
+
+/// The primes of a private key and the values derived from them, 
+/// which are needed to use the chinese remainder theorem (RFC 8017 section 3.2). They are wiped on drop.
+#[derive(Clone)]
+pub struct CrtParams {
+    pub p: Integer,
+    pub q: Integer,
+    /// d mod (p - 1)
+    pub dp: Integer,
+    /// d mod (q - 1)
+    pub dq: Integer,
+    /// q^-1 mod p
+    pub qinv: Integer,
+}
+
+impl fmt::Debug for CrtParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CrtParams { <redacted> }")
+    }
+}
+
+impl Drop for CrtParams {
+    fn drop(&mut self) {
+        for n in [&mut self.p, &mut self.q, &mut self.dp, &mut self.dq, &mut self.qinv] {
+            zeroize_integer(n);
+        }
+    }
+}
+
+/// The CRT values divide by p - 1 and q - 1, and the prime test doesn't terminate for 1
+fn check_primes(n: &Integer, p: &Integer, q: &Integer) -> Result<()> {
+    match *p > 1 && *q > 1 && Integer::from(p * q) == *n {
+        true => Ok(()),
+        false => Err(Error::KeyFormat("the primes don't match the modulus".to_string())),
+    }
+}
+
+impl CrtParams {
+    pub fn new(p: Integer, q: Integer, d: &Integer) -> Self {
+        let mut p_1 = Integer::from(&p - 1);
+        let mut q_1 = Integer::from(&q - 1);
+        let dp = Integer::from(d % &p_1);
+        let dq = Integer::from(d % &q_1);
+        zeroize_integer(&mut p_1);
+        zeroize_integer(&mut q_1);
+        let qinv = find_inverse(&q, &p);
+        CrtParams { p, q, dp, dq, qinv }
+    }
+
+    /// CRT parameters read from a key, p and q have to be factors of `n` greater than 1
+    pub fn from_parts(n: &Integer, p: Integer, q: Integer, dp: Integer, dq: Integer, qinv: Integer) -> Result<Self> {
+        // dropping the parameters on error wipes them
+        let crt = CrtParams { p, q, dp, dq, qinv };
+        check_primes(n, &crt.p, &crt.q)?;
+        Ok(crt)
+    }
+
+    /// Like `new`, for keys that only store the primes and qinv
+    pub fn from_primes(n: &Integer, mut p: Integer, mut q: Integer, d: &Integer) -> Result<Self> {
+        if let Err(e) = check_primes(n, &p, &q) {
+            zeroize_integer(&mut p);
+            zeroize_integer(&mut q);
+            return Err(e);
+        }
+        Ok(CrtParams::new(p, q, d))
+    }
+
+    /// Recovers the primes from the modulus and both exponents, for keys that were stored without them
+    /// (NIST SP 800-56B appendix C.2)
+    pub fn recover(n: &Integer, e: &Integer, d: &Integer) -> Result<Self> {
+        let not_recovered = || Error::InvalidKey("could not recover the primes from n, e and d".to_string());
+        // k = d * e - 1 is a multiple of lambda(n), write it as 2^t * r with r odd
+        let mut k = (d * e).complete() - 1u32;
+        if k <= 0 || k.is_odd() || *n <= 3 {
+            zeroize_integer(&mut k);
+            return Err(not_recovered());
+        }
+        let t = k.find_one(0).unwrap_or(0);
+        let mut r = Integer::from(&k >> t);
+        zeroize_integer(&mut k);
+        let n_1 = Integer::from(n - 1);
+
+        let mut rng = RandState::new();
+        rng.seed(&Integer::from(rand::random::<u64>()));
+        let mut factor = None;
+        // every try finds a factor with probability of at least 1/2
+        for _ in 0..100 {
+            let g: Integer = Integer::from(n - 3).random_below(&mut rng) + 2;
+            // pow_mod reuses g for the result, so only y has to be wiped
+            let mut y = match g.pow_mod(&r, n) {
+                Ok(y) => y,
+                Err(mut g) => {
+                    zeroize_integer(&mut g);
+                    break;
+                },
+            };
+            for _ in 0..t {
+                if y == 1 || y == n_1 {
+                    break;
+                }
+                let mut x = Integer::from(y.square_ref()) % n;
+                if x == 1 {
+                    // y is a non trivial square root of 1, so gcd(y - 1, n) is a factor
+                    let mut y_1 = Integer::from(&y - 1);
+                    factor = Some(gcd(&y_1, n));
+                    zeroize_integer(&mut y_1);
+                }
+                std::mem::swap(&mut x, &mut y);
+                zeroize_integer(&mut x);
+            }
+            zeroize_integer(&mut y);
+            if factor.is_some() {
+                break;
+            }
+        }
+        zeroize_integer(&mut r);
+        match factor {
+            Some(p) => {
+                let q = Integer::from(n / &p);
+                let (p, q) = if p > q { (p, q) } else { (q, p) };
+                Ok(CrtParams::new(p, q, d))
+            },
+            None => Err(not_recovered()),
+        }
+    }
 }
 
 impl RsaPrivateKey {
     pub fn new(bits: u32) -> Self {
         let cores = num_cpus::get_physical();
         let (p, q) = generate_p_q(bits, cores);
-        let n_phi = calculate_n_phi(&p, &q);
+        let mut n_phi = calculate_n_phi(&p, &q);
         let e = generate_e(&n_phi);
         let d = generate_d(&e, &n_phi);
+        zeroize_integer(&mut n_phi);
         let n = Integer::from(&p * &q);
-        RsaPrivateKey { d, n , public_key_part: e }
+        let crt = Some(CrtParams::new(p, q, &d));
+        RsaPrivateKey { d, n , public_key_part: e, crt }
+    }
+
+    /// Creates a private key from its parts, without checking if they are consistent (see `validate`)
+    pub fn from_components(n: Integer, e: Integer, d: Integer, crt: Option<CrtParams>) -> Self {
+        RsaPrivateKey { d, n, public_key_part: e, crt }
+    }
+
+    #[inline(always)]
+    pub fn crt_params(&self) -> Option<&CrtParams> {
+        self.crt.as_ref()
+    }
+
+    /// The CRT parameters, recovered from n, e and d if the key doesn't contain them
+    pub fn crt_params_or_recover(&self) -> Result<Cow<'_, CrtParams>> {
+        match &self.crt {
+            Some(crt) => Ok(Cow::Borrowed(crt)),
+            None => Ok(Cow::Owned(CrtParams::recover(&self.n, &self.public_key_part, &self.d)?)),
+        }
+    }
+
+    /// Returns the public key belonging to this private key
+    pub fn to_public_key(&self) -> RsaPublicKey {
+        RsaPublicKey::new(Integer::from(&self.n), Integer::from(&self.public_key_part))
+    }
+
+    #[inline(always)]
+    pub(crate) fn private_exponent(&self) -> &Integer {
+        &self.d
+    }
+}
+
+impl fmt::Debug for RsaPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RsaPrivateKey")
+            .field("n", &self.n)
+            .field("e", &self.public_key_part)
+            .field("d", &format_args!("<redacted>"))
+            .field("crt", &self.crt)
+            .finish()
+    }
+}
+
+impl Drop for RsaPrivateKey {
+    fn drop(&mut self) {
+        zeroize_integer(&mut self.d);
     }
 }
 
@@ -77,55 +284,87 @@ impl RsaKey for RsaPrivateKey {
 
     #[inline(always)]
     fn get_parts(&self) -> Vec<&Integer> {
-        let mut parts = vec![];
-        parts.push(&self.d);
-        parts.push(&self.n);
-        parts.push(&self.public_key_part);
+        let mut parts = vec![&self.d, &self.n, &self.public_key_part];
+        if let Some(crt) = &self.crt {
+            parts.extend([&crt.p, &crt.q, &crt.dp, &crt.dq, &crt.qinv]);
+        }
         parts
     }
 
-    fn deserialize(key: String) -> Result<Self, ParseIntegerError> {
-        let parts: Vec<&str> = key.split(SEPARATOR).collect();
-        let d = Integer::parse_radix(parts[0], 16)?.complete();
-        let n = Integer::parse_radix(parts[1], 16)?.complete();
-        let public_key_part = Integer::parse_radix(parts[2], 16)?.complete();
-        Ok(RsaPrivateKey { d, n, public_key_part })
+    /// Keys written before CRT parameters were stored only consist of d, n and e
+    fn deserialize(key: String) -> Result<Self> {
+        let key = Zeroizing::new(key);
+        let mut parts = vec![];
+        for part in split_parts(&key, &[3, 8])? {
+            match parse_hex_part(part) {
+                Ok(n) => parts.push(n),
+                Err(e) => {
+                    parts.iter_mut().for_each(zeroize_integer);
+                    return Err(e);
+                },
+            }
+        }
+        // the values are moved out instead of cloned, so no copies are left behind
+        let with_crt = parts.len() == 8;
+        let mut parts = parts.into_iter();
+        let mut next = || parts.next().unwrap_or_default();
+        let (mut d, n, e) = (next(), next(), next());
+        let crt = match with_crt.then(|| CrtParams::from_parts(&n, next(), next(), next(), next(), next())).transpose() {
+            Ok(crt) => crt,
+            Err(e) => {
+                zeroize_integer(&mut d);
+                return Err(e);
+            },
+        };
+        Ok(RsaPrivateKey::from_components(n, e, d, crt))
     }
 
     fn serialize(&self) -> String {
-        let parts = self.get_parts();
-        assert_eq!(parts.len(), 3);
-        let mut buffer = String::new();
+        let parts = Zeroizing::new(self.get_parts().into_iter().map(RsaPrivateKey::into_hex).collect::<Vec<String>>());
+        parts.join(SEPARATOR)
+    }
 
-        buffer += &RsaPrivateKey::into_hex(parts[0]); 
-        buffer += SEPARATOR;
-        buffer += &RsaPrivateKey::into_hex(parts[1]); 
-        buffer += SEPARATOR;
-        buffer += &RsaPrivateKey::into_hex(parts[2]);
+    #[inline(always)]
+    fn modulus(&self) -> &Integer {
+        &self.n
+    }
 
-        buffer
+    #[inline(always)]
+    fn public_exponent(&self) -> &Integer {
+        &self.public_key_part
+    }
+
+    fn is_private(&self) -> bool {
+        true
+    }
+
+    fn has_crt_params(&self) -> bool {
+        self.crt.is_some()
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct RsaPublicKey {
     e: Integer,
     n: Integer,
 }
 
+impl RsaPublicKey {
+    pub fn new(n: Integer, e: Integer) -> Self {
+        RsaPublicKey { e, n }
+    }
+}
+
 impl RsaKey for RsaPublicKey {
 
     fn get_parts(&self) -> Vec<&Integer> {
-        let mut parts = vec![];
-        parts.push(&self.e);
-        parts.push(&self.n);
-
-        parts
+        vec![&self.e, &self.n]
     }
 
-    fn deserialize(key: String) -> Result<Self, ParseIntegerError> where Self: Sized {
-        let parts: Vec<&str> = key.split(SEPARATOR).collect();
-        let e = Integer::from_str_radix(parts[0], 16)?;
-        let n = Integer::from_str_radix(parts[1], 16)?;
+    fn deserialize(key: String) -> Result<Self> where Self: Sized {
+        let parts = split_parts(&key, &[2])?;
+        let e = parse_hex_part(parts[0])?;
+        let n = parse_hex_part(parts[1])?;
 
         Ok(RsaPublicKey { e, n })
     }
@@ -140,14 +379,67 @@ impl RsaKey for RsaPublicKey {
 
         buffer
     }
+
+    #[inline(always)]
+    fn modulus(&self) -> &Integer {
+        &self.n
+    }
+
+    #[inline(always)]
+    fn public_exponent(&self) -> &Integer {
+        &self.e
+    }
+
+    fn is_private(&self) -> bool {
+        false
+    }
+}
+
+/// Splits a serialized key into its hex encoded parts, 
+/// returning an error if the number of parts is not one of `expected`
+fn split_parts<'a>(key: &'a str, expected: &[usize]) -> Result<Vec<&'a str>> {
+    let parts: Vec<&str> = key.trim().split(SEPARATOR).collect();
+    if !expected.contains(&parts.len()) {
+        let expected = expected.iter().map(usize::to_string).collect::<Vec<String>>().join(" or ");
+        return Err(Error::KeyFormat(format!("expected {} parts, found {}", expected, parts.len())));
+    }
+    Ok(parts)
+}
+
+/// Decodes the hex digits to a buffer that is wiped, parsing them with rug would leave a copy
+fn parse_hex_part(part: &str) -> Result<Integer> {
+    let hex = part.trim().as_bytes();
+    if hex.is_empty() {
+        return Err(Error::KeyFormat("invalid hex value: no digits".to_string()));
+    }
+    let mut bytes = Zeroizing::new(vec![0u8; hex.len().div_ceil(2)]);
+    let len = bytes.len();
+    for (i, &digit) in hex.iter().rev().enumerate() {
+        let nibble = (digit as char).to_digit(16).ok_or_else(|| Error::KeyFormat("invalid hex value: invalid digit found in string".to_string()))?;
+        bytes[len - 1 - i / 2] |= (nibble as u8) << (4 * (i % 2));
+    }
+    Ok(Integer::from_digits(&bytes[..], Order::Msf))
 }
 
 /// Wrapper for Integer, to share it between threads.
-/// These will always be immutable, so it is safe to share them
+/// These will always be immutable, so it is safe to share them.
+/// The primes that are not used are wiped on drop.
 struct SendInteger {
     n: Integer,
 }
 
+impl Drop for SendInteger {
+    fn drop(&mut self) {
+        zeroize_integer(&mut self.n);
+    }
+}
+
+impl SendInteger {
+    fn take(mut self) -> Integer {
+        std::mem::take(&mut self.n)
+    }
+}
+
 unsafe impl Sync for SendInteger {}
 unsafe impl Send for SendInteger {}
 
@@ -155,10 +447,11 @@ unsafe impl Send for SendInteger {}
 
 pub fn generate_p_q(bits: u32, n_threads: usize) -> (Integer, Integer) {
 
-    assert!(n_threads >= 2);
+    // a single thread keeps searching until both primes are found
+    let n_threads = n_threads.max(1);
     let found_primes = Arc::new(AtomicBool::new(false));  
     let sieve = Arc::new(Sieve::new(10000));
-    let (tx, rx) = mpsc::sync_channel(n_threads as usize);
+    let (tx, rx) = mpsc::sync_channel(n_threads);
     
     for _ in 0..n_threads {
         let t = tx.clone();
@@ -167,7 +460,7 @@ pub fn generate_p_q(bits: u32, n_threads: usize) -> (Integer, Integer) {
 
         thread::spawn(move || {
             let mut rng = RandState::new();
-            let lower_bound = Integer::from(Integer::from(2_i16).pow(bits - 1));
+            let lower_bound = Integer::from(2_i16).pow(bits - 1);
             let seed = rand::random::<i16>();
             rng.seed(&Integer::from(seed));
 
@@ -179,22 +472,26 @@ pub fn generate_p_q(bits: u32, n_threads: usize) -> (Integer, Integer) {
             }
         });
     }
-    let p = Integer::from(rx.recv().unwrap().n);
-    let q = Integer::from(rx.recv().unwrap().n);
+    let p = rx.recv().unwrap().take();
+    let mut q = rx.recv().unwrap().take();
+    while q == p {
+        zeroize_integer(&mut q);
+        q = rx.recv().unwrap().take();
+    }
     found_primes.swap(true, Ordering::SeqCst); // signal other threads to stop searching
     (p, q)
 }
 
 #[inline(always)]
 pub fn calculate_n_phi(p: &Integer, q: &Integer) -> Integer {
-    Integer::from(Integer::from(p - 1) * Integer::from(q - 1))
+    Integer::from(p - 1) * Integer::from(q - 1)
 }
 
 pub fn generate_e(n_phi: &Integer) -> Integer {
     let mut rng = RandState::new();
     let mut e;
     loop {
-        e = Integer::random_below(Integer::from(n_phi), &mut rng); 
+        e = Integer::from(n_phi).random_below(&mut rng); 
         if gcd(n_phi, &e) == 1 {
             return e;
         }
@@ -205,21 +502,74 @@ pub fn generate_d(e: &Integer, n_phi: &Integer) -> Integer {
     find_inverse(e, n_phi)
 }
 
+/// Public exponent of generated keys, random or a fixed value like 65537
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicExponent {
+    Random,
+    Fixed(u64),
+}
+
+impl std::fmt::Display for PublicExponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PublicExponent::Random => write!(f, "random"),
+            PublicExponent::Fixed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::str::FromStr for PublicExponent {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "random" => Ok(PublicExponent::Random),
+            e => match e.parse::<u64>() {
+                Ok(e) if e >= 3 && e % 2 == 1 => Ok(PublicExponent::Fixed(e)),
+                _ => Err(Error::InvalidArgs(format!("Invalid public exponent: {}, has to be 'random' or an odd number of at least 3.", s))),
+            },
+        }
+    }
+}
+
 pub fn generate_key_pair(bits: u32, n_threads: usize) -> (RsaPrivateKey, RsaPublicKey)  {
-    let (p, q) = generate_p_q(bits, n_threads);
+    generate_key_pair_with_exponent(bits, n_threads, PublicExponent::Random)
+}
+
+/// Generates a key pair, a fixed exponent needs new primes until it is coprime to phi(n)
+pub fn generate_key_pair_with_exponent(bits: u32, n_threads: usize, exponent: PublicExponent) -> (RsaPrivateKey, RsaPublicKey)  {
+    let (p, q, mut n_phi, e) = loop {
+        let (mut p, mut q) = generate_p_q(bits, n_threads);
+        let mut n_phi = calculate_n_phi(&p, &q);
+        match exponent {
+            PublicExponent::Random => {
+                let e = generate_e(&n_phi);
+                break (p, q, n_phi, e);
+            },
+            PublicExponent::Fixed(e) => {
+                let e = Integer::from(e);
+                if e < n_phi && gcd(&n_phi, &e) == 1 {
+                    break (p, q, n_phi, e);
+                }
+            },
+        }
+        for n in [&mut p, &mut q, &mut n_phi] {
+            zeroize_integer(n);
+        }
+    };
     let n = Integer::from(&p * &q);
-    let n_phi = calculate_n_phi(&p, &q);
-    let e = generate_e(&n_phi);
     let d = generate_d(&e, &n_phi);
-    (RsaPrivateKey { d, n: Integer::from(&n), public_key_part: Integer::from(&e) }, RsaPublicKey { e, n })
+    zeroize_integer(&mut n_phi);
+    let crt = Some(CrtParams::new(p, q, &d));
+    (RsaPrivateKey { d, n: Integer::from(&n), public_key_part: Integer::from(&e), crt }, RsaPublicKey { e, n })
 }
 
-pub fn encrypt_msg(msg: &Integer, RsaPublicKey { e, n, ..}: RsaPublicKey) -> Integer {
-    pow_mod(msg, &e, &n)
+pub fn encrypt_msg(msg: &Integer, key: &RsaPublicKey) -> Result<Integer> {
+    primitives::rsaep(key, msg)
 }
 
-pub fn decrypt_cypher(c: &Integer, RsaPrivateKey { d, n, .. }: RsaPrivateKey) -> Integer {
-    pow_mod(c, &d, &n)
+pub fn decrypt_cypher(c: &Integer, key: &RsaPrivateKey) -> Result<Integer> {
+    primitives::rsadp(key, c)
 }
 
 #[test]
@@ -248,6 +598,24 @@ fn test_serialize() {
     let deserialized_key =  deserialized_key.unwrap();
     assert_eq!(key.d, deserialized_key.d);
     assert_eq!(key.n, deserialized_key.n);
+    assert_eq!(key.crt.as_ref().unwrap().qinv, deserialized_key.crt.as_ref().unwrap().qinv);
+}
+
+#[test]
+fn test_deserialize_legacy_private_key() {
+    let key = RsaPrivateKey::from_file("sk_22-06-06T20:51");
+    assert!(key.is_ok());
+    assert!(key.unwrap().crt_params().is_none());
+}
+
+#[test]
+fn test_debug_redacted() {
+    let (sk, _) = generate_key_pair(256, 2);
+    let debug = format!("{:?}", sk);
+    assert!(debug.contains("d: <redacted>"));
+    assert!(debug.contains("crt: Some(CrtParams { <redacted> })"));
+    assert!(!debug.contains(&sk.d.to_string()));
+    assert!(!debug.contains(&sk.crt.as_ref().unwrap().p.to_string()));
 }
 
 #[test]
@@ -257,8 +625,64 @@ fn test_bit() {
     assert!(!n.get_bit(3));
 }
 
+#[test]
+fn test_deserialize_malformed() {
+    assert!(matches!(RsaPrivateKey::deserialize("abc".to_string()), Err(Error::KeyFormat(_))));
+    assert!(matches!(RsaPrivateKey::deserialize(format!("abc{}xyz{}01", SEPARATOR, SEPARATOR)), Err(Error::KeyFormat(_))));
+    assert!(matches!(RsaPublicKey::deserialize(String::new()), Err(Error::KeyFormat(_))));
+    assert!(RsaPublicKey::deserialize(format!("03{}0f", SEPARATOR)).is_ok());
+}
+
+#[test]
+fn test_parse_hex_part() {
+    assert_eq!(parse_hex_part("f").unwrap(), 15);
+    assert_eq!(parse_hex_part(" 1Ab\n").unwrap(), 0x1ab);
+    assert_eq!(parse_hex_part("000100").unwrap(), 256);
+    assert!(parse_hex_part("").is_err());
+    assert!(parse_hex_part("-1").is_err());
+    assert!(parse_hex_part("0x10").is_err());
+    let n = Integer::from(Integer::u_pow_u(3, 200));
+    assert_eq!(parse_hex_part(&RsaPublicKey::into_hex(&n)).unwrap(), n);
+}
+
 #[test]
 fn generate_key_pair_and_serialize() {
     let (_, pk) = generate_key_pair(1024, num_cpus::get_physical());
     pk.serialize();
-}
\ No newline at end of file
+}
+#[test]
+fn test_to_public_key() {
+    let sk = RsaPrivateKey::from_file("sk_22-06-06T20:51").unwrap();
+    let pk = RsaPublicKey::from_file("pk_22-06-06T20:51").unwrap();
+    assert_eq!(sk.to_public_key().serialize(), pk.serialize());
+}
+
+#[test]
+fn test_fingerprints() {
+    // reference values from `ssh-keygen -lf` and `openssl pkey -pubin -outform DER | sha256sum`
+    let pk = RsaPublicKey::from_file("pk_22-06-06T20:51").unwrap();
+    let sk = RsaPrivateKey::from_file("sk_22-06-06T20:51").unwrap();
+    assert_eq!(crate::input_module::to_hex(&pk.fingerprint()), "48ec4ff1fd85f92e4a4e80f5bbd58827b9baab3a203f09cb8cd30fa4cc48fa10");
+    assert_eq!(pk.ssh_fingerprint(), "SHA256:BLAdyZ9gjcPzVZd5bXu/L1ZAUxYF0tWlBf0S3m5utIo");
+    assert_eq!(sk.fingerprint(), pk.fingerprint());
+}
+
+#[test]
+fn test_recover_crt_params() {
+    let (sk, _) = generate_key_pair(256, 2);
+    let crt = sk.crt_params().unwrap();
+    let recovered = CrtParams::recover(sk.modulus(), sk.public_exponent(), sk.private_exponent()).unwrap();
+    assert_eq!(Integer::from(&recovered.p * &recovered.q), sk.n);
+    assert!(recovered.p == crt.p || recovered.p == crt.q);
+    assert!(CrtParams::recover(sk.modulus(), sk.public_exponent(), &Integer::from(&sk.d + 2)).is_err());
+}
+
+#[test]
+fn test_generate_with_fixed_exponent() {
+    let (sk, pk) = generate_key_pair_with_exponent(256, 2, "65537".parse().unwrap());
+    assert_eq!(*pk.public_exponent(), 65537);
+    assert!(sk.validate().is_ok());
+    assert_eq!("RANDOM".parse::<PublicExponent>().unwrap(), PublicExponent::Random);
+    assert!("65536".parse::<PublicExponent>().is_err());
+    assert!("1".parse::<PublicExponent>().is_err());
+}
@@ -0,0 +1,488 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use crate::formats::{self, Encoding, KeyFormat, LoadedKey};
+use crate::input_module::{from_hex, to_hex};
+use crate::key_gen::{RsaKey, RsaPrivateKey, RsaPublicKey};
+use crate::output::{self, FileMode};
+
+const METADATA_FILE: &str = "meta.json";
+const PRIVATE_KEY_FILE: &str = "private.pem";
+const PUBLIC_KEY_FILE: &str = "public.pem";
+
+/// Shortest fingerprint prefix a key can be found by, in hex digits
+const MIN_FINGERPRINT_PREFIX: usize = 8;
+
+/// What a key may be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Usage {
+    Encrypt,
+    Sign,
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Usage::Encrypt => write!(f, "encrypt"),
+            Usage::Sign => write!(f, "sign"),
+        }
+    }
+}
+
+impl FromStr for Usage {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "encrypt" => Ok(Usage::Encrypt),
+            "sign" => Ok(Usage::Sign),
+            invalid => Err(Error::KeyFormat(format!("invalid key usage: {}", invalid))),
+        }
+    }
+}
+
+/// When and why a key was revoked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revocation {
+    pub time: DateTime<Utc>,
+    pub reason: String,
+}
+
+/// Metadata stored next to every key of the keyring
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMetadata {
+    pub name: String,
+    /// SHA-256 of the SubjectPublicKeyInfo
+    pub fingerprint: Vec<u8>,
+    pub created: DateTime<Utc>,
+    /// size of the modulus in bits
+    pub size: u32,
+    pub algorithm: String,
+    pub usage: Vec<Usage>,
+    pub comment: String,
+    /// the key is valid from its creation until it expires
+    pub expires: Option<DateTime<Utc>>,
+    /// the private key is stored, not only the public key
+    pub private: bool,
+    pub revoked: Option<Revocation>,
+    /// fingerprint of the key this one replaced with `keys rotate`
+    pub predecessor: Option<Vec<u8>>,
+    /// fingerprint of the key that replaced this one
+    pub successor: Option<Vec<u8>>,
+}
+
+impl KeyMetadata {
+    /// Metadata of a key created now
+    pub fn new<K: RsaKey>(name: &str, key: &K, usage: Vec<Usage>, comment: &str, expires: Option<DateTime<Utc>>) -> Self {
+        KeyMetadata {
+            name: name.to_string(),
+            fingerprint: key.fingerprint(),
+            created: Utc::now(),
+            size: key.modulus().significant_bits(),
+            algorithm: "RSA".to_string(),
+            usage,
+            comment: comment.to_string(),
+            expires,
+            private: key.is_private(),
+            revoked: None,
+            predecessor: None,
+            successor: None,
+        }
+    }
+
+    pub fn allows(&self, usage: Usage) -> bool {
+        self.usage.contains(&usage)
+    }
+
+    /// "valid", "expired" or "revoked" at the given time
+    pub fn status(&self, now: DateTime<Utc>) -> &'static str {
+        match (&self.revoked, self.expires) {
+            (Some(_), _) => "revoked",
+            (None, Some(expires)) if expires <= now => "expired",
+            (None, _) => "valid",
+        }
+    }
+
+    /// Fails for revoked keys and keys that expired before `now`
+    pub fn check_valid(&self, now: DateTime<Utc>) -> Result<()> {
+        match (&self.revoked, self.expires) {
+            (Some(revocation), _) => Err(Error::InvalidKey(format!(
+                "{} was revoked on {}: {}", self.name, revocation.time.format("%Y-%m-%d %H:%M"), revocation.reason
+            ))),
+            (None, Some(expires)) if expires <= now => Err(Error::InvalidKey(format!("{} expired on {}", self.name, expires.format("%Y-%m-%d %H:%M")))),
+            (None, _) => Ok(()),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "fingerprint": to_hex(&self.fingerprint),
+            "created": self.created.to_rfc3339(),
+            "size": self.size,
+            "algorithm": self.algorithm,
+            "usage": self.usage.iter().map(ToString::to_string).collect::<Vec<String>>(),
+            "comment": self.comment,
+            "expires": self.expires.map(|expires| expires.to_rfc3339()),
+            "private": self.private,
+            "revoked": self.revoked.as_ref().map(|revocation| json!({
+                "time": revocation.time.to_rfc3339(),
+                "reason": revocation.reason,
+            })),
+            "predecessor": self.predecessor.as_deref().map(to_hex),
+            "successor": self.successor.as_deref().map(to_hex),
+        })
+    }
+
+    /// Parses the metadata, revocation and links are optional
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let invalid = |field: &str| Error::KeyFormat(format!("invalid or missing '{}' in key metadata", field));
+        let string = |field: &str| value[field].as_str().ok_or_else(|| invalid(field));
+        let time = |field: &str| DateTime::parse_from_rfc3339(string(field)?).map(|time| time.with_timezone(&Utc)).map_err(|_| invalid(field));
+        let fingerprint = |field: &str| match value[field].is_null() {
+            true => Ok(None),
+            false => from_hex(string(field)?).map(Some),
+        };
+        let revoked = &value["revoked"];
+        Ok(KeyMetadata {
+            name: string("name")?.to_string(),
+            fingerprint: from_hex(string("fingerprint")?)?,
+            created: time("created")?,
+            size: value["size"].as_u64().and_then(|size| u32::try_from(size).ok()).ok_or_else(|| invalid("size"))?,
+            algorithm: string("algorithm")?.to_string(),
+            usage: value["usage"]
+                .as_array()
+                .ok_or_else(|| invalid("usage"))?
+                .iter()
+                .map(|usage| usage.as_str().ok_or_else(|| invalid("usage"))?.parse())
+                .collect::<Result<Vec<Usage>>>()?,
+            comment: string("comment")?.to_string(),
+            expires: match value["expires"].is_null() {
+                true => None,
+                false => Some(time("expires")?),
+            },
+            private: value["private"].as_bool().ok_or_else(|| invalid("private"))?,
+            revoked: match revoked.is_null() {
+                true => None,
+                false => Some(Revocation {
+                    time: revoked["time"]
+                        .as_str()
+                        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                        .ok_or_else(|| invalid("revoked"))?
+                        .with_timezone(&Utc),
+                    reason: revoked["reason"].as_str().ok_or_else(|| invalid("revoked"))?.to_string(),
+                }),
+            },
+            predecessor: fingerprint("predecessor")?,
+            successor: fingerprint("successor")?,
+        })
+    }
+}
+
+/// Checks that a name can be used as directory name and is not mistaken for an option
+fn verify_name(name: &str) -> Result<()> {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || "._-@:".contains(c);
+    if name.is_empty() || name.starts_with(['.', '-']) || !name.chars().all(valid_char) {
+        return Err(Error::InvalidArgs(format!("Invalid key name: '{}', has to consist of letters, digits and '._-@:'", name)));
+    }
+    Ok(())
+}
+
+/// Directory with one subdirectory per key, containing the key as PKCS#8 and SPKI PEM and its metadata
+pub struct Keyring {
+    dir: PathBuf,
+}
+
+impl Keyring {
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        Keyring { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn key_dir(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub fn public_key_path(&self, metadata: &KeyMetadata) -> PathBuf {
+        self.key_dir(&metadata.name).join(PUBLIC_KEY_FILE)
+    }
+
+    /// Path of the private key, an error for keys that only have the public part
+    pub fn private_key_path(&self, metadata: &KeyMetadata) -> Result<PathBuf> {
+        match metadata.private {
+            true => Ok(self.key_dir(&metadata.name).join(PRIVATE_KEY_FILE)),
+            false => Err(Error::KeyFormat(format!("the keyring only contains the public key of {}", metadata.name))),
+        }
+    }
+
+    /// All keys, sorted by name. A keyring that was not created yet is empty.
+    pub fn list(&self) -> Result<Vec<KeyMetadata>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let mut keys = vec![];
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path().join(METADATA_FILE);
+            if path.is_file() {
+                let metadata: Value = serde_json::from_slice(&std::fs::read(&path)?)
+                    .map_err(|e| Error::KeyFormat(format!("invalid key metadata in {}: {}", path.display(), e)))?;
+                let key = KeyMetadata::from_json(&metadata)?;
+                // every operation finds the directory by the name, so it has to be the directory the metadata is in
+                if verify_name(&key.name).is_err() || entry.file_name().to_str() != Some(key.name.as_str()) {
+                    return Err(Error::KeyFormat(format!("key metadata in {} names a different key: '{}'", path.display(), key.name)));
+                }
+                keys.push(key);
+            }
+        }
+        keys.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(keys)
+    }
+
+    /// Finds a key by its name, or by a prefix of its hex fingerprint of at least 8 digits
+    pub fn find(&self, id: &str) -> Result<KeyMetadata> {
+        let keys = self.list()?;
+        if let Some(key) = keys.iter().find(|key| key.name == id) {
+            return Ok(key.clone());
+        }
+        let prefix = id.replace(':', "").to_ascii_lowercase();
+        let mut matching = keys.into_iter().filter(|key| prefix.len() >= MIN_FINGERPRINT_PREFIX && to_hex(&key.fingerprint).starts_with(&prefix));
+        match (matching.next(), matching.next()) {
+            (Some(key), None) => Ok(key),
+            (Some(_), Some(_)) => Err(Error::InvalidArgs(format!("Error, several keys have a fingerprint starting with {}", id))),
+            (None, _) => Err(Error::InvalidArgs(format!("Error, no key named {} or with that fingerprint in {}", id, self.dir.display()))),
+        }
+    }
+
+    /// The key with the given fingerprint, None if it is not in the keyring
+    pub fn find_by_fingerprint(&self, fingerprint: &[u8]) -> Result<Option<KeyMetadata>> {
+        Ok(self.list()?.into_iter().find(|key| key.fingerprint == fingerprint))
+    }
+
+    /// Adds a key, names and fingerprints have to be unique
+    pub fn add(&self, key: &LoadedKey, metadata: &KeyMetadata) -> Result<()> {
+        verify_name(&metadata.name)?;
+        if let Some(existing) = self.list()?.into_iter().find(|existing| existing.name == metadata.name || existing.fingerprint == metadata.fingerprint) {
+            return Err(Error::InvalidArgs(format!("Error, the keyring already contains {} with fingerprint {}", existing.name, to_hex(&existing.fingerprint))));
+        }
+
+        let key_dir = self.key_dir(&metadata.name);
+        output::create_private_dir(&key_dir)?;
+        let public_key = match key {
+            LoadedKey::Private(key) => {
+                let encoded = Zeroizing::new(formats::encode_private_key(key, KeyFormat::Pkcs8, Encoding::Pem)?);
+                output::write_file(key_dir.join(PRIVATE_KEY_FILE), &encoded, FileMode::Private, true)?;
+                key.to_public_key()
+            },
+            LoadedKey::Public(key) => key.clone(),
+        };
+        let encoded = formats::encode_public_key(&public_key, KeyFormat::Spki, Encoding::Pem)?;
+        output::write_file(key_dir.join(PUBLIC_KEY_FILE), &encoded, FileMode::Default, true)?;
+        self.update(metadata)
+    }
+
+    /// Writes changed metadata of a key in the keyring
+    pub fn update(&self, metadata: &KeyMetadata) -> Result<()> {
+        let json = serde_json::to_string_pretty(&metadata.to_json()).unwrap_or_default();
+        output::write_file(self.key_dir(&metadata.name).join(METADATA_FILE), json.as_bytes(), FileMode::Default, true)
+    }
+
+    /// Replaces a private key by `successor`, which takes over its name, usage and comment.
+    /// The old key is renamed to `<name>.<n>` and expires now, its private key stays available for decryption.
+    /// If the successor can't be added, the old key is put back.
+    pub fn rotate(&self, metadata: &KeyMetadata, successor: RsaPrivateKey, expires: Option<DateTime<Utc>>) -> Result<KeyMetadata> {
+        verify_name(&metadata.name)?;
+        if !metadata.private {
+            return Err(Error::InvalidArgs(format!("Error, only keys with a private key can be rotated, the keyring only contains the public key of {}", metadata.name)));
+        }
+        let names: Vec<String> = self.list()?.into_iter().map(|key| key.name).collect();
+        let archived = (1..).map(|n| format!("{}.{}", metadata.name, n)).find(|name| !names.contains(name)).unwrap_or_default();
+        let mut new = KeyMetadata::new(&metadata.name, &successor, metadata.usage.clone(), &metadata.comment, expires);
+        new.predecessor = Some(metadata.fingerprint.clone());
+
+        let now = Utc::now();
+        let mut old = metadata.clone();
+        old.name = archived;
+        old.successor = Some(new.fingerprint.clone());
+        old.expires = Some(old.expires.map_or(now, |expires| expires.min(now)));
+        std::fs::rename(self.key_dir(&metadata.name), self.key_dir(&old.name))?;
+        if let Err(e) = self.update(&old).and_then(|_| self.add(&LoadedKey::Private(successor), &new)) {
+            // anything under the name was created by the failed add
+            let _ = std::fs::remove_dir_all(self.key_dir(&metadata.name));
+            std::fs::rename(self.key_dir(&old.name), self.key_dir(&metadata.name))?;
+            self.update(metadata)?;
+            return Err(e);
+        }
+        Ok(new)
+    }
+
+    pub fn read_public_key(&self, metadata: &KeyMetadata) -> Result<RsaPublicKey> {
+        formats::read_public_key(&self.public_key_path(metadata).to_string_lossy(), None)
+    }
+
+    pub fn read_private_key(&self, metadata: &KeyMetadata) -> Result<RsaPrivateKey> {
+        formats::read_private_key(&self.private_key_path(metadata)?.to_string_lossy(), None)
+    }
+
+    /// Removes the key and its metadata
+    pub fn delete(&self, metadata: &KeyMetadata) -> Result<()> {
+        verify_name(&metadata.name)?;
+        std::fs::remove_dir_all(self.key_dir(&metadata.name))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Keyring, KeyMetadata, Revocation, Usage};
+    use crate::error::Error;
+    use crate::formats::LoadedKey;
+    use crate::input_module::to_hex;
+    use chrono::{Duration, Utc};
+
+    use crate::key_gen::{generate_key_pair, RsaKey};
+
+    fn temp_keyring(name: &str) -> Keyring {
+        let dir = std::env::temp_dir().join(format!("rust_rsa_keyring_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Keyring::open(dir)
+    }
+
+    #[test]
+    fn test_metadata_json() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let mut metadata = KeyMetadata::new("alice", &sk, vec![Usage::Encrypt], "work", Some(chrono::Utc::now()));
+        let parsed = KeyMetadata::from_json(&metadata.to_json()).unwrap();
+        assert_eq!(parsed, metadata);
+        metadata.revoked = Some(Revocation { time: chrono::Utc::now(), reason: "compromised".to_string() });
+        metadata.predecessor = Some(vec![1, 2, 3]);
+        assert_eq!(KeyMetadata::from_json(&metadata.to_json()).unwrap(), metadata);
+        assert!(parsed.private && parsed.allows(Usage::Encrypt) && !parsed.allows(Usage::Sign));
+        assert!(KeyMetadata::from_json(&serde_json::json!({"name": "alice"})).is_err());
+    }
+
+    #[test]
+    fn test_add_find_delete() {
+        let keyring = temp_keyring("add");
+        assert!(keyring.list().unwrap().is_empty());
+
+        let (sk, pk) = generate_key_pair(256, 2);
+        let metadata = KeyMetadata::new("alice", &sk, vec![Usage::Encrypt, Usage::Sign], "", None);
+        keyring.add(&LoadedKey::Private(sk), &metadata).unwrap();
+        let (_, other) = generate_key_pair(256, 2);
+        let other_metadata = KeyMetadata::new("bob", &other, vec![Usage::Encrypt], "", None);
+        keyring.add(&LoadedKey::Public(other.clone()), &other_metadata).unwrap();
+
+        // names and fingerprints are unique
+        assert!(keyring.add(&LoadedKey::Public(pk.clone()), &KeyMetadata::new("carol", &pk, vec![], "", None)).is_err());
+        assert!(keyring.add(&LoadedKey::Public(pk.clone()), &KeyMetadata::new("bob", &pk, vec![], "", None)).is_err());
+        assert!(keyring.add(&LoadedKey::Public(pk.clone()), &KeyMetadata::new("../x", &pk, vec![], "", None)).is_err());
+
+        let names: Vec<String> = keyring.list().unwrap().into_iter().map(|key| key.name).collect();
+        assert_eq!(names, vec!["alice", "bob"]);
+        let found = keyring.find(&to_hex(&pk.fingerprint())[..8]).unwrap();
+        assert_eq!(found.name, "alice");
+        assert_eq!(keyring.read_public_key(&found).unwrap().fingerprint(), pk.fingerprint());
+        assert!(keyring.read_private_key(&found).is_ok());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&keyring.private_key_path(&found).unwrap()), 0o600);
+            assert_eq!(mode(keyring.dir()), 0o700);
+        }
+        assert!(keyring.read_private_key(&keyring.find("bob").unwrap()).is_err());
+        assert!(keyring.find(&to_hex(&pk.fingerprint())[..4]).is_err());
+
+        keyring.delete(&found).unwrap();
+        assert!(keyring.find("alice").is_err());
+        assert_eq!(keyring.list().unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(keyring.dir());
+    }
+
+    #[test]
+    fn test_edited_name() {
+        let keyring = temp_keyring("edited");
+        let (sk, _) = generate_key_pair(256, 2);
+        let mut metadata = KeyMetadata::new("alice", &sk, vec![Usage::Encrypt], "", None);
+        keyring.add(&LoadedKey::Private(sk), &metadata).unwrap();
+
+        // metadata naming a directory outside of the keyring, or another key, is refused
+        for name in ["../..", "bob"] {
+            let mut edited = metadata.clone();
+            edited.name = name.to_string();
+            let json = serde_json::to_string(&edited.to_json()).unwrap();
+            std::fs::write(keyring.dir().join("alice").join(super::METADATA_FILE), json).unwrap();
+            assert!(matches!(keyring.list(), Err(Error::KeyFormat(_))), "{}", name);
+            assert!(keyring.find("alice").is_err());
+        }
+        assert!(keyring.dir().join("alice").is_dir());
+        metadata.name = "../..".to_string();
+        assert!(keyring.delete(&metadata).is_err());
+        let _ = std::fs::remove_dir_all(keyring.dir());
+    }
+
+    #[test]
+    fn test_validity() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let now = Utc::now();
+        let mut metadata = KeyMetadata::new("alice", &sk, vec![Usage::Encrypt], "", Some(now + Duration::days(1)));
+        assert_eq!(metadata.status(now), "valid");
+        assert!(metadata.check_valid(now).is_ok());
+        assert_eq!(metadata.status(now + Duration::days(2)), "expired");
+        assert!(metadata.check_valid(now + Duration::days(2)).is_err());
+
+        metadata.revoked = Some(Revocation { time: now, reason: "compromised".to_string() });
+        assert_eq!(metadata.status(now), "revoked");
+        assert!(metadata.check_valid(now).unwrap_err().to_string().contains("compromised"));
+    }
+
+    #[test]
+    fn test_rotate() {
+        let keyring = temp_keyring("rotate");
+        let (sk, pk) = generate_key_pair(256, 2);
+        let metadata = KeyMetadata::new("alice", &sk, vec![Usage::Encrypt], "work", None);
+        keyring.add(&LoadedKey::Private(sk), &metadata).unwrap();
+        let (successor, new_pk) = generate_key_pair(256, 2);
+        let new = keyring.rotate(&metadata, successor, None).unwrap();
+
+        // the successor takes over the name, the old key keeps its private key
+        let current = keyring.find("alice").unwrap();
+        assert_eq!(current, new);
+        assert_eq!(current.fingerprint, new_pk.fingerprint());
+        assert_eq!(current.predecessor, Some(pk.fingerprint()));
+        assert_eq!(current.comment, "work");
+        let old = keyring.find("alice.1").unwrap();
+        assert_eq!(old.successor, Some(new_pk.fingerprint()));
+        assert_eq!(old.status(Utc::now()), "expired");
+        assert!(keyring.read_private_key(&old).is_ok());
+
+        let (successor, _) = generate_key_pair(256, 2);
+        keyring.rotate(&current, successor, None).unwrap();
+        assert!(keyring.find("alice.2").is_ok());
+        assert_eq!(keyring.find_by_fingerprint(&pk.fingerprint()).unwrap().unwrap().name, "alice.1");
+
+        // a successor that can't be added leaves the old key in place
+        let (bob, _) = generate_key_pair(256, 2);
+        let bob_metadata = KeyMetadata::new("bob", &bob, vec![Usage::Encrypt], "", None);
+        keyring.add(&LoadedKey::Private(bob), &bob_metadata).unwrap();
+        let current = keyring.find("alice").unwrap();
+        let duplicate = keyring.read_private_key(&bob_metadata).unwrap();
+        assert!(keyring.rotate(&current, duplicate, None).is_err());
+        assert_eq!(keyring.find("alice").unwrap(), current);
+        assert!(keyring.find("alice.3").is_err());
+        assert!(keyring.read_private_key(&current).is_ok());
+        let _ = std::fs::remove_dir_all(keyring.dir());
+    }
+}
@@ -1,9 +1,25 @@
+pub mod error;
+pub mod formats;
+pub mod hash;
 pub mod helpers;
+pub mod jwt;
 pub mod key_gen;
+pub mod padding;
+pub mod primitives;
+pub mod signature;
 pub mod prime_gen;
 pub mod input_module;
+pub mod validation;
+pub mod x509;
+pub mod cms;
 pub mod commands;
-pub mod parser;
+pub mod container;
+pub mod cli;
+pub mod settings;
+pub mod output;
+pub mod keyring;
+
+pub use error::{Error, Result};
 
 #[cfg(test)]
 pub mod tests {
@@ -0,0 +1,197 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{Error, Result};
+
+/// Who may read a written file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileMode {
+    /// the permissions of the umask
+    Default,
+    /// only readable and writable by the owner, for private keys
+    Private,
+}
+
+// distinguishes the temporary files of one process
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Refuses to replace an existing file, unless `overwrite` is set
+pub fn check_overwrite(path: impl AsRef<Path>, overwrite: bool) -> Result<()> {
+    let path = path.as_ref();
+    if !overwrite && path.exists() {
+        return Err(Error::InvalidArgs(format!("Error, {} already exists, --force overwrites it", path.display())));
+    }
+    Ok(())
+}
+
+/// A file that is written to a temporary file next to it and only replaces it on `commit`,
+/// so a crash or error never leaves a half-written file. Without commit the temporary file is removed.
+pub struct AtomicFile {
+    file: File,
+    temp: PathBuf,
+    path: PathBuf,
+    overwrite: bool,
+    committed: bool,
+}
+
+impl AtomicFile {
+    pub fn create(path: impl AsRef<Path>, mode: FileMode, overwrite: bool) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        check_overwrite(&path, overwrite)?;
+        let name = path.file_name().ok_or_else(|| Error::InvalidArgs(format!("Error, {} is not a file name", path.display())))?;
+        let temp = path.with_file_name(format!(
+            ".{}.{}-{}.tmp",
+            name.to_string_lossy(),
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(match mode {
+                FileMode::Default => 0o666,
+                FileMode::Private => 0o600,
+            });
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        let file = options.open(&temp)?;
+        Ok(AtomicFile { file, temp, path, overwrite, committed: false })
+    }
+
+    /// Writes the data to disk and moves it to the path of the file
+    pub fn commit(mut self) -> Result<()> {
+        self.file.sync_all()?;
+        check_overwrite(&self.path, self.overwrite)?;
+        std::fs::rename(&self.temp, &self.path)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.temp);
+        }
+    }
+}
+
+/// Writes `data` to `path` atomically, every file the commands and the keyring write goes through it
+pub fn write_file(path: impl AsRef<Path>, data: &[u8], mode: FileMode, overwrite: bool) -> Result<()> {
+    let mut file = AtomicFile::create(path, mode, overwrite)?;
+    file.write_all(data)?;
+    file.commit()
+}
+
+/// Creates a directory and its parents, only accessible by the owner
+pub fn create_private_dir(path: impl AsRef<Path>) -> Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    Ok(builder.create(path)?)
+}
+
+/// Output of a command that is streamed to stdout or to a file, which is only replaced on `finish`
+pub enum Output {
+    Stdout(BufWriter<io::StdoutLock<'static>>),
+    File(BufWriter<AtomicFile>),
+}
+
+impl Output {
+    /// Stdout if `file_name` is None
+    pub fn open(file_name: Option<&str>, mode: FileMode, overwrite: bool) -> Result<Self> {
+        match file_name {
+            Some(file_name) => Ok(Output::File(BufWriter::new(AtomicFile::create(file_name, mode, overwrite)?))),
+            None => Ok(Output::Stdout(BufWriter::new(io::stdout().lock()))),
+        }
+    }
+
+    pub fn finish(self) -> Result<()> {
+        match self {
+            Output::Stdout(mut stdout) => Ok(stdout.flush()?),
+            Output::File(file) => file.into_inner().map_err(|e| e.into_error())?.commit(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::File(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::File(file) => file.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{write_file, AtomicFile, FileMode};
+    use crate::error::Error;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("rust_rsa_output_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_file() {
+        let dir = temp_dir("write");
+        let path = dir.join("key");
+        write_file(&path, b"first", FileMode::Private, false).unwrap();
+        assert!(matches!(write_file(&path, b"second", FileMode::Private, false), Err(Error::InvalidArgs(_))));
+        assert_eq!(std::fs::read(&path).unwrap(), b"first");
+        write_file(&path, b"second", FileMode::Private, true).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        // only the written file is left
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_no_commit() {
+        let dir = temp_dir("commit");
+        let path = dir.join("out");
+        write_file(&path, b"old", FileMode::Default, false).unwrap();
+        let mut file = AtomicFile::create(&path, FileMode::Default, true).unwrap();
+        file.write_all(b"half").unwrap();
+        drop(file);
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
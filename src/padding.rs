@@ -0,0 +1,230 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::{Rng, RngCore};
+
+use crate::error::{Error, Result};
+use crate::hash::{mgf1, HashAlgorithm};
+use crate::input_module::{i2osp, os2ip};
+use crate::key_gen::{RsaKey, RsaPrivateKey, RsaPublicKey};
+use crate::primitives;
+
+/// Encryption schemes, turning a message into an encoded message of the same length as the modulus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    /// Textbook RSA. The message bytes are read little endian like in `input_module::string_to_number`,
+    /// so ciphertexts created before padding was supported can still be decrypted.
+    None,
+    /// RSAES-PKCS1-v1_5, RFC 8017 section 7.2
+    Pkcs1,
+    /// RSAES-OAEP with MGF1 and an empty label, RFC 8017 section 7.1
+    Oaep(HashAlgorithm),
+}
+
+impl Padding {
+    /// Maximum length of a message in bytes, for a modulus that is `k` bytes long
+    pub fn max_message_len(&self, k: usize) -> usize {
+        match self {
+            Padding::None => k.saturating_sub(1),
+            Padding::Pkcs1 => k.saturating_sub(11),
+            Padding::Oaep(hash) => k.saturating_sub(2 * hash.output_len() + 2),
+        }
+    }
+
+    /// Encodes `msg` into a `k` byte long encoded message
+    pub fn encode(&self, msg: &[u8], k: usize) -> Result<Vec<u8>> {
+        let max_len = self.max_message_len(k);
+        if msg.len() > max_len || (max_len == 0 && *self != Padding::None) {
+            return Err(Error::SizeLimit(format!(
+                "message is {} bytes, the maximum for a {} bit key with {} padding is {} bytes",
+                msg.len(), k * 8, self, max_len
+            )));
+        }
+        match self {
+            Padding::None => {
+                let mut em = vec![0; k - msg.len()];
+                em.extend(msg.iter().rev());
+                Ok(em)
+            },
+            Padding::Pkcs1 => {
+                let mut rng = rand::thread_rng();
+                let mut em = vec![0x00, 0x02];
+                em.extend((0..k - msg.len() - 3).map(|_| rng.gen_range(1..=255u8)));
+                em.push(0x00);
+                em.extend_from_slice(msg);
+                Ok(em)
+            },
+            Padding::Oaep(hash) => {
+                let h_len = hash.output_len();
+                let mut db = hash.digest(&[]);
+                db.resize(k - msg.len() - h_len - 2, 0);
+                db.push(0x01);
+                db.extend_from_slice(msg);
+
+                let mut seed = vec![0; h_len];
+                rand::thread_rng().fill_bytes(&mut seed);
+                xor_in_place(&mut db, &mgf1(*hash, &seed, k - h_len - 1));
+                xor_in_place(&mut seed, &mgf1(*hash, &db, h_len));
+
+                let mut em = vec![0x00];
+                em.extend_from_slice(&seed);
+                em.extend_from_slice(&db);
+                Ok(em)
+            },
+        }
+    }
+
+    /// Decodes a `k` byte long encoded message.
+    /// All decoding failures return the same error, to not give away why decoding failed.
+    pub fn decode(&self, em: &[u8], k: usize) -> Result<Vec<u8>> {
+        let decryption_error = || Error::Padding("decryption error".to_string());
+        if em.len() != k {
+            return Err(decryption_error());
+        }
+        match self {
+            Padding::None => {
+                let mut msg: Vec<u8> = em.iter().rev().copied().collect();
+                while msg.last() == Some(&0) {
+                    msg.pop();
+                }
+                Ok(msg)
+            },
+            Padding::Pkcs1 => {
+                if k < 11 || em[0] != 0x00 || em[1] != 0x02 {
+                    return Err(decryption_error());
+                }
+                match em[2..].iter().position(|b| *b == 0) {
+                    // padding string has to be at least 8 bytes long
+                    Some(ps_len) if ps_len >= 8 => Ok(em[2 + ps_len + 1..].to_vec()),
+                    _ => Err(decryption_error()),
+                }
+            },
+            Padding::Oaep(hash) => {
+                let h_len = hash.output_len();
+                if k < 2 * h_len + 2 {
+                    return Err(decryption_error());
+                }
+                let mut seed = em[1..1 + h_len].to_vec();
+                let mut db = em[1 + h_len..].to_vec();
+                xor_in_place(&mut seed, &mgf1(*hash, &db, h_len));
+                xor_in_place(&mut db, &mgf1(*hash, &seed, k - h_len - 1));
+
+                let l_hash = hash.digest(&[]);
+                let separator = db[h_len..].iter().position(|b| *b != 0);
+                match separator {
+                    Some(i) if em[0] == 0 && db[..h_len] == l_hash[..] && db[h_len + i] == 0x01 => {
+                        Ok(db[h_len + i + 1..].to_vec())
+                    },
+                    _ => Err(decryption_error()),
+                }
+            },
+        }
+    }
+
+    /// RSAES encryption of `msg`, the ciphertext is as long as the modulus
+    pub fn encrypt(&self, key: &RsaPublicKey, msg: &[u8]) -> Result<Vec<u8>> {
+        let k = key.size();
+        let c = primitives::rsaep(key, &os2ip(&self.encode(msg, k)?))?;
+        i2osp(&c, k)
+    }
+
+    /// RSAES decryption of a ciphertext created by `encrypt`
+    pub fn decrypt(&self, key: &RsaPrivateKey, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let k = key.size();
+        if ciphertext.len() != k {
+            return Err(Error::Padding("decryption error".to_string()));
+        }
+        let m = primitives::rsadp(key, &os2ip(ciphertext))?;
+        self.decode(&i2osp(&m, k)?, k)
+    }
+}
+
+#[inline(always)]
+pub(crate) fn xor_in_place(data: &mut [u8], mask: &[u8]) {
+    for (d, m) in data.iter_mut().zip(mask) {
+        *d ^= m;
+    }
+}
+
+impl fmt::Display for Padding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Padding::None => write!(f, "none"),
+            Padding::Pkcs1 => write!(f, "pkcs1"),
+            Padding::Oaep(HashAlgorithm::Sha256) => write!(f, "oaep"),
+            Padding::Oaep(hash) => write!(f, "oaep-{}", hash),
+        }
+    }
+}
+
+impl FromStr for Padding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "raw" => Ok(Padding::None),
+            "pkcs1" => Ok(Padding::Pkcs1),
+            "oaep" => Ok(Padding::Oaep(HashAlgorithm::Sha256)),
+            other => match other.strip_prefix("oaep-") {
+                Some(hash) => Ok(Padding::Oaep(hash.parse()?)),
+                None => Err(Error::InvalidArgs(format!("Invalid padding: {}, has to be 'none', 'pkcs1' or 'oaep'.", s))),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Padding;
+    use crate::error::Error;
+    use crate::hash::HashAlgorithm;
+    use crate::key_gen::RsaKey;
+
+    #[test]
+    fn test_encode_decode() {
+        let msg = b"Sed ut perspiciatis unde omnis";
+        for padding in [Padding::None, Padding::Pkcs1, Padding::Oaep(HashAlgorithm::Sha256), Padding::Oaep(HashAlgorithm::Sha512)] {
+            let em = padding.encode(msg, 256).unwrap();
+            assert_eq!(em.len(), 256);
+            assert_eq!(em[0], 0);
+            assert_eq!(padding.decode(&em, 256).unwrap(), msg.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_message_too_long() {
+        let msg = vec![1; 191];
+        assert_eq!(Padding::Oaep(HashAlgorithm::Sha256).max_message_len(256), 190);
+        assert!(matches!(Padding::Oaep(HashAlgorithm::Sha256).encode(&msg, 256), Err(Error::SizeLimit(_))));
+        assert!(Padding::Pkcs1.encode(&msg, 256).is_ok());
+        assert!(matches!(Padding::Pkcs1.encode(&[1; 246], 256), Err(Error::SizeLimit(_))));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let mut em = Padding::Oaep(HashAlgorithm::Sha256).encode(b"hello", 128).unwrap();
+        em[10] ^= 1;
+        assert!(matches!(Padding::Oaep(HashAlgorithm::Sha256).decode(&em, 128), Err(Error::Padding(_))));
+        assert!(Padding::Pkcs1.decode(&[0; 128], 128).is_err());
+        assert!(Padding::Pkcs1.decode(&[0, 2, 0], 3).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let (sk, pk) = crate::key_gen::generate_key_pair(1024, 2);
+        for padding in [Padding::Pkcs1, Padding::Oaep(HashAlgorithm::Sha256)] {
+            let ciphertext = padding.encrypt(&pk, b"content key").unwrap();
+            assert_eq!(ciphertext.len(), pk.size());
+            assert_eq!(padding.decrypt(&sk, &ciphertext).unwrap(), b"content key");
+            assert!(padding.decrypt(&sk, &ciphertext[1..]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("oaep".parse::<Padding>().unwrap(), Padding::Oaep(HashAlgorithm::Sha256));
+        assert_eq!("oaep-sha384".parse::<Padding>().unwrap(), Padding::Oaep(HashAlgorithm::Sha384));
+        assert_eq!("PKCS1".parse::<Padding>().unwrap(), Padding::Pkcs1);
+        assert!("pss".parse::<Padding>().is_err());
+    }
+}
@@ -1,2 +0,0 @@
-pub mod parser;
-pub mod opt;
\ No newline at end of file
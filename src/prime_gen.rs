@@ -5,7 +5,7 @@ use self::sieve_of_eratosthenes::Sieve;
 
 pub fn is_prime(n: &Integer, sieve: &Sieve) -> bool {
 
-    if !n.get_bit(0) || !sieve.is_prime_candidate(&n) { return false };
+    if !n.get_bit(0) || !sieve.is_prime_candidate(n) { return false };
 
     let (s, d) = get_factors(n);
 
@@ -22,7 +22,7 @@ fn get_factors(n: &Integer) -> (Integer, Integer) {
     let mut d = Integer::from(n - 1);
     let mut s: u32 = 0;
 
-    while Integer::from(&d % &2) == 0 {
+    while Integer::from(&d % 2) == 0 {
         s += 1;
         d >>= 1;
     }
@@ -49,8 +49,8 @@ fn rabin_miller_test(n: &Integer, d: &Integer, s: &Integer) -> bool {
     while &r < s  {
     // second condition: a^(2^r * d) mod n == -1 mod n
         let two = Integer::from(2);
-        let exp = &Integer::from(two.pow(r) * d);
-        if pow_mod(a, exp, n) == Integer::from(n - &1) {
+        let exp = &(two.pow(r) * d);
+        if pow_mod(a, exp, n) == Integer::from(n - 1) {
             return true;
         }
         r += 1;
@@ -0,0 +1,75 @@
+//! The RSA primitives as defined in RFC 8017 section 5.
+//! All of them check that their input is in the range of 0 to n - 1,
+//! since a larger input would silently be reduced mod n.
+use rug::Integer;
+
+use crate::error::{Error, Result};
+use crate::helpers::pow_mod;
+use crate::key_gen::{RsaKey, RsaPrivateKey, RsaPublicKey};
+
+#[inline(always)]
+fn in_range(x: &Integer, n: &Integer) -> bool {
+    *x >= 0 && x < n
+}
+
+/// RSAEP, encrypts the message representative `m` with the public key
+pub fn rsaep(key: &RsaPublicKey, m: &Integer) -> Result<Integer> {
+    if !in_range(m, key.modulus()) {
+        return Err(Error::MessageRepresentativeOutOfRange);
+    }
+    Ok(pow_mod(m, key.public_exponent(), key.modulus()))
+}
+
+/// RSADP, decrypts the ciphertext representative `c` with the private key
+pub fn rsadp(key: &RsaPrivateKey, c: &Integer) -> Result<Integer> {
+    if !in_range(c, key.modulus()) {
+        return Err(Error::CiphertextRepresentativeOutOfRange);
+    }
+    Ok(pow_mod(c, key.private_exponent(), key.modulus()))
+}
+
+/// RSASP1, signs the message representative `m` with the private key
+pub fn rsasp1(key: &RsaPrivateKey, m: &Integer) -> Result<Integer> {
+    if !in_range(m, key.modulus()) {
+        return Err(Error::MessageRepresentativeOutOfRange);
+    }
+    Ok(pow_mod(m, key.private_exponent(), key.modulus()))
+}
+
+/// RSAVP1, recovers the message representative from the signature representative `s`
+pub fn rsavp1(key: &RsaPublicKey, s: &Integer) -> Result<Integer> {
+    if !in_range(s, key.modulus()) {
+        return Err(Error::SignatureRepresentativeOutOfRange);
+    }
+    Ok(pow_mod(s, key.public_exponent(), key.modulus()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+
+    use crate::error::Error;
+    use crate::key_gen::generate_key_pair;
+    use super::*;
+
+    #[test]
+    fn test_primitives_roundtrip() {
+        let (sk, pk) = generate_key_pair(512, 2);
+        let m = Integer::from(123456789);
+        let c = rsaep(&pk, &m).unwrap();
+        assert_eq!(rsadp(&sk, &c).unwrap(), m);
+        let s = rsasp1(&sk, &m).unwrap();
+        assert_eq!(rsavp1(&pk, &s).unwrap(), m);
+    }
+
+    #[test]
+    fn test_primitives_out_of_range() {
+        let (sk, pk) = generate_key_pair(512, 2);
+        let n = Integer::from(pk.modulus());
+        assert!(matches!(rsaep(&pk, &n), Err(Error::MessageRepresentativeOutOfRange)));
+        assert!(matches!(rsaep(&pk, &Integer::from(-1)), Err(Error::MessageRepresentativeOutOfRange)));
+        assert!(matches!(rsadp(&sk, &n), Err(Error::CiphertextRepresentativeOutOfRange)));
+        assert!(matches!(rsasp1(&sk, &Integer::from(&n + 1)), Err(Error::MessageRepresentativeOutOfRange)));
+        assert!(matches!(rsavp1(&pk, &Integer::from(-5)), Err(Error::SignatureRepresentativeOutOfRange)));
+    }
+}
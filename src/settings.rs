@@ -0,0 +1,246 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::commands::util;
+use crate::error::{Error, Result};
+use crate::formats::Encoding;
+use crate::key_gen::PublicExponent;
+use crate::padding::Padding;
+
+/// Environment variable naming the config file, used if --config is not given
+pub const CONFIG_ENV: &str = "RUST_RSA_CONFIG";
+
+/// Names of the settings in the config file, and the environment variable overriding each of them
+const SETTINGS: [(&str, &str); 5] = [
+    ("key_size", "RUST_RSA_KEY_SIZE"),
+    ("exponent", "RUST_RSA_EXPONENT"),
+    ("output_format", "RUST_RSA_OUTPUT_FORMAT"),
+    ("keyring_dir", "RUST_RSA_KEYRING_DIR"),
+    ("padding", "RUST_RSA_PADDING"),
+];
+
+/// Where the value of a setting comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    BuiltIn,
+    File(PathBuf),
+    Env(&'static str),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::BuiltIn => write!(f, "built-in"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Env(name) => write!(f, "${}", name),
+        }
+    }
+}
+
+/// Defaults of the commands, options given on the command line take precedence over them.
+/// Each setting is taken from its environment variable, the config file or the built-in value, in that order.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// size of generated keys
+    pub key_size: u32,
+    pub exponent: PublicExponent,
+    /// encoding of containers, signatures, keys and certificates
+    pub output_format: Encoding,
+    /// directory of the keyring
+    pub keyring_dir: PathBuf,
+    /// padding of encrypt and decrypt, None picks it by the kind of message
+    pub padding: Option<Padding>,
+    /// the config file that was read
+    pub file: Option<PathBuf>,
+    sources: [Source; SETTINGS.len()],
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            key_size: 2048,
+            exponent: PublicExponent::Random,
+            output_format: Encoding::Pem,
+            keyring_dir: data_dir().join("keyring"),
+            padding: None,
+            file: None,
+            sources: std::array::from_fn(|_| Source::BuiltIn),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/rust_rsa/config.toml`, or in `~/.config` if the variable is not set
+pub fn default_config_file() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join("rust_rsa").join("config.toml")
+}
+
+/// `$XDG_DATA_HOME/rust_rsa`, or in `~/.local/share` if the variable is not set
+fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share").join("rust_rsa")
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    match std::env::var_os(var).filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => home_dir().join(fallback),
+    }
+}
+
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default()
+}
+
+/// Expands a leading `~/` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+impl Settings {
+    /// Reads the config file given with --config, or named by `RUST_RSA_CONFIG`, or the default one if it exists,
+    /// then the environment variables
+    pub fn load(config_file: Option<&str>) -> Result<Self> {
+        let mut settings = Settings::default();
+        let file = match config_file.map(PathBuf::from).or_else(|| std::env::var_os(CONFIG_ENV).map(PathBuf::from)) {
+            Some(file) => Some(file),
+            None => Some(default_config_file()).filter(|file| file.exists()),
+        };
+        if let Some(file) = file {
+            let contents = std::fs::read_to_string(&file)
+                .map_err(|e| Error::InvalidArgs(format!("Error, unable to read config file {}: {}", file.display(), e)))?;
+            settings.apply_file(&contents, &file)?;
+        }
+        settings.apply_env(|name| std::env::var(name).ok())?;
+        Ok(settings)
+    }
+
+    /// Applies the settings of a TOML config file, unknown settings are an error to catch typos
+    pub fn apply_file(&mut self, contents: &str, file: &Path) -> Result<()> {
+        let table: toml::Table = contents
+            .parse()
+            .map_err(|e| Error::InvalidArgs(format!("Error, invalid config file {}: {}", file.display(), e)))?;
+        for (name, value) in table {
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Integer(value) => value.to_string(),
+                other => return Err(Error::InvalidArgs(format!("Error, invalid value for {} in {}: {}", name, file.display(), other))),
+            };
+            self.set(&name, &value, Source::File(file.to_path_buf()))?;
+        }
+        self.file = Some(file.to_path_buf());
+        Ok(())
+    }
+
+    /// Applies the environment variables that are set, `lookup` returns the value of a variable
+    pub fn apply_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        for (name, var) in SETTINGS {
+            if let Some(value) = lookup(var) {
+                self.set(name, &value, Source::Env(var))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn set(&mut self, name: &str, value: &str, source: Source) -> Result<()> {
+        let invalid = |e: Error| Error::InvalidArgs(format!("Error, invalid {} from {}: {}", name, source, e));
+        match name {
+            "key_size" => self.key_size = util::parse_bit_size(value).map_err(invalid)?,
+            "exponent" => self.exponent = value.parse().map_err(invalid)?,
+            "output_format" => self.output_format = value.parse().map_err(invalid)?,
+            "keyring_dir" => self.keyring_dir = expand_home(value),
+            "padding" => self.padding = match value {
+                "auto" => None,
+                padding => Some(padding.parse().map_err(invalid)?),
+            },
+            unknown => return Err(Error::InvalidArgs(format!("Error, unknown setting {} in {}", unknown, source))),
+        }
+        if let Some(index) = SETTINGS.iter().position(|(setting, _)| *setting == name) {
+            self.sources[index] = source;
+        }
+        Ok(())
+    }
+
+    /// Where the setting `name` comes from
+    pub fn source(&self, name: &str) -> Option<&Source> {
+        SETTINGS.iter().position(|(setting, _)| *setting == name).map(|index| &self.sources[index])
+    }
+
+    /// The effective settings as config file, with the source of each value as comment
+    pub fn show(&self) -> String {
+        let values = [
+            toml::Value::Integer(self.key_size.into()),
+            match self.exponent {
+                PublicExponent::Random => toml::Value::String("random".to_string()),
+                PublicExponent::Fixed(e) => i64::try_from(e).map(toml::Value::Integer).unwrap_or_else(|_| toml::Value::String(e.to_string())),
+            },
+            toml::Value::String(self.output_format.to_string()),
+            toml::Value::String(self.keyring_dir.display().to_string()),
+            toml::Value::String(self.padding.map_or("auto".to_string(), |padding| padding.to_string())),
+        ];
+        let mut shown = match &self.file {
+            Some(file) => format!("# config file: {}\n", file.display()),
+            None => format!("# no config file, the default is {}\n", default_config_file().display()),
+        };
+        shown += "# each setting can be overridden by its environment variable, e.g. RUST_RSA_KEY_SIZE for key_size\n";
+        for ((name, _), (value, source)) in SETTINGS.iter().zip(values.iter().zip(&self.sources)) {
+            shown += &format!("{} = {}  # {}\n", name, value, source);
+        }
+        shown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{Settings, Source};
+    use crate::formats::Encoding;
+    use crate::key_gen::PublicExponent;
+    use crate::padding::Padding;
+
+    #[test]
+    fn test_precedence() {
+        let mut settings = Settings::default();
+        let file = Path::new("config.toml");
+        settings.apply_file("key_size = 4096\nexponent = 65537\npadding = \"pkcs1\"\nkeyring_dir = \"/keys\"", file).unwrap();
+        settings.apply_env(|name| (name == "RUST_RSA_KEY_SIZE").then(|| "1024".to_string())).unwrap();
+
+        assert_eq!(settings.key_size, 1024);
+        assert_eq!(settings.source("key_size"), Some(&Source::Env("RUST_RSA_KEY_SIZE")));
+        assert_eq!(settings.exponent, PublicExponent::Fixed(65537));
+        assert_eq!(settings.source("exponent"), Some(&Source::File(file.to_path_buf())));
+        assert_eq!(settings.padding, Some(Padding::Pkcs1));
+        assert_eq!(settings.output_format, Encoding::Pem);
+        assert_eq!(settings.source("output_format"), Some(&Source::BuiltIn));
+        assert_eq!(settings.keyring_dir, Path::new("/keys"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let file = Path::new("config.toml");
+        assert!(Settings::default().apply_file("key_size = 1000", file).is_err());
+        assert!(Settings::default().apply_file("key_sise = 1024", file).is_err());
+        assert!(Settings::default().apply_file("padding = [\"oaep\"]", file).is_err());
+        assert!(Settings::default().apply_file("key_size = ", file).is_err());
+        assert!(Settings::default().apply_env(|_| Some("xml".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_show() {
+        let mut settings = Settings::default();
+        settings.apply_file("output_format = \"der\"\npadding = \"auto\"", Path::new("config.toml")).unwrap();
+        let shown = settings.show();
+        assert!(shown.starts_with("# config file: config.toml\n"));
+        assert!(shown.contains("key_size = 2048  # built-in\n"));
+        assert!(shown.contains("output_format = \"der\"  # config.toml"));
+        assert!(shown.contains("padding = \"auto\""));
+
+        // the shown settings can be used as config file
+        let mut reread = Settings::default();
+        reread.apply_file(&shown, Path::new("shown.toml")).unwrap();
+        assert_eq!(reread.output_format, Encoding::Der);
+        assert_eq!(reread.keyring_dir, settings.keyring_dir);
+    }
+}
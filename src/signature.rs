@@ -0,0 +1,288 @@
+//! Signature schemes with appendix, RFC 8017 section 8
+use std::fmt;
+use std::str::FromStr;
+
+use rand::RngCore;
+
+use crate::error::{Error, Result};
+use crate::formats::der::{self, DerReader};
+use crate::hash::{mgf1, HashAlgorithm};
+use crate::input_module::{i2osp, os2ip};
+use crate::key_gen::{RsaKey, RsaPrivateKey, RsaPublicKey};
+use crate::padding::xor_in_place;
+use crate::primitives;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureScheme {
+    /// RSASSA-PKCS1-v1_5, RFC 8017 section 8.2
+    Pkcs1(HashAlgorithm),
+    /// RSASSA-PSS with MGF1 using the same hash and a salt as long as the hash, RFC 8017 section 8.1
+    Pss(HashAlgorithm),
+}
+
+impl SignatureScheme {
+    pub fn hash(&self) -> HashAlgorithm {
+        match self {
+            SignatureScheme::Pkcs1(hash) | SignatureScheme::Pss(hash) => *hash,
+        }
+    }
+
+    /// AlgorithmIdentifier used in certificates and CMS, RFC 4055 section 3 for the PSS parameters
+    pub fn algorithm_identifier(&self) -> Vec<u8> {
+        match self {
+            SignatureScheme::Pkcs1(hash) => {
+                let oid = match hash {
+                    HashAlgorithm::Sha256 => der::OID_SHA256_WITH_RSA,
+                    HashAlgorithm::Sha384 => der::OID_SHA384_WITH_RSA,
+                    HashAlgorithm::Sha512 => der::OID_SHA512_WITH_RSA,
+                };
+                der::sequence(&[der::oid(oid), der::null()])
+            },
+            SignatureScheme::Pss(hash) => {
+                let mgf = der::sequence(&[der::oid(der::OID_MGF1), hash.algorithm_identifier()]);
+                let params = der::sequence(&[
+                    der::context(0, &hash.algorithm_identifier()),
+                    der::context(1, &mgf),
+                    der::context(2, &der::integer(&hash.output_len().into())),
+                ]);
+                der::sequence(&[der::oid(der::OID_RSASSA_PSS), params])
+            },
+        }
+    }
+
+    /// Parses the AlgorithmIdentifiers written by `algorithm_identifier`, PSS is only supported
+    /// with MGF1 using the same hash and a salt as long as the hash
+    pub fn from_algorithm_identifier(data: &[u8]) -> Result<Self> {
+        let unsupported = |what: &str| Error::Encoding(format!("unsupported signature algorithm: {}", what));
+        let mut outer = DerReader::new(data);
+        let mut algorithm = outer.read_sequence()?;
+        outer.finish()?;
+        let oid = algorithm.read_oid()?;
+        let hashes = [HashAlgorithm::Sha256, HashAlgorithm::Sha384, HashAlgorithm::Sha512];
+        let pkcs1_oids = [der::OID_SHA256_WITH_RSA, der::OID_SHA384_WITH_RSA, der::OID_SHA512_WITH_RSA];
+        if let Some(i) = pkcs1_oids.iter().position(|known| *known == oid.as_slice()) {
+            // NULL parameters are required, but some encoders omit them
+            if !algorithm.is_empty() {
+                algorithm.read_null()?;
+            }
+            algorithm.finish()?;
+            return Ok(SignatureScheme::Pkcs1(hashes[i]));
+        }
+        if oid != der::OID_RSASSA_PSS {
+            return Err(unsupported(&oid.iter().map(u64::to_string).collect::<Vec<String>>().join(".")));
+        }
+
+        let read_hash = |data: &[u8]| -> Result<HashAlgorithm> {
+            let mut outer = DerReader::new(data);
+            let mut hash = outer.read_sequence()?;
+            outer.finish()?;
+            let oid = hash.read_oid()?;
+            if !hash.is_empty() {
+                hash.read_null()?;
+            }
+            hash.finish()?;
+            hashes.into_iter().find(|h| h.oid() == oid.as_slice()).ok_or_else(|| unsupported("PSS hash"))
+        };
+        let mut params = algorithm.read_sequence()?;
+        algorithm.finish()?;
+        // the defaults are SHA-1 and a salt length of 20, which are not supported
+        let hash = read_hash(params.read_optional(0xa0)?.ok_or_else(|| unsupported("PSS with SHA-1"))?)?;
+        let mgf = params.read_optional(0xa1)?.ok_or_else(|| unsupported("PSS with SHA-1"))?;
+        let mut mgf_outer = DerReader::new(mgf);
+        let mut mgf = mgf_outer.read_sequence()?;
+        mgf_outer.finish()?;
+        if mgf.read_oid()? != der::OID_MGF1 || read_hash(mgf.read_raw()?)? != hash {
+            return Err(unsupported("PSS with a different mask generation function"));
+        }
+        mgf.finish()?;
+        let salt_len = params.read_optional(0xa2)?.map(|salt| DerReader::new(salt).read_integer()).transpose()?;
+        if salt_len != Some(hash.output_len().into()) {
+            return Err(unsupported("PSS with a salt length different from the hash length"));
+        }
+        if let Some(trailer) = params.read_optional(0xa3)? {
+            if DerReader::new(trailer).read_integer()? != 1 {
+                return Err(unsupported("PSS trailer field"));
+            }
+        }
+        params.finish()?;
+        Ok(SignatureScheme::Pss(hash))
+    }
+
+    /// Signs `message`, the signature is as long as the modulus
+    pub fn sign(&self, key: &RsaPrivateKey, message: &[u8]) -> Result<Vec<u8>> {
+        let k = key.size();
+        let mod_bits = key.modulus().significant_bits() as usize;
+        let em = match self {
+            SignatureScheme::Pkcs1(hash) => emsa_pkcs1_encode(*hash, message, k)?,
+            SignatureScheme::Pss(hash) => emsa_pss_encode(*hash, message, mod_bits - 1)?,
+        };
+        let s = primitives::rsasp1(key, &os2ip(&em))?;
+        i2osp(&s, k)
+    }
+
+    /// Verifies `signature` over `message`, all failures return the same error
+    pub fn verify(&self, key: &RsaPublicKey, message: &[u8], signature: &[u8]) -> Result<()> {
+        let invalid = || Error::Verification("invalid signature".to_string());
+        let k = key.size();
+        if signature.len() != k {
+            return Err(invalid());
+        }
+        let m = primitives::rsavp1(key, &os2ip(signature)).map_err(|_| invalid())?;
+        let valid = match self {
+            SignatureScheme::Pkcs1(hash) => {
+                let em = i2osp(&m, k).map_err(|_| invalid())?;
+                emsa_pkcs1_encode(*hash, message, k).is_ok_and(|expected| expected == em)
+            },
+            SignatureScheme::Pss(hash) => {
+                let em_bits = key.modulus().significant_bits() as usize - 1;
+                let em = i2osp(&m, em_bits.div_ceil(8)).map_err(|_| invalid())?;
+                emsa_pss_verify(*hash, message, &em, em_bits)
+            },
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(invalid()),
+        }
+    }
+}
+
+/// DER encoded DigestInfo of the hash of `message`
+pub fn digest_info(hash: HashAlgorithm, message: &[u8]) -> Vec<u8> {
+    der::sequence(&[hash.algorithm_identifier(), der::octet_string(&hash.digest(message))])
+}
+
+/// EMSA-PKCS1-v1_5, RFC 8017 section 9.2
+fn emsa_pkcs1_encode(hash: HashAlgorithm, message: &[u8], em_len: usize) -> Result<Vec<u8>> {
+    let t = digest_info(hash, message);
+    if em_len < t.len() + 11 {
+        return Err(Error::SizeLimit(format!("intended encoded message length {} is too short for {}", em_len, hash)));
+    }
+    let mut em = vec![0x00, 0x01];
+    em.resize(em_len - t.len() - 1, 0xff);
+    em.push(0x00);
+    em.extend_from_slice(&t);
+    Ok(em)
+}
+
+/// EMSA-PSS-ENCODE, RFC 8017 section 9.1.1
+fn emsa_pss_encode(hash: HashAlgorithm, message: &[u8], em_bits: usize) -> Result<Vec<u8>> {
+    let h_len = hash.output_len();
+    let s_len = h_len;
+    let em_len = em_bits.div_ceil(8);
+    if em_len < h_len + s_len + 2 {
+        return Err(Error::SizeLimit(format!("the key is too small for PSS with {}", hash)));
+    }
+
+    let mut salt = vec![0; s_len];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let h = pss_hash(hash, message, &salt);
+
+    let mut db = vec![0; em_len - s_len - h_len - 2];
+    db.push(0x01);
+    db.extend_from_slice(&salt);
+    xor_in_place(&mut db, &mgf1(hash, &h, em_len - h_len - 1));
+    db[0] &= 0xff >> (8 * em_len - em_bits);
+
+    let mut em = db;
+    em.extend_from_slice(&h);
+    em.push(0xbc);
+    Ok(em)
+}
+
+/// H = Hash(0x00 * 8 || mHash || salt)
+fn pss_hash(hash: HashAlgorithm, message: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut m_prime = vec![0; 8];
+    m_prime.extend_from_slice(&hash.digest(message));
+    m_prime.extend_from_slice(salt);
+    hash.digest(&m_prime)
+}
+
+/// EMSA-PSS-VERIFY, RFC 8017 section 9.1.2
+fn emsa_pss_verify(hash: HashAlgorithm, message: &[u8], em: &[u8], em_bits: usize) -> bool {
+    let h_len = hash.output_len();
+    let s_len = h_len;
+    let em_len = em_bits.div_ceil(8);
+    if em.len() != em_len || em_len < h_len + s_len + 2 || em[em_len - 1] != 0xbc {
+        return false;
+    }
+    let (masked_db, h) = em[..em_len - 1].split_at(em_len - h_len - 1);
+    // the leftmost 8 * emLen - emBits bits have to be zero
+    let zero_bits = 8 * em_len - em_bits;
+    if zero_bits > 0 && masked_db[0] >> (8 - zero_bits) != 0 {
+        return false;
+    }
+
+    let mut db = masked_db.to_vec();
+    xor_in_place(&mut db, &mgf1(hash, h, em_len - h_len - 1));
+    db[0] &= 0xff >> zero_bits;
+    let ps_len = em_len - h_len - s_len - 2;
+    if db[..ps_len].iter().any(|b| *b != 0) || db[ps_len] != 0x01 {
+        return false;
+    }
+    pss_hash(hash, message, &db[ps_len + 1..]) == h
+}
+
+impl fmt::Display for SignatureScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureScheme::Pkcs1(hash) => write!(f, "pkcs1-{}", hash),
+            SignatureScheme::Pss(hash) => write!(f, "pss-{}", hash),
+        }
+    }
+}
+
+impl FromStr for SignatureScheme {
+    type Err = Error;
+
+    /// Accepts `pkcs1` and `pss`, optionally followed by the hash, e.g. `pss-sha384`. The default hash is SHA-256.
+    fn from_str(s: &str) -> Result<Self> {
+        let lower = s.to_ascii_lowercase();
+        let (scheme, hash) = match lower.split_once('-') {
+            Some((scheme, hash)) => (scheme, hash.parse()?),
+            None => (lower.as_str(), HashAlgorithm::Sha256),
+        };
+        match scheme {
+            "pkcs1" => Ok(SignatureScheme::Pkcs1(hash)),
+            "pss" => Ok(SignatureScheme::Pss(hash)),
+            _ => Err(Error::InvalidArgs(format!("Invalid signature scheme: {}, has to be 'pkcs1' or 'pss'.", s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignatureScheme;
+    use crate::error::Error;
+    use crate::hash::HashAlgorithm;
+    use crate::key_gen::generate_key_pair;
+
+    #[test]
+    fn test_sign_verify() {
+        // 1023 bit moduli have an encoded message shorter than the modulus for PSS
+        let (sk, pk) = generate_key_pair(1024, 2);
+        for scheme in [SignatureScheme::Pkcs1(HashAlgorithm::Sha256), SignatureScheme::Pkcs1(HashAlgorithm::Sha512), SignatureScheme::Pss(HashAlgorithm::Sha256), SignatureScheme::Pss(HashAlgorithm::Sha384)] {
+            let mut signature = scheme.sign(&sk, b"message").unwrap();
+            assert!(scheme.verify(&pk, b"message", &signature).is_ok(), "{}", scheme);
+            assert!(matches!(scheme.verify(&pk, b"massage", &signature), Err(Error::Verification(_))));
+            signature[5] ^= 1;
+            assert!(scheme.verify(&pk, b"message", &signature).is_err());
+        }
+    }
+
+    #[test]
+    fn test_algorithm_identifier() {
+        for scheme in [SignatureScheme::Pkcs1(HashAlgorithm::Sha384), SignatureScheme::Pss(HashAlgorithm::Sha256), SignatureScheme::Pss(HashAlgorithm::Sha512)] {
+            assert_eq!(SignatureScheme::from_algorithm_identifier(&scheme.algorithm_identifier()).unwrap(), scheme);
+        }
+        // rsaEncryption is not a signature algorithm
+        let rsa = crate::formats::spki::rsa_algorithm_identifier();
+        assert!(SignatureScheme::from_algorithm_identifier(&rsa).is_err());
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("pss".parse::<SignatureScheme>().unwrap(), SignatureScheme::Pss(HashAlgorithm::Sha256));
+        assert_eq!("pkcs1-sha512".parse::<SignatureScheme>().unwrap(), SignatureScheme::Pkcs1(HashAlgorithm::Sha512));
+        assert!("dsa".parse::<SignatureScheme>().is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! Consistency checks for keys, to detect corrupted or tampered key files before they are used.
+use std::fmt;
+
+use rug::Integer;
+use rug::rand::RandState;
+
+use crate::error::{Error, Result};
+use crate::key_gen::{RsaKey, RsaPrivateKey, RsaPublicKey};
+use crate::prime_gen::is_prime;
+use crate::prime_gen::sieve_of_eratosthenes::Sieve;
+use crate::primitives;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckResult {
+    Passed,
+    Failed,
+}
+
+#[derive(Debug)]
+pub struct KeyCheck {
+    pub description: &'static str,
+    pub result: CheckResult,
+}
+
+/// Results of all checks that were run against a key
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub checks: Vec<KeyCheck>,
+}
+
+impl ValidationReport {
+    fn check(&mut self, description: &'static str, passed: bool) {
+        let result = if passed { CheckResult::Passed } else { CheckResult::Failed };
+        self.checks.push(KeyCheck { description, result });
+    }
+
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.result != CheckResult::Failed)
+    }
+
+    /// Returns an error naming the first failed check
+    pub fn into_result(self) -> Result<()> {
+        match self.checks.into_iter().find(|c| c.result == CheckResult::Failed) {
+            Some(check) => Err(Error::InvalidKey(check.description.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let status = match check.result {
+                CheckResult::Passed => " OK ",
+                CheckResult::Failed => "FAIL",
+            };
+            writeln!(f, "[{}] {}", status, check.description)?;
+        }
+        write!(f, "Result: {}", if self.passed() { "pass" } else { "fail" })
+    }
+}
+
+fn check_public_parts(report: &mut ValidationReport, n: &Integer, e: &Integer) {
+    let n_is_odd = n.is_odd() && *n > 1;
+    report.check("modulus is odd", n_is_odd);
+    // only run the prime test if the modulus is odd, since it rejects even numbers anyway
+    report.check("modulus is composite", n_is_odd && !is_prime(n, &Sieve::new(10000)));
+    report.check("public exponent is odd and in range 3 to n - 1", e.is_odd() && *e >= 3 && e < n);
+}
+
+impl RsaPublicKey {
+    pub fn validation_report(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        check_public_parts(&mut report, self.modulus(), self.public_exponent());
+        report
+    }
+
+    /// Checks that the modulus is odd and composite and that the public exponent is in range
+    pub fn validate(&self) -> Result<()> {
+        self.validation_report().into_result()
+    }
+}
+
+impl RsaPrivateKey {
+    pub fn validation_report(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let (n, e, d) = (self.modulus(), self.public_exponent(), self.private_exponent());
+        check_public_parts(&mut report, n, e);
+        report.check("private exponent is in range 2 to n - 1", *d > 1 && d < n);
+
+        // legacy keys don't store the primes, recovering them from n, e and d lets the same checks run
+        let crt = self.crt_params_or_recover();
+        if self.crt_params().is_none() {
+            report.check("p and q can be recovered from n, e and d", crt.is_ok());
+        }
+        if let Ok(crt) = crt {
+            let sieve = Sieve::new(10000);
+            let primes_match = crt.p > 1 && crt.q > 1 && Integer::from(&crt.p * &crt.q) == *n;
+            report.check("p and q are greater than 1 and p * q equals the modulus", primes_match);
+            // the other checks are meaningless for the wrong primes, and the prime test doesn't terminate for 1
+            let p_1 = Integer::from(&crt.p - 1);
+            let q_1 = Integer::from(&crt.q - 1);
+            report.check("p and q are prime", primes_match && is_prime(&crt.p, &sieve) && is_prime(&crt.q, &sieve));
+            let lambda = p_1.clone().lcm(&q_1);
+            report.check("d * e = 1 mod lambda(n)", primes_match && Integer::from(d * e) % &lambda == 1);
+            report.check("dp = d mod (p - 1)", primes_match && crt.dp == Integer::from(d % &p_1));
+            report.check("dq = d mod (q - 1)", primes_match && crt.dq == Integer::from(d % &q_1));
+            report.check("qinv * q = 1 mod p", primes_match && Integer::from(&crt.qinv * &crt.q) % &crt.p == 1);
+        }
+
+        report.check("pairwise consistency (encrypt then decrypt)", self.pairwise_consistent());
+        report
+    }
+
+    /// Checks the public parts like `RsaPublicKey::validate`, the CRT parameters, which are recovered
+    /// from n, e and d for legacy keys, and that decrypting an encrypted random value returns that value
+    pub fn validate(&self) -> Result<()> {
+        self.validation_report().into_result()
+    }
+
+    fn pairwise_consistent(&self) -> bool {
+        let n = self.modulus();
+        if *n <= 3 {
+            return false;
+        }
+        let mut rng = RandState::new();
+        rng.seed(&Integer::from(rand::random::<u64>()));
+        // random value in range 2 to n - 2
+        let m = Integer::from(n - 3).random_below(&mut rng) + 2;
+        primitives::rsaep(&self.to_public_key(), &m)
+            .and_then(|c| primitives::rsadp(self, &c))
+            .map(|decrypted| decrypted == m)
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+
+    use super::CheckResult;
+    use crate::error::Error;
+    use crate::key_gen::{generate_key_pair, RsaKey, RsaPrivateKey, RsaPublicKey};
+
+    #[test]
+    fn test_generated_keys_are_valid() {
+        let (sk, pk) = generate_key_pair(256, 2);
+        assert!(pk.validate().is_ok());
+        let report = sk.validation_report();
+        assert!(report.passed(), "{}", report);
+    }
+
+    #[test]
+    fn test_tampered_private_exponent() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let crt = sk.crt_params().cloned();
+        let d = Integer::from(sk.private_exponent() + 2);
+        let tampered = RsaPrivateKey::from_components(Integer::from(sk.modulus()), Integer::from(sk.public_exponent()), d, crt);
+        assert!(matches!(tampered.validate(), Err(Error::InvalidKey(_))));
+    }
+
+    #[test]
+    fn test_legacy_key_without_crt() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let legacy = RsaPrivateKey::from_components(Integer::from(sk.modulus()), Integer::from(sk.public_exponent()), Integer::from(sk.private_exponent()), None);
+        assert!(legacy.validate().is_ok());
+        // the recovered primes are checked like stored ones
+        assert!(legacy.validation_report().checks.iter().any(|check| check.description == "p and q are prime"));
+        let d = Integer::from(sk.private_exponent() + 2);
+        let tampered = RsaPrivateKey::from_components(Integer::from(sk.modulus()), Integer::from(sk.public_exponent()), d, None);
+        let report = tampered.validation_report();
+        assert!(report.checks.iter().any(|check| check.description.starts_with("p and q can be recovered") && check.result == CheckResult::Failed));
+        assert!(RsaPrivateKey::from_file("sk_22-06-06T20:51").unwrap().validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_public_key() {
+        assert!(RsaPublicKey::new(Integer::from(15), Integer::from(4)).validate().is_err());
+        assert!(RsaPublicKey::new(Integer::from(14), Integer::from(3)).validate().is_err());
+        // prime modulus
+        assert!(RsaPublicKey::new(Integer::from(65537), Integer::from(3)).validate().is_err());
+        assert!(RsaPublicKey::new(Integer::from(15), Integer::from(7)).validate().is_ok());
+    }
+
+    #[test]
+    fn test_inconsistent_crt() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let crt = sk.crt_params().unwrap();
+        let mut wrong = crt.clone();
+        wrong.qinv += 1;
+        let key = RsaPrivateKey::from_components(Integer::from(sk.modulus()), Integer::from(sk.public_exponent()), Integer::from(sk.private_exponent()), Some(wrong));
+        assert!(!key.validation_report().passed());
+    }
+
+    #[test]
+    fn test_prime_one() {
+        let (sk, _) = generate_key_pair(256, 2);
+        let n = Integer::from(sk.modulus());
+        let mut crt = sk.crt_params().unwrap().clone();
+        crt.p = Integer::from(1);
+        crt.q = n.clone();
+        let key = RsaPrivateKey::from_components(n, Integer::from(sk.public_exponent()), Integer::from(sk.private_exponent()), Some(crt));
+        let report = key.validation_report();
+        assert!(report.checks.iter().any(|check| check.description == "p and q are prime" && check.result == CheckResult::Failed));
+        assert!(matches!(key.validate(), Err(Error::InvalidKey(_))));
+    }
+}
@@ -0,0 +1,232 @@
+//! Self-signed and issued X.509 v3 certificates (RFC 5280) and PKCS#10 certification requests (RFC 2986)
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use rug::Integer;
+use rug::integer::Order;
+
+use crate::error::{Error, Result};
+use crate::formats::{der, spki};
+use crate::hash::HashAlgorithm;
+use crate::key_gen::{RsaPrivateKey, RsaPublicKey};
+use crate::signature::SignatureScheme;
+
+use super::{Name, SubjectAltName, OID_BASIC_CONSTRAINTS, OID_EXTENSION_REQUEST, OID_SUBJECT_ALT_NAME};
+
+/// Collects the fields of a certificate or certification request, which are then signed by
+/// [`CertificateBuilder::self_signed`], [`CertificateBuilder::issue`] or [`CertificateBuilder::request`].
+#[derive(Debug, Clone)]
+pub struct CertificateBuilder {
+    subject: Name,
+    serial: Integer,
+    not_before: DateTime<Utc>,
+    not_after: DateTime<Utc>,
+    subject_alt_names: Vec<SubjectAltName>,
+    ca: bool,
+    path_len: Option<u32>,
+    scheme: SignatureScheme,
+}
+
+impl CertificateBuilder {
+    /// Starts with a random serial, a validity of one year from now, no extensions
+    /// except basic constraints for an end entity and sha256WithRSAEncryption
+    pub fn new(subject: Name) -> Self {
+        let mut serial = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut serial);
+        // positive and always 16 bytes long
+        serial[0] = (serial[0] & 0x7f) | 0x40;
+        let now = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap_or_default();
+        CertificateBuilder {
+            subject,
+            serial: Integer::from_digits(&serial, Order::Msf),
+            not_before: now,
+            not_after: now + Duration::days(365),
+            subject_alt_names: vec![],
+            ca: false,
+            path_len: None,
+            scheme: SignatureScheme::Pkcs1(HashAlgorithm::Sha256),
+        }
+    }
+
+    pub fn serial(mut self, serial: Integer) -> Self {
+        self.serial = serial;
+        self
+    }
+
+    pub fn validity(mut self, not_before: DateTime<Utc>, not_after: DateTime<Utc>) -> Self {
+        self.not_before = not_before;
+        self.not_after = not_after;
+        self
+    }
+
+    pub fn subject_alt_names(mut self, names: Vec<SubjectAltName>) -> Self {
+        self.subject_alt_names = names;
+        self
+    }
+
+    /// Marks the certificate as a CA, optionally limiting the number of intermediate CAs below it
+    pub fn ca(mut self, path_len: Option<u32>) -> Self {
+        self.ca = true;
+        self.path_len = path_len;
+        self
+    }
+
+    pub fn signature_scheme(mut self, scheme: SignatureScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// DER encoded certificate signed by its own key, so the issuer is the subject
+    pub fn self_signed(&self, key: &RsaPrivateKey) -> Result<Vec<u8>> {
+        self.issue(&key.to_public_key(), &self.subject, key)
+    }
+
+    /// DER encoded certificate for `subject_key`, signed by `issuer_key`
+    pub fn issue(&self, subject_key: &RsaPublicKey, issuer: &Name, issuer_key: &RsaPrivateKey) -> Result<Vec<u8>> {
+        if self.serial <= 0 || self.serial.significant_digits::<u8>() > 20 {
+            return Err(Error::InvalidArgs("Error, the serial number has to be positive and at most 20 bytes long".to_string()));
+        }
+        if self.not_before >= self.not_after {
+            return Err(Error::InvalidArgs("Error, the certificate has to be valid after its start date".to_string()));
+        }
+        self.check_subject()?;
+
+        let algorithm = self.scheme.algorithm_identifier();
+        let tbs = der::sequence(&[
+            der::context(0, &der::integer(&Integer::from(2))),
+            der::integer(&self.serial),
+            algorithm.clone(),
+            issuer.to_der(),
+            der::sequence(&[der::time(&self.not_before), der::time(&self.not_after)]),
+            self.subject.to_der(),
+            spki::to_der(subject_key),
+            der::context(3, &der::sequence(&self.extensions())),
+        ]);
+        let signature = self.scheme.sign(issuer_key, &tbs)?;
+        Ok(der::sequence(&[tbs, algorithm, der::bit_string(&signature)]))
+    }
+
+    /// DER encoded PKCS#10 certification request, the extensions are in an extensionRequest attribute.
+    /// Serial and validity are chosen by the CA, so they are not part of the request.
+    pub fn request(&self, key: &RsaPrivateKey) -> Result<Vec<u8>> {
+        self.check_subject()?;
+        let extension_request = der::sequence(&[
+            der::oid(OID_EXTENSION_REQUEST),
+            der::set(&[der::sequence(&self.extensions())]),
+        ]);
+        let info = der::sequence(&[
+            der::integer(&Integer::from(0)),
+            self.subject.to_der(),
+            spki::to_der(key),
+            // [0] IMPLICIT SET OF Attribute
+            der::context(0, &extension_request),
+        ]);
+        let algorithm = self.scheme.algorithm_identifier();
+        let signature = self.scheme.sign(key, &info)?;
+        Ok(der::sequence(&[info, algorithm, der::bit_string(&signature)]))
+    }
+
+    fn check_subject(&self) -> Result<()> {
+        match self.subject.is_empty() && self.subject_alt_names.is_empty() {
+            true => Err(Error::InvalidArgs("Error, a subject name or subject alternative name is needed".to_string())),
+            false => Ok(()),
+        }
+    }
+
+    fn extensions(&self) -> Vec<Vec<u8>> {
+        let mut constraints = vec![];
+        if self.ca {
+            constraints.push(der::boolean(true));
+            if let Some(path_len) = self.path_len {
+                constraints.push(der::integer(&Integer::from(path_len)));
+            }
+        }
+        // has to be critical for CAs
+        let mut extensions = vec![extension(OID_BASIC_CONSTRAINTS, self.ca, &der::sequence(&constraints))];
+        if !self.subject_alt_names.is_empty() {
+            let names: Vec<Vec<u8>> = self.subject_alt_names.iter().map(SubjectAltName::to_der).collect();
+            // has to be critical if the subject is empty
+            extensions.push(extension(OID_SUBJECT_ALT_NAME, self.subject.is_empty(), &der::sequence(&names)));
+        }
+        extensions
+    }
+}
+
+/// Extension with `critical` only encoded if it is true, as it defaults to false
+fn extension(oid: &[u64], critical: bool, value: &[u8]) -> Vec<u8> {
+    let mut fields = vec![der::oid(oid)];
+    if critical {
+        fields.push(der::boolean(true));
+    }
+    fields.push(der::octet_string(value));
+    der::sequence(&fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration};
+    use rug::Integer;
+
+    use super::CertificateBuilder;
+    use crate::formats::{der::DerReader, spki};
+    use crate::hash::HashAlgorithm;
+    use crate::key_gen::{generate_key_pair, RsaKey};
+    use crate::signature::SignatureScheme;
+
+    /// Splits a signed structure into the signed data, algorithm identifier and signature
+    fn split_signed(data: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let mut outer = DerReader::new(data);
+        let mut signed = outer.read_sequence().unwrap();
+        outer.finish().unwrap();
+        let tbs = signed.read_raw().unwrap().to_vec();
+        let algorithm = signed.read_raw().unwrap().to_vec();
+        let signature = signed.read_bit_string().unwrap().to_vec();
+        signed.finish().unwrap();
+        (tbs, algorithm, signature)
+    }
+
+    #[test]
+    fn test_self_signed() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        for scheme in [SignatureScheme::Pkcs1(HashAlgorithm::Sha256), SignatureScheme::Pss(HashAlgorithm::Sha256)] {
+            let cert = CertificateBuilder::new("CN=test".parse().unwrap())
+                .subject_alt_names(vec!["localhost".parse().unwrap(), "127.0.0.1".parse().unwrap()])
+                .serial(Integer::from(4711))
+                .signature_scheme(scheme)
+                .self_signed(&sk)
+                .unwrap();
+            let (tbs, algorithm, signature) = split_signed(&cert);
+            assert_eq!(algorithm, scheme.algorithm_identifier());
+            assert!(scheme.verify(&pk, &tbs, &signature).is_ok());
+
+            let mut tbs = DerReader::new(&tbs).read_sequence().unwrap();
+            assert_eq!(tbs.read_tlv().unwrap(), (0xa0, &[0x02, 0x01, 0x02][..]));
+            assert_eq!(tbs.read_integer().unwrap(), 4711);
+            let _algorithm = tbs.read_raw().unwrap();
+            let issuer = tbs.read_raw().unwrap();
+            let _validity = tbs.read_raw().unwrap();
+            assert_eq!(tbs.read_raw().unwrap(), issuer);
+            assert_eq!(spki::from_der(tbs.read_raw().unwrap()).unwrap().serialize(), pk.serialize());
+        }
+    }
+
+    #[test]
+    fn test_request() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        let csr = CertificateBuilder::new("CN=test,O=Org".parse().unwrap()).ca(Some(0)).request(&sk).unwrap();
+        let (info, _, signature) = split_signed(&csr);
+        assert!(SignatureScheme::Pkcs1(HashAlgorithm::Sha256).verify(&pk, &info, &signature).is_ok());
+        let mut info = DerReader::new(&info).read_sequence().unwrap();
+        assert_eq!(info.read_integer().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let (sk, _) = generate_key_pair(1024, 2);
+        let builder = CertificateBuilder::new("CN=test".parse().unwrap());
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert!(builder.clone().validity(start, start - Duration::days(1)).self_signed(&sk).is_err());
+        assert!(builder.clone().serial(Integer::from(0)).self_signed(&sk).is_err());
+        assert!(builder.serial(Integer::from(1) << 160).self_signed(&sk).is_err());
+        assert!(CertificateBuilder::new(Default::default()).self_signed(&sk).is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! Parsing of X.509 certificates, RFC 5280 section 4.1
+use chrono::{DateTime, Utc};
+use rug::Integer;
+
+use crate::error::{Error, Result};
+use crate::formats::der::{self, DerReader};
+use crate::formats::{pem, spki};
+use crate::key_gen::RsaPublicKey;
+use crate::signature::SignatureScheme;
+
+use super::{Name, SubjectAltName, PEM_LABEL_CERTIFICATE};
+use super::{OID_AUTHORITY_KEY_IDENTIFIER, OID_BASIC_CONSTRAINTS, OID_EXTENDED_KEY_USAGE, OID_KEY_USAGE, OID_SUBJECT_ALT_NAME, OID_SUBJECT_KEY_IDENTIFIER};
+
+/// keyCertSign bit of the key usage extension
+pub const KEY_USAGE_KEY_CERT_SIGN: u16 = 0x8000 >> 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasicConstraints {
+    pub ca: bool,
+    pub path_len: Option<u32>,
+}
+
+/// A parsed certificate with an RSA subject public key.
+/// Issuer and subject are also kept DER encoded, as names are matched by their encoding.
+#[derive(Debug, Clone)]
+pub struct Certificate {
+    der: Vec<u8>,
+    tbs: Vec<u8>,
+    /// The error message if the algorithm is not supported, e.g. for old roots signed with SHA-1
+    scheme: std::result::Result<SignatureScheme, String>,
+    signature: Vec<u8>,
+    version: u32,
+    serial: Integer,
+    issuer: Name,
+    issuer_der: Vec<u8>,
+    subject: Name,
+    subject_der: Vec<u8>,
+    not_before: DateTime<Utc>,
+    not_after: DateTime<Utc>,
+    public_key: RsaPublicKey,
+    basic_constraints: Option<BasicConstraints>,
+    key_usage: Option<u16>,
+    subject_alt_names: Vec<SubjectAltName>,
+    unknown_critical: Vec<Vec<u64>>,
+}
+
+impl Certificate {
+    pub fn from_der(data: &[u8]) -> Result<Self> {
+        let mut outer = DerReader::new(data);
+        let mut cert = outer.read_sequence()?;
+        outer.finish()?;
+        let tbs_raw = cert.read_raw()?;
+        let algorithm = cert.read_raw()?;
+        let signature = cert.read_bit_string()?.to_vec();
+        cert.finish()?;
+        let scheme = SignatureScheme::from_algorithm_identifier(algorithm).map_err(|e| e.to_string());
+
+        let mut tbs = DerReader::new(tbs_raw).read_sequence()?;
+        let version = match tbs.read_optional(0xa0)? {
+            Some(version) => match DerReader::new(version).read_integer()?.to_u32() {
+                Some(v @ 0..=2) => v + 1,
+                _ => return Err(Error::Encoding("unknown certificate version".to_string())),
+            },
+            None => 1,
+        };
+        let serial = tbs.read_integer()?;
+        if tbs.read_raw()? != algorithm {
+            return Err(Error::Encoding("signature algorithm does not match the one in the signed data".to_string()));
+        }
+        let issuer_der = tbs.read_raw()?.to_vec();
+        let mut validity = tbs.read_sequence()?;
+        let not_before = validity.read_time()?;
+        let not_after = validity.read_time()?;
+        validity.finish()?;
+        let subject_der = tbs.read_raw()?.to_vec();
+        let public_key = spki::from_der(tbs.read_raw()?)?;
+        // issuerUniqueID and subjectUniqueID are not used
+        tbs.read_optional(0x81)?;
+        tbs.read_optional(0x82)?;
+
+        let mut certificate = Certificate {
+            der: data.to_vec(),
+            tbs: tbs_raw.to_vec(),
+            scheme,
+            signature,
+            version,
+            serial,
+            issuer: Name::from_der(&issuer_der)?,
+            issuer_der,
+            subject: Name::from_der(&subject_der)?,
+            subject_der,
+            not_before,
+            not_after,
+            public_key,
+            basic_constraints: None,
+            key_usage: None,
+            subject_alt_names: vec![],
+            unknown_critical: vec![],
+        };
+        if let Some(extensions) = tbs.read_optional(0xa3)? {
+            certificate.read_extensions(extensions)?;
+        }
+        tbs.finish()?;
+        Ok(certificate)
+    }
+
+    fn read_extensions(&mut self, data: &[u8]) -> Result<()> {
+        let mut outer = DerReader::new(data);
+        let mut extensions = outer.read_sequence()?;
+        outer.finish()?;
+        let mut seen = vec![];
+        while !extensions.is_empty() {
+            let mut extension = extensions.read_sequence()?;
+            let oid = extension.read_oid()?;
+            let critical = match extension.peek_tag() {
+                Some(der::TAG_BOOLEAN) => extension.read_boolean()?,
+                _ => false,
+            };
+            let value = extension.read_octet_string()?;
+            extension.finish()?;
+            if seen.contains(&oid) {
+                return Err(Error::Encoding("duplicate certificate extension".to_string()));
+            }
+
+            match oid.as_slice() {
+                OID_BASIC_CONSTRAINTS => self.basic_constraints = Some(read_basic_constraints(value)?),
+                OID_KEY_USAGE => self.key_usage = Some(read_key_usage(value)?),
+                OID_SUBJECT_ALT_NAME => self.subject_alt_names = SubjectAltName::from_der_list(value)?,
+                // key identifiers and extended key usage do not restrict chain building
+                OID_SUBJECT_KEY_IDENTIFIER | OID_AUTHORITY_KEY_IDENTIFIER | OID_EXTENDED_KEY_USAGE => {},
+                _ if critical => self.unknown_critical.push(oid.clone()),
+                _ => {},
+            }
+            seen.push(oid);
+        }
+        Ok(())
+    }
+
+    /// Parses a DER certificate or every CERTIFICATE block of a PEM file, other PEM blocks are skipped
+    pub fn parse_all(data: &[u8]) -> Result<Vec<Self>> {
+        if !pem::is_pem(data) {
+            return Ok(vec![Certificate::from_der(data)?]);
+        }
+        let certificates = pem::decode_all(&String::from_utf8_lossy(data))?
+            .into_iter()
+            .filter(|(label, _)| label == PEM_LABEL_CERTIFICATE)
+            .map(|(_, der)| Certificate::from_der(&der))
+            .collect::<Result<Vec<Self>>>()?;
+        match certificates.is_empty() {
+            true => Err(Error::Encoding("no certificate found".to_string())),
+            false => Ok(certificates),
+        }
+    }
+
+    /// Parses a single DER or PEM certificate
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut certificates = Certificate::parse_all(data)?;
+        match certificates.len() {
+            1 => Ok(certificates.remove(0)),
+            n => Err(Error::Encoding(format!("expected a single certificate, found {}", n))),
+        }
+    }
+
+    pub fn read(file_name: &str) -> Result<Self> {
+        Certificate::parse(&std::fs::read(file_name)?)
+    }
+
+    pub fn read_all(file_name: &str) -> Result<Vec<Self>> {
+        Certificate::parse_all(&std::fs::read(file_name)?)
+    }
+
+    /// Verifies the signature of the certificate with the public key of its issuer
+    pub fn verify_signature(&self, issuer_key: &RsaPublicKey) -> Result<()> {
+        match &self.scheme {
+            Ok(scheme) => scheme.verify(issuer_key, &self.tbs, &self.signature),
+            Err(e) => Err(Error::Verification(e.clone())),
+        }
+    }
+
+    pub fn is_valid_at(&self, time: DateTime<Utc>) -> bool {
+        self.not_before <= time && time <= self.not_after
+    }
+
+    pub fn is_self_issued(&self) -> bool {
+        self.issuer_der == self.subject_der
+    }
+
+    pub fn is_ca(&self) -> bool {
+        self.basic_constraints.is_some_and(|constraints| constraints.ca)
+    }
+
+    /// A missing key usage extension allows every usage
+    pub fn allows_cert_sign(&self) -> bool {
+        self.key_usage.is_none_or(|usage| usage & KEY_USAGE_KEY_CERT_SIGN != 0)
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn to_pem(&self) -> String {
+        pem::encode(PEM_LABEL_CERTIFICATE, &self.der)
+    }
+
+    /// None if the signature algorithm is not supported
+    pub fn signature_scheme(&self) -> Option<SignatureScheme> {
+        self.scheme.clone().ok()
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn serial(&self) -> &Integer {
+        &self.serial
+    }
+
+    pub fn issuer(&self) -> &Name {
+        &self.issuer
+    }
+
+    pub fn issuer_der(&self) -> &[u8] {
+        &self.issuer_der
+    }
+
+    pub fn subject(&self) -> &Name {
+        &self.subject
+    }
+
+    pub fn subject_der(&self) -> &[u8] {
+        &self.subject_der
+    }
+
+    pub fn not_before(&self) -> DateTime<Utc> {
+        self.not_before
+    }
+
+    pub fn not_after(&self) -> DateTime<Utc> {
+        self.not_after
+    }
+
+    pub fn public_key(&self) -> &RsaPublicKey {
+        &self.public_key
+    }
+
+    pub fn basic_constraints(&self) -> Option<BasicConstraints> {
+        self.basic_constraints
+    }
+
+    pub fn key_usage(&self) -> Option<u16> {
+        self.key_usage
+    }
+
+    pub fn subject_alt_names(&self) -> &[SubjectAltName] {
+        &self.subject_alt_names
+    }
+
+    /// Object identifiers of critical extensions that are not understood, such certificates must not be accepted
+    pub fn unknown_critical_extensions(&self) -> &[Vec<u64>] {
+        &self.unknown_critical
+    }
+}
+
+fn read_basic_constraints(data: &[u8]) -> Result<BasicConstraints> {
+    let mut outer = DerReader::new(data);
+    let mut constraints = outer.read_sequence()?;
+    outer.finish()?;
+    let ca = match constraints.peek_tag() {
+        Some(der::TAG_BOOLEAN) => constraints.read_boolean()?,
+        _ => false,
+    };
+    let path_len = match constraints.is_empty() {
+        true => None,
+        false => Some(constraints.read_integer()?.to_u32().ok_or_else(|| Error::Encoding("path length constraint too large".to_string()))?),
+    };
+    constraints.finish()?;
+    Ok(BasicConstraints { ca, path_len })
+}
+
+/// Bit `i` of the bit string is `0x8000 >> i`, only the first 9 usages are defined
+fn read_key_usage(data: &[u8]) -> Result<u16> {
+    let mut outer = DerReader::new(data);
+    let bits = outer.read(der::TAG_BIT_STRING)?;
+    outer.finish()?;
+    match bits {
+        [unused, bytes @ ..] if *unused < 8 && !bytes.is_empty() => {
+            Ok(u16::from_be_bytes([bytes[0], bytes.get(1).copied().unwrap_or(0)]))
+        },
+        _ => Err(Error::Encoding("invalid key usage".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration};
+
+    use super::Certificate;
+    use crate::error::Error;
+    use crate::formats::pem;
+    use crate::hash::HashAlgorithm;
+    use crate::key_gen::{generate_key_pair, RsaKey};
+    use crate::signature::SignatureScheme;
+    use crate::x509::{CertificateBuilder, SubjectAltName, PEM_LABEL_CERTIFICATE};
+
+    // generated with `openssl req -x509 -newkey rsa:1024 -nodes -subj "/CN=fixture/O=Example" -days 36500
+    //   -sigopt rsa_padding_mode:pss -sigopt rsa_pss_saltlen:digest
+    //   -addext "subjectAltName=DNS:fixture.example" -addext "keyUsage=critical,keyCertSign,cRLSign"`
+    const OPENSSL_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIICujCCAe+gAwIBAgIUH7eo0+ae9/aypVAiZ7b1U/C67gwwQQYJKoZIhvcNAQEK
+MDSgDzANBglghkgBZQMEAgEFAKEcMBoGCSqGSIb3DQEBCDANBglghkgBZQMEAgEF
+AKIDAgEgMCQxEDAOBgNVBAMMB2ZpeHR1cmUxEDAOBgNVBAoMB0V4YW1wbGUwIBcN
+MjYxMDE4MjE0NDEyWhgPMjEyNjA5MjQyMTQ0MTJaMCQxEDAOBgNVBAMMB2ZpeHR1
+cmUxEDAOBgNVBAoMB0V4YW1wbGUwgZ8wDQYJKoZIhvcNAQEBBQADgY0AMIGJAoGB
+AOPMblUF6HdZsHMQfM7OGQ9flhOGMuKqgK6owkcl26Q0yG2drZ6/UMp/98CJBiCx
+92bqhLALNvi5MzqGVLjxxgVQ8oKh66dngPf8tDdz0HLoSsLsbbkkJeduA5jcE/Sy
+U8MvHeT5hRCUDHYuJH9HQsZ4fmRKw2Cwdg7ielatWp5NAgMBAAGjfzB9MB0GA1Ud
+DgQWBBQJoUoLXX665BsOiD/l5IJRvP1VNzAfBgNVHSMEGDAWgBQJoUoLXX665BsO
+iD/l5IJRvP1VNzAPBgNVHRMBAf8EBTADAQH/MBoGA1UdEQQTMBGCD2ZpeHR1cmUu
+ZXhhbXBsZTAOBgNVHQ8BAf8EBAMCAQYwQQYJKoZIhvcNAQEKMDSgDzANBglghkgB
+ZQMEAgEFAKEcMBoGCSqGSIb3DQEBCDANBglghkgBZQMEAgEFAKIDAgEgA4GBAH+n
+Ohm5bCVbuDupetH9Q0vv/tOHSg9oJBYOY+TdHPLQtBzks2+ZdfNfLTp4sO3Eiz4b
+k3GnT5FDpW/zi7j/PvIr1OOoVEJorlSwa26Uq6lZSfGJCRtnqLfQDeaMmNNeIM1k
++yoySlsH2m1JwXmkf6IVUuRmM9FF42F72tXEhUfe
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_parse_built() {
+        let (sk, pk) = generate_key_pair(1024, 2);
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let der = CertificateBuilder::new("CN=test,O=Org".parse().unwrap())
+            .validity(start, start + Duration::days(10))
+            .subject_alt_names(vec!["localhost".parse().unwrap()])
+            .ca(Some(2))
+            .signature_scheme(SignatureScheme::Pss(HashAlgorithm::Sha256))
+            .self_signed(&sk)
+            .unwrap();
+        let pem = pem::encode(PEM_LABEL_CERTIFICATE, &der);
+        for cert in [Certificate::parse(&der).unwrap(), Certificate::parse(pem.as_bytes()).unwrap()] {
+            assert_eq!(cert.version(), 3);
+            assert_eq!(cert.subject().to_string(), "CN=test, O=Org");
+            assert!(cert.is_self_issued());
+            assert_eq!(cert.not_before(), start);
+            assert!(cert.is_valid_at(start + Duration::days(10)));
+            assert!(!cert.is_valid_at(start + Duration::days(11)));
+            assert_eq!(cert.public_key().serialize(), pk.serialize());
+            assert!(cert.is_ca());
+            assert_eq!(cert.basic_constraints().unwrap().path_len, Some(2));
+            assert_eq!(cert.subject_alt_names(), &[SubjectAltName::Dns("localhost".to_string())]);
+            assert!(cert.verify_signature(&pk).is_ok());
+            assert!(cert.verify_signature(&generate_key_pair(1024, 2).1).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_openssl() {
+        let cert = Certificate::parse(OPENSSL_CERT.as_bytes()).unwrap();
+        assert_eq!(cert.subject().to_string(), "CN=fixture, O=Example");
+        assert_eq!(cert.signature_scheme(), Some(SignatureScheme::Pss(HashAlgorithm::Sha256)));
+        assert_eq!(cert.subject_alt_names(), &[SubjectAltName::Dns("fixture.example".to_string())]);
+        assert!(cert.allows_cert_sign());
+        assert!(cert.unknown_critical_extensions().is_empty());
+        assert!(cert.verify_signature(cert.public_key()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let mut der = pem::decode(OPENSSL_CERT).unwrap().1;
+        assert!(Certificate::parse(&der[..der.len() - 1]).is_err());
+        let last = der.len() - 1;
+        der[last] ^= 1;
+        let cert = Certificate::parse(&der).unwrap();
+        assert!(cert.verify_signature(cert.public_key()).is_err());
+
+        // certificates signed with unsupported algorithms can be loaded, but not verified
+        let (sk, _) = generate_key_pair(1024, 2);
+        let der = CertificateBuilder::new("CN=sha1".parse().unwrap()).self_signed(&sk).unwrap();
+        let sha256_with_rsa = crate::formats::der::oid(crate::formats::der::OID_SHA256_WITH_RSA);
+        let mut sha1_with_rsa = sha256_with_rsa.clone();
+        *sha1_with_rsa.last_mut().unwrap() = 5;
+        let mut replaced = vec![];
+        let mut rest = &der[..];
+        while let Some(i) = rest.windows(sha256_with_rsa.len()).position(|w| w == sha256_with_rsa) {
+            replaced.extend_from_slice(&rest[..i]);
+            replaced.extend_from_slice(&sha1_with_rsa);
+            rest = &rest[i + sha256_with_rsa.len()..];
+        }
+        replaced.extend_from_slice(rest);
+        let cert = Certificate::parse(&replaced).unwrap();
+        assert_eq!(cert.signature_scheme(), None);
+        assert!(matches!(cert.verify_signature(cert.public_key()), Err(Error::Verification(_))));
+
+        assert!(Certificate::parse(b"-----BEGIN PUBLIC KEY-----\nMAA=\n-----END PUBLIC KEY-----\n").is_err());
+    }
+}
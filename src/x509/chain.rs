@@ -0,0 +1,194 @@
+//! Verification of certificate chains against trust anchors, a subset of RFC 5280 section 6
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::error::{Error, Result};
+
+use super::Certificate;
+
+/// Longest accepted path, including end entity and trust anchor
+const MAX_PATH_LEN: usize = 10;
+
+/// Trusted certificates, usually self-signed roots
+#[derive(Debug, Clone, Default)]
+pub struct TrustStore {
+    anchors: Vec<Certificate>,
+}
+
+impl TrustStore {
+    pub fn new(anchors: Vec<Certificate>) -> Self {
+        TrustStore { anchors }
+    }
+
+    /// Loads the certificates of every file in `dir`, files without certificates are skipped
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let mut anchors = vec![];
+        let mut entries = std::fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries.into_iter().filter(|entry| entry.path().is_file()) {
+            if let Ok(certificates) = Certificate::parse_all(&std::fs::read(entry.path())?) {
+                anchors.extend(certificates);
+            }
+        }
+        Ok(TrustStore { anchors })
+    }
+
+    pub fn anchors(&self) -> &[Certificate] {
+        &self.anchors
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchors.is_empty()
+    }
+
+    fn is_anchor(&self, certificate: &Certificate) -> bool {
+        self.anchors.iter().any(|anchor| anchor.to_der() == certificate.to_der())
+    }
+
+    /// Builds a path from the end entity certificate `chain[0]` to a trust anchor, using the
+    /// other certificates of `chain` as intermediates. Every certificate on the path has to be
+    /// valid at `time` and every issuer has to be a CA allowed to sign certificates, within the
+    /// path length constraints. Returns the path, ending with the trust anchor.
+    pub fn verify<'a>(&'a self, chain: &'a [Certificate], time: DateTime<Utc>) -> Result<Vec<&'a Certificate>> {
+        let leaf = chain.first().ok_or_else(|| Error::Verification("empty certificate chain".to_string()))?;
+        let mut path = vec![leaf];
+        loop {
+            let current = path[path.len() - 1];
+            check_certificate(current, time)?;
+            if self.is_anchor(current) {
+                return Ok(path);
+            }
+            if path.len() >= MAX_PATH_LEN {
+                return Err(Error::Verification(format!("no trust anchor within {} certificates", MAX_PATH_LEN)));
+            }
+
+            let mut candidates = self.anchors.iter()
+                .chain(&chain[1..])
+                .filter(|c| c.subject_der() == current.issuer_der() && !path.iter().any(|p| p.to_der() == c.to_der()))
+                .peekable();
+            if candidates.peek().is_none() {
+                return Err(Error::Verification(format!("no trusted issuer '{}' found for '{}'", current.issuer(), current.subject())));
+            }
+            let issuer = candidates.find(|c| current.verify_signature(c.public_key()).is_ok())
+                .ok_or_else(|| Error::Verification(format!("signature of '{}' does not match the key of its issuer", current.subject())))?;
+            check_issuer(issuer, path.len() - 1, self.is_anchor(issuer))?;
+            path.push(issuer);
+        }
+    }
+}
+
+fn check_certificate(certificate: &Certificate, time: DateTime<Utc>) -> Result<()> {
+    if !certificate.is_valid_at(time) {
+        return Err(Error::Verification(format!(
+            "'{}' is only valid from {} to {}", certificate.subject(), certificate.not_before(), certificate.not_after()
+        )));
+    }
+    if let Some(oid) = certificate.unknown_critical_extensions().first() {
+        let oid = oid.iter().map(u64::to_string).collect::<Vec<String>>().join(".");
+        return Err(Error::Verification(format!("'{}' has the unsupported critical extension {}", certificate.subject(), oid)));
+    }
+    Ok(())
+}
+
+/// `below` is the number of intermediate CAs between the issuer and the end entity.
+/// Trust anchors without basic constraints are accepted, as old v1 roots do not have them.
+fn check_issuer(issuer: &Certificate, below: usize, is_anchor: bool) -> Result<()> {
+    match issuer.basic_constraints() {
+        Some(constraints) if constraints.ca => {
+            if constraints.path_len.is_some_and(|path_len| below > path_len as usize) {
+                return Err(Error::Verification(format!("path length constraint of '{}' exceeded", issuer.subject())));
+            }
+        },
+        None if is_anchor => {},
+        _ => return Err(Error::Verification(format!("'{}' is not a CA", issuer.subject()))),
+    }
+    match issuer.allows_cert_sign() {
+        true => Ok(()),
+        false => Err(Error::Verification(format!("key usage of '{}' does not allow signing certificates", issuer.subject()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Utc};
+
+    use super::TrustStore;
+    use crate::error::Error;
+    use crate::key_gen::{generate_key_pair, RsaPrivateKey};
+    use crate::x509::{Certificate, CertificateBuilder, Name};
+
+    struct Issued {
+        certificate: Certificate,
+        name: Name,
+        key: RsaPrivateKey,
+    }
+
+    fn issue(subject: &str, ca: Option<Option<u32>>, issuer: Option<&Issued>) -> Issued {
+        let (key, public_key) = generate_key_pair(1024, 2);
+        let name: Name = subject.parse().unwrap();
+        let mut builder = CertificateBuilder::new(name.clone());
+        if let Some(path_len) = ca {
+            builder = builder.ca(path_len);
+        }
+        let der = match issuer {
+            Some(issuer) => builder.issue(&public_key, &issuer.name, &issuer.key).unwrap(),
+            None => builder.self_signed(&key).unwrap(),
+        };
+        Issued { certificate: Certificate::from_der(&der).unwrap(), name, key }
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let root = issue("CN=root", Some(None), None);
+        let intermediate = issue("CN=intermediate", Some(Some(0)), Some(&root));
+        let leaf = issue("CN=leaf", None, Some(&intermediate));
+        let store = TrustStore::new(vec![root.certificate.clone()]);
+        let now = Utc::now();
+
+        let chain = vec![leaf.certificate.clone(), intermediate.certificate.clone()];
+        let path = store.verify(&chain, now).unwrap();
+        let subjects: Vec<String> = path.iter().map(|c| c.subject().to_string()).collect();
+        assert_eq!(subjects, ["CN=leaf", "CN=intermediate", "CN=root"]);
+        // the root may be part of the chain
+        let with_root = vec![leaf.certificate.clone(), intermediate.certificate.clone(), root.certificate.clone()];
+        assert_eq!(store.verify(&with_root, now).unwrap().len(), 3);
+
+        assert!(matches!(store.verify(&chain[..1], now), Err(Error::Verification(_))));
+        assert!(store.verify(&chain, now + Duration::days(400)).is_err());
+        assert!(TrustStore::new(vec![]).verify(&chain, now).is_err());
+        // a self-signed certificate is trusted if it is a trust anchor itself
+        assert_eq!(store.verify(std::slice::from_ref(&root.certificate), now).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_verify_constraints() {
+        let root = issue("CN=root", Some(None), None);
+        let store = TrustStore::new(vec![root.certificate.clone()]);
+        // certificates issued below start to be valid later
+        let now = Utc::now() + Duration::hours(1);
+
+        // an end entity can not issue certificates
+        let not_ca = issue("CN=not ca", None, Some(&root));
+        let leaf = issue("CN=leaf", None, Some(&not_ca));
+        assert!(store.verify(&[leaf.certificate, not_ca.certificate], now).unwrap_err().to_string().contains("is not a CA"));
+
+        // path length 0 only allows end entities below
+        let first = issue("CN=first", Some(Some(0)), Some(&root));
+        let second = issue("CN=second", Some(None), Some(&first));
+        let leaf = issue("CN=leaf", None, Some(&second));
+        let chain = [leaf.certificate, second.certificate, first.certificate];
+        assert!(store.verify(&chain, now).unwrap_err().to_string().contains("path length"));
+
+        // same name, but signed by a different key
+        let fake_root = issue("CN=root", Some(None), None);
+        let leaf = issue("CN=leaf", None, Some(&fake_root));
+        assert!(store.verify(&[leaf.certificate], now).unwrap_err().to_string().contains("signature"));
+
+        let expired = CertificateBuilder::new("CN=old".parse().unwrap())
+            .validity(DateTime::from_timestamp(1_000_000_000, 0).unwrap(), DateTime::from_timestamp(1_100_000_000, 0).unwrap())
+            .issue(&generate_key_pair(1024, 2).1, &root.name, &root.key)
+            .unwrap();
+        assert!(store.verify(&[Certificate::from_der(&expired).unwrap()], now).unwrap_err().to_string().contains("only valid"));
+    }
+}
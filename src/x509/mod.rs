@@ -0,0 +1,22 @@
+//! Minimal X.509 support for RSA keys
+mod builder;
+mod certificate;
+mod chain;
+mod name;
+
+pub use builder::CertificateBuilder;
+pub use certificate::{BasicConstraints, Certificate, KEY_USAGE_KEY_CERT_SIGN};
+pub use chain::TrustStore;
+pub use name::{AttributeType, Name, SubjectAltName};
+
+pub const OID_SUBJECT_KEY_IDENTIFIER: &[u64] = &[2, 5, 29, 14];
+pub const OID_KEY_USAGE: &[u64] = &[2, 5, 29, 15];
+pub const OID_SUBJECT_ALT_NAME: &[u64] = &[2, 5, 29, 17];
+pub const OID_BASIC_CONSTRAINTS: &[u64] = &[2, 5, 29, 19];
+pub const OID_AUTHORITY_KEY_IDENTIFIER: &[u64] = &[2, 5, 29, 35];
+pub const OID_EXTENDED_KEY_USAGE: &[u64] = &[2, 5, 29, 37];
+/// PKCS#9 extensionRequest attribute of certification requests
+pub const OID_EXTENSION_REQUEST: &[u64] = &[1, 2, 840, 113549, 1, 9, 14];
+
+pub const PEM_LABEL_CERTIFICATE: &str = "CERTIFICATE";
+pub const PEM_LABEL_REQUEST: &str = "CERTIFICATE REQUEST";
@@ -0,0 +1,301 @@
+//! Distinguished names and subject alternative names, RFC 5280 sections 4.1.2.4 and 4.2.1.6
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::formats::der::{self, DerReader};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeType {
+    CommonName,
+    Country,
+    State,
+    Locality,
+    Organization,
+    OrganizationalUnit,
+    Email,
+    /// Any other attribute, shown with its dotted object identifier
+    Other(Vec<u64>),
+}
+
+const ATTRIBUTES: [(AttributeType, &str, &[u64]); 7] = [
+    (AttributeType::CommonName, "CN", &[2, 5, 4, 3]),
+    (AttributeType::Country, "C", &[2, 5, 4, 6]),
+    (AttributeType::State, "ST", &[2, 5, 4, 8]),
+    (AttributeType::Locality, "L", &[2, 5, 4, 7]),
+    (AttributeType::Organization, "O", &[2, 5, 4, 10]),
+    (AttributeType::OrganizationalUnit, "OU", &[2, 5, 4, 11]),
+    (AttributeType::Email, "emailAddress", &[1, 2, 840, 113549, 1, 9, 1]),
+];
+
+impl AttributeType {
+    pub fn oid(&self) -> &[u64] {
+        match self {
+            AttributeType::Other(oid) => oid,
+            known => ATTRIBUTES.iter().find(|(t, _, _)| t == known).map(|(_, _, oid)| *oid).unwrap_or_default(),
+        }
+    }
+
+    pub fn from_oid(oid: Vec<u64>) -> Self {
+        match ATTRIBUTES.iter().find(|(_, _, known)| *known == oid.as_slice()) {
+            Some((t, _, _)) => t.clone(),
+            None => AttributeType::Other(oid),
+        }
+    }
+
+    /// Encodes a value with the string type X.520 and PKIX expect for the attribute
+    fn encode_value(&self, value: &str) -> Vec<u8> {
+        match self {
+            AttributeType::Country => der::tlv(der::TAG_PRINTABLE_STRING, value.as_bytes()),
+            AttributeType::Email => der::tlv(der::TAG_IA5_STRING, value.as_bytes()),
+            _ => der::tlv(der::TAG_UTF8_STRING, value.as_bytes()),
+        }
+    }
+}
+
+impl fmt::Display for AttributeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match ATTRIBUTES.iter().find(|(t, _, _)| t == self) {
+            Some((_, short, _)) => write!(f, "{}", short),
+            None => write!(f, "{}", self.oid().iter().map(u64::to_string).collect::<Vec<String>>().join(".")),
+        }
+    }
+}
+
+impl FromStr for AttributeType {
+    type Err = Error;
+
+    /// Accepts the short names like `CN`, case insensitive, or a dotted object identifier
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some((t, _, _)) = ATTRIBUTES.iter().find(|(_, short, _)| short.eq_ignore_ascii_case(s)) {
+            return Ok(t.clone());
+        }
+        match s.split('.').map(str::parse).collect::<std::result::Result<Vec<u64>, _>>() {
+            Ok(oid) if oid.len() >= 2 => Ok(AttributeType::from_oid(oid)),
+            _ => Err(Error::InvalidArgs(format!("Invalid name attribute: {}, has to be one of CN, C, ST, L, O, OU, emailAddress or an object identifier.", s))),
+        }
+    }
+}
+
+/// A distinguished name with one attribute per relative distinguished name, in encoding order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Name {
+    attributes: Vec<(AttributeType, String)>,
+}
+
+impl Name {
+    pub fn new() -> Self {
+        Name::default()
+    }
+
+    pub fn push(&mut self, attribute: AttributeType, value: &str) {
+        self.attributes.push((attribute, value.to_string()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    pub fn attributes(&self) -> &[(AttributeType, String)] {
+        &self.attributes
+    }
+
+    pub fn common_name(&self) -> Option<&str> {
+        self.attributes.iter().find(|(t, _)| *t == AttributeType::CommonName).map(|(_, value)| value.as_str())
+    }
+
+    /// Parses a Name, attributes of multi valued RDNs are added one after another
+    pub fn from_der(data: &[u8]) -> Result<Self> {
+        let mut outer = DerReader::new(data);
+        let mut rdns = outer.read_sequence()?;
+        outer.finish()?;
+        let mut name = Name::new();
+        while !rdns.is_empty() {
+            let mut rdn = DerReader::new(rdns.read(der::TAG_SET)?);
+            while !rdn.is_empty() {
+                let mut attribute = rdn.read_sequence()?;
+                let oid = attribute.read_oid()?;
+                let value = attribute.read_string()?;
+                attribute.finish()?;
+                name.push(AttributeType::from_oid(oid), &value);
+            }
+        }
+        Ok(name)
+    }
+
+    pub fn to_der(&self) -> Vec<u8> {
+        let rdns: Vec<Vec<u8>> = self.attributes.iter()
+            .map(|(t, value)| der::set(&[der::sequence(&[der::oid(t.oid()), t.encode_value(value)])]))
+            .collect();
+        der::sequence(&rdns)
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.attributes.iter().map(|(t, value)| format!("{}={}", t, value)).collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+impl FromStr for Name {
+    type Err = Error;
+
+    /// Parses `CN=example.com,O=Example` or the OpenSSL style `/CN=example.com/O=Example`.
+    /// The attributes are encoded in the given order, separators inside values are not supported.
+    fn from_str(s: &str) -> Result<Self> {
+        let separator = if s.starts_with('/') { '/' } else { ',' };
+        let mut name = Name::new();
+        for part in s.split(separator).map(str::trim).filter(|part| !part.is_empty()) {
+            let (attribute, value) = part.split_once('=')
+                .ok_or_else(|| Error::InvalidArgs(format!("Invalid name component: {}, has to be ATTRIBUTE=value.", part)))?;
+            let attribute: AttributeType = attribute.trim().parse()?;
+            let value = value.trim();
+            let valid = match attribute {
+                AttributeType::Country => value.len() == 2 && value.chars().all(|c| c.is_ascii_alphabetic()),
+                AttributeType::Email => !value.is_empty() && value.is_ascii(),
+                _ => !value.is_empty(),
+            };
+            if !valid {
+                return Err(Error::InvalidArgs(format!("Invalid value for {}: '{}'", attribute, value)));
+            }
+            name.push(attribute, value);
+        }
+        Ok(name)
+    }
+}
+
+/// GeneralName entries of the subject alternative name extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubjectAltName {
+    Dns(String),
+    Ip(IpAddr),
+    Email(String),
+    Uri(String),
+}
+
+impl SubjectAltName {
+    /// Parses GeneralNames, skipping the types that are not supported
+    pub fn from_der_list(data: &[u8]) -> Result<Vec<Self>> {
+        let mut outer = DerReader::new(data);
+        let mut names = outer.read_sequence()?;
+        outer.finish()?;
+        let mut result = vec![];
+        while !names.is_empty() {
+            let (tag, content) = names.read_tlv()?;
+            let text = || String::from_utf8(content.to_vec()).map_err(|_| Error::Encoding("invalid subject alternative name".to_string()));
+            match tag {
+                0x81 => result.push(SubjectAltName::Email(text()?)),
+                0x82 => result.push(SubjectAltName::Dns(text()?)),
+                0x86 => result.push(SubjectAltName::Uri(text()?)),
+                0x87 => match content.len() {
+                    4 => result.push(SubjectAltName::Ip(IpAddr::from(<[u8; 4]>::try_from(content).unwrap_or_default()))),
+                    16 => result.push(SubjectAltName::Ip(IpAddr::from(<[u8; 16]>::try_from(content).unwrap_or_default()))),
+                    _ => return Err(Error::Encoding("invalid IP address length".to_string())),
+                },
+                _ => {},
+            }
+        }
+        Ok(result)
+    }
+
+    /// GeneralName with IMPLICIT context specific tag
+    pub fn to_der(&self) -> Vec<u8> {
+        match self {
+            SubjectAltName::Email(email) => der::tlv(0x81, email.as_bytes()),
+            SubjectAltName::Dns(dns) => der::tlv(0x82, dns.as_bytes()),
+            SubjectAltName::Uri(uri) => der::tlv(0x86, uri.as_bytes()),
+            SubjectAltName::Ip(IpAddr::V4(ip)) => der::tlv(0x87, &ip.octets()),
+            SubjectAltName::Ip(IpAddr::V6(ip)) => der::tlv(0x87, &ip.octets()),
+        }
+    }
+}
+
+impl fmt::Display for SubjectAltName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubjectAltName::Dns(dns) => write!(f, "DNS:{}", dns),
+            SubjectAltName::Ip(ip) => write!(f, "IP:{}", ip),
+            SubjectAltName::Email(email) => write!(f, "email:{}", email),
+            SubjectAltName::Uri(uri) => write!(f, "URI:{}", uri),
+        }
+    }
+}
+
+impl FromStr for SubjectAltName {
+    type Err = Error;
+
+    /// Accepts `DNS:`, `IP:`, `email:` and `URI:` prefixes like OpenSSL. Without a prefix,
+    /// IP addresses are detected and everything else is a DNS name.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidArgs(format!("Invalid subject alternative name: {}", s));
+        let (kind, value) = match s.split_once(':') {
+            Some((kind, value)) if ["dns", "ip", "email", "uri"].contains(&kind.to_ascii_lowercase().as_str()) => (kind.to_ascii_lowercase(), value),
+            _ if s.parse::<IpAddr>().is_ok() => ("ip".to_string(), s),
+            _ => ("dns".to_string(), s),
+        };
+        // DNS names, email addresses and URIs are IA5Strings
+        if value.is_empty() || (kind != "ip" && !value.is_ascii()) {
+            return Err(invalid());
+        }
+        match kind.as_str() {
+            "ip" => value.parse().map(SubjectAltName::Ip).map_err(|_| invalid()),
+            "email" => Ok(SubjectAltName::Email(value.to_string())),
+            "uri" => Ok(SubjectAltName::Uri(value.to_string())),
+            _ => Ok(SubjectAltName::Dns(value.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AttributeType, Name, SubjectAltName};
+
+    #[test]
+    fn test_name_from_str() {
+        let name: Name = "CN=example.com, O=Example Org,C=DE".parse().unwrap();
+        assert_eq!(name.common_name(), Some("example.com"));
+        assert_eq!(name.to_string(), "CN=example.com, O=Example Org, C=DE");
+        assert_eq!("/CN=example.com/O=Example Org/C=DE".parse::<Name>().unwrap(), name);
+        assert_eq!("2.5.4.5=42".parse::<Name>().unwrap().attributes()[0].0, AttributeType::Other(vec![2, 5, 4, 5]));
+        assert!("C=Germany".parse::<Name>().is_err());
+        assert!("XY=1".parse::<Name>().is_err());
+        assert!("CN".parse::<Name>().is_err());
+        assert!("".parse::<Name>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_name_from_der() {
+        let name: Name = "CN=example.com,O=Org,C=DE,emailAddress=a@b.c,2.5.4.5=1".parse().unwrap();
+        assert_eq!(Name::from_der(&name.to_der()).unwrap(), name);
+        assert!(Name::from_der(&[0x30, 0x02, 0x31, 0x01]).is_err());
+    }
+
+    #[test]
+    fn test_name_to_der() {
+        let name: Name = "C=DE,CN=a".parse().unwrap();
+        // SEQUENCE { SET { SEQUENCE { countryName, PrintableString } }, SET { SEQUENCE { commonName, UTF8String } } }
+        assert_eq!(name.to_der(), vec![
+            0x30, 0x19,
+            0x31, 0x0b, 0x30, 0x09, 0x06, 0x03, 0x55, 0x04, 0x06, 0x13, 0x02, b'D', b'E',
+            0x31, 0x0a, 0x30, 0x08, 0x06, 0x03, 0x55, 0x04, 0x03, 0x0c, 0x01, b'a',
+        ]);
+    }
+
+    #[test]
+    fn test_subject_alt_name() {
+        assert_eq!("example.com".parse::<SubjectAltName>().unwrap(), SubjectAltName::Dns("example.com".to_string()));
+        assert_eq!("127.0.0.1".parse::<SubjectAltName>().unwrap().to_der(), vec![0x87, 0x04, 127, 0, 0, 1]);
+        assert_eq!("IP:::1".parse::<SubjectAltName>().unwrap().to_string(), "IP:::1");
+        assert_eq!("email:a@b.c".parse::<SubjectAltName>().unwrap().to_der(), vec![0x81, 0x05, b'a', b'@', b'b', b'.', b'c']);
+        assert!("IP:localhost".parse::<SubjectAltName>().is_err());
+        assert!("DNS:".parse::<SubjectAltName>().is_err());
+
+        let names: Vec<SubjectAltName> = ["a.b", "::1", "10.0.0.1", "email:x@y.z", "URI:https://a.b/"].iter().map(|s| s.parse().unwrap()).collect();
+        let mut encoded: Vec<Vec<u8>> = names.iter().map(SubjectAltName::to_der).collect();
+        // otherName is skipped
+        encoded.push(vec![0xa0, 0x00]);
+        assert_eq!(SubjectAltName::from_der_list(&crate::formats::der::sequence(&encoded)).unwrap(), names);
+    }
+}